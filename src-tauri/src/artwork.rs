@@ -0,0 +1,219 @@
+// Artwork cache module for PodPico
+// Stores podcast and episode cover images locally, deduplicated by content, with thumbnails
+
+use crate::error::PodPicoError;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Longest side of generated thumbnails, in pixels
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+/// Artwork larger than this is not cached
+const MAX_ARTWORK_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Local copies of one artwork image
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedArtwork {
+    pub image_path: String,
+    pub thumbnail_path: String,
+}
+
+pub struct ArtworkCache {
+    cache_directory: PathBuf,
+    client: reqwest::Client,
+}
+
+impl ArtworkCache {
+    pub fn new(cache_directory: &str) -> Self {
+        Self {
+            cache_directory: PathBuf::from(cache_directory),
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+        }
+    }
+
+    pub async fn initialize(&self) -> Result<(), PodPicoError> {
+        fs::create_dir_all(&self.cache_directory).await?;
+        Ok(())
+    }
+
+    /// Downloads the image at `url` and stores it with a thumbnail. Images are named
+    /// after their content hash, so artwork shared by several podcasts or episodes
+    /// is stored once.
+    pub async fn cache_artwork(&self, url: &str) -> Result<CachedArtwork, PodPicoError> {
+        log::info!("Caching artwork from {}", url);
+
+        let response =
+            self.client.get(url).send().await.map_err(|e| {
+                PodPicoError::NetworkError(format!("Failed to fetch artwork: {}", e))
+            })?;
+        if !response.status().is_success() {
+            return Err(PodPicoError::NetworkError(format!(
+                "HTTP error {} fetching artwork",
+                response.status()
+            )));
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_ARTWORK_BYTES)
+        {
+            return Err(PodPicoError::ArtworkError(format!(
+                "Artwork at {} is larger than {} bytes",
+                url, MAX_ARTWORK_BYTES
+            )));
+        }
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| PodPicoError::NetworkError(format!("Failed to read artwork: {}", e)))?;
+        if bytes.len() as u64 > MAX_ARTWORK_BYTES {
+            return Err(PodPicoError::ArtworkError(format!(
+                "Artwork at {} is larger than {} bytes",
+                url, MAX_ARTWORK_BYTES
+            )));
+        }
+
+        self.store(&bytes).await
+    }
+
+    /// Stores image bytes under their content hash and creates the thumbnail,
+    /// reusing files that already exist
+    pub async fn store(&self, bytes: &[u8]) -> Result<CachedArtwork, PodPicoError> {
+        let format = image::guess_format(bytes)
+            .map_err(|_| PodPicoError::ArtworkError("Unrecognised image format".to_string()))?;
+        let extension = format.extensions_str().first().copied().unwrap_or("img");
+
+        let hash = format!("{:x}", Sha256::digest(bytes));
+        let image_path = self.cache_directory.join(format!("{}.{}", hash, extension));
+        let thumbnail_path = self
+            .cache_directory
+            .join(format!("{}-{}.png", hash, THUMBNAIL_SIZE));
+
+        if !image_path.exists() {
+            write_atomically(&image_path, bytes).await?;
+        }
+        if !thumbnail_path.exists() {
+            let bytes = bytes.to_vec();
+            let target = thumbnail_path.clone();
+            tokio::task::spawn_blocking(move || write_thumbnail(&bytes, &target))
+                .await
+                .map_err(|e| PodPicoError::ArtworkError(e.to_string()))??;
+        }
+
+        Ok(CachedArtwork {
+            image_path: image_path.to_string_lossy().to_string(),
+            thumbnail_path: thumbnail_path.to_string_lossy().to_string(),
+        })
+    }
+}
+
+/// Decodes the image and writes it as a PNG no larger than `THUMBNAIL_SIZE`
+/// on either side, keeping its aspect ratio. Smaller images are not scaled up.
+fn write_thumbnail(bytes: &[u8], target: &Path) -> Result<(), PodPicoError> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| PodPicoError::ArtworkError(format!("Failed to decode artwork: {}", e)))?;
+    let thumbnail = if image.width() > THUMBNAIL_SIZE || image.height() > THUMBNAIL_SIZE {
+        image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+    } else {
+        image
+    };
+
+    let partial = target.with_extension("png.part");
+    thumbnail
+        .save_with_format(&partial, image::ImageFormat::Png)
+        .map_err(|e| PodPicoError::ArtworkError(format!("Failed to write thumbnail: {}", e)))?;
+    std::fs::rename(&partial, target)?;
+    Ok(())
+}
+
+async fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), PodPicoError> {
+    let partial = path.with_extension("part");
+    fs::write(&partial, bytes).await?;
+    fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::{Method::GET, MockServer};
+    use std::io::Cursor;
+    use tempfile::tempdir;
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbImage::from_pixel(width, height, image::Rgb([200, 30, 30]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_cache_artwork_creates_thumbnail() {
+        let temp_dir = tempdir().unwrap();
+        let cache = ArtworkCache::new(&temp_dir.path().to_string_lossy());
+        cache.initialize().await.unwrap();
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/cover.png");
+            then.status(200).body(png_bytes(1024, 512));
+        });
+
+        let cached = cache
+            .cache_artwork(&server.url("/cover.png"))
+            .await
+            .unwrap();
+
+        assert!(cached.image_path.ends_with(".png"));
+        assert_eq!(
+            std::fs::read(&cached.image_path).unwrap(),
+            png_bytes(1024, 512)
+        );
+        let thumbnail = image::open(&cached.thumbnail_path).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (256, 128));
+    }
+
+    #[tokio::test]
+    async fn test_identical_artwork_is_stored_once() {
+        let temp_dir = tempdir().unwrap();
+        let cache = ArtworkCache::new(&temp_dir.path().to_string_lossy());
+        cache.initialize().await.unwrap();
+
+        let first = cache.store(&png_bytes(64, 64)).await.unwrap();
+        let second = cache.store(&png_bytes(64, 64)).await.unwrap();
+        let other = cache.store(&png_bytes(32, 32)).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first.image_path, other.image_path);
+        // Two images with one thumbnail each
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 4);
+
+        // Small images are not scaled up
+        let thumbnail = image::open(&first.thumbnail_path).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (64, 64));
+    }
+
+    #[tokio::test]
+    async fn test_non_image_content_is_rejected() {
+        let temp_dir = tempdir().unwrap();
+        let cache = ArtworkCache::new(&temp_dir.path().to_string_lossy());
+        cache.initialize().await.unwrap();
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/cover.jpg");
+            then.status(200).body("<html>Not found</html>");
+        });
+
+        let result = cache.cache_artwork(&server.url("/cover.jpg")).await;
+
+        assert!(matches!(result, Err(PodPicoError::ArtworkError(_))));
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+}
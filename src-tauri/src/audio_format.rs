@@ -0,0 +1,222 @@
+// Audio format detection module for PodPico
+// Decides file extensions from MIME types, URLs and the file's leading bytes
+
+use std::path::Path;
+
+/// Extensions treated as episode audio when scanning directories and devices
+pub const AUDIO_FILE_EXTENSIONS: &[&str] = &[
+    "mp3", "m4a", "m4b", "aac", "ogg", "oga", "opus", "flac", "wav",
+];
+
+/// Extension used when nothing else identifies the format
+pub const FALLBACK_AUDIO_EXTENSION: &str = "mp3";
+
+/// Number of leading bytes `sniff_audio_extension` needs to recognise every format
+pub const SNIFF_HEADER_LEN: usize = 64;
+
+pub fn is_audio_extension(extension: &str) -> bool {
+    AUDIO_FILE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| is_audio_extension(&ext.to_string_lossy()))
+}
+
+/// Maps an audio MIME type (parameters allowed) to its file extension
+pub fn extension_for_mime_type(mime_type: &str) -> Option<&'static str> {
+    let essence = mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+
+    match essence.as_str() {
+        "audio/mpeg" | "audio/mp3" | "audio/mpeg3" | "audio/x-mpeg" | "audio/x-mp3" => Some("mp3"),
+        "audio/mp4" | "audio/x-m4a" | "audio/m4a" | "audio/mp4a-latm" => Some("m4a"),
+        "audio/x-m4b" | "audio/m4b" => Some("m4b"),
+        "audio/aac" | "audio/aacp" | "audio/x-aac" => Some("aac"),
+        "audio/ogg" | "application/ogg" | "audio/x-ogg" | "audio/vorbis" => Some("ogg"),
+        "audio/opus" => Some("opus"),
+        "audio/flac" | "audio/x-flac" => Some("flac"),
+        "audio/wav" | "audio/x-wav" | "audio/wave" | "audio/vnd.wave" => Some("wav"),
+        _ => None,
+    }
+}
+
+/// Codec usually stored in files with the given extension
+pub fn codec_for_extension(extension: &str) -> Option<&'static str> {
+    match extension.to_lowercase().as_str() {
+        "mp3" => Some("mp3"),
+        "m4a" | "m4b" | "aac" => Some("aac"),
+        "opus" => Some("opus"),
+        "ogg" | "oga" => Some("vorbis"),
+        "flac" => Some("flac"),
+        "wav" => Some("wav"),
+        _ => None,
+    }
+}
+
+/// Extension of the last URL path segment when it names an audio format
+pub fn extension_from_url(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file_name = path.rsplit('/').next()?;
+    let (_, extension) = file_name.rsplit_once('.')?;
+    let extension = extension.to_lowercase();
+    is_audio_extension(&extension).then_some(extension)
+}
+
+/// Recognises the container from the first bytes of a file
+pub fn sniff_audio_extension(header: &[u8]) -> Option<&'static str> {
+    if header.starts_with(b"ID3") {
+        return Some("mp3");
+    }
+    if header.starts_with(b"fLaC") {
+        return Some("flac");
+    }
+    if header.starts_with(b"OggS") {
+        // The first Ogg page carries the codec identification header
+        let is_opus = header.windows(8).any(|window| window == b"OpusHead");
+        return Some(if is_opus { "opus" } else { "ogg" });
+    }
+    if header.len() >= 12 && header.starts_with(b"RIFF") && &header[8..12] == b"WAVE" {
+        return Some("wav");
+    }
+    if header.len() >= 12 && &header[4..8] == b"ftyp" {
+        return Some(if &header[8..12] == b"M4B " {
+            "m4b"
+        } else {
+            "m4a"
+        });
+    }
+    if header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0 {
+        // MPEG frame sync; layer bits 00 mean an AAC ADTS header instead
+        let layer = (header[1] >> 1) & 0x03;
+        return Some(if layer == 0 { "aac" } else { "mp3" });
+    }
+    None
+}
+
+/// Picks the extension for a downloaded file, trusting the file's contents first,
+/// then the server's Content-Type, the feed's enclosure type and finally the URL
+pub fn choose_audio_extension(
+    header: &[u8],
+    content_type: Option<&str>,
+    enclosure_type: Option<&str>,
+    url: &str,
+) -> String {
+    sniff_audio_extension(header)
+        .or_else(|| content_type.and_then(extension_for_mime_type))
+        .or_else(|| enclosure_type.and_then(extension_for_mime_type))
+        .map(str::to_string)
+        .or_else(|| extension_from_url(url))
+        .unwrap_or_else(|| FALLBACK_AUDIO_EXTENSION.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_for_mime_type() {
+        assert_eq!(extension_for_mime_type("audio/mpeg"), Some("mp3"));
+        assert_eq!(extension_for_mime_type("Audio/X-M4A"), Some("m4a"));
+        assert_eq!(
+            extension_for_mime_type("audio/ogg; codecs=opus"),
+            Some("ogg")
+        );
+        assert_eq!(extension_for_mime_type("audio/opus"), Some("opus"));
+        assert_eq!(extension_for_mime_type("audio/x-flac"), Some("flac"));
+        assert_eq!(extension_for_mime_type("application/octet-stream"), None);
+    }
+
+    #[test]
+    fn test_extension_from_url() {
+        assert_eq!(
+            extension_from_url("https://cdn.example.com/show/ep.OGG?token=1"),
+            Some("ogg".to_string())
+        );
+        assert_eq!(
+            extension_from_url("https://example.com/ep.opus#t=10"),
+            Some("opus".to_string())
+        );
+        assert_eq!(extension_from_url("https://example.com/media/12345"), None);
+        assert_eq!(extension_from_url("https://example.com/page.html"), None);
+    }
+
+    #[test]
+    fn test_sniff_audio_extension() {
+        assert_eq!(sniff_audio_extension(b"ID3\x04\x00"), Some("mp3"));
+        assert_eq!(
+            sniff_audio_extension(&[0xFF, 0xFB, 0x90, 0x64]),
+            Some("mp3")
+        );
+        assert_eq!(
+            sniff_audio_extension(&[0xFF, 0xF1, 0x50, 0x80]),
+            Some("aac")
+        );
+        assert_eq!(sniff_audio_extension(b"fLaC\x00\x00\x00\x22"), Some("flac"));
+        assert_eq!(
+            sniff_audio_extension(b"RIFF\x24\x08\x00\x00WAVEfmt "),
+            Some("wav")
+        );
+        assert_eq!(
+            sniff_audio_extension(b"\x00\x00\x00\x20ftypM4A \x00\x00"),
+            Some("m4a")
+        );
+        assert_eq!(
+            sniff_audio_extension(b"\x00\x00\x00\x20ftypM4B \x00\x00"),
+            Some("m4b")
+        );
+
+        let mut ogg_opus = b"OggS".to_vec();
+        ogg_opus.resize(28, 0);
+        ogg_opus.extend_from_slice(b"OpusHead");
+        assert_eq!(sniff_audio_extension(&ogg_opus), Some("opus"));
+
+        let mut ogg_vorbis = b"OggS".to_vec();
+        ogg_vorbis.resize(28, 0);
+        ogg_vorbis.extend_from_slice(b"\x01vorbis");
+        assert_eq!(sniff_audio_extension(&ogg_vorbis), Some("ogg"));
+
+        assert_eq!(sniff_audio_extension(b"<html>"), None);
+        assert_eq!(sniff_audio_extension(b""), None);
+    }
+
+    #[test]
+    fn test_choose_audio_extension_priority() {
+        let url = "https://example.com/episode.mp3";
+
+        assert_eq!(
+            choose_audio_extension(b"fLaC", Some("audio/ogg"), Some("audio/mpeg"), url),
+            "flac"
+        );
+        assert_eq!(
+            choose_audio_extension(b"", Some("audio/ogg"), Some("audio/mpeg"), url),
+            "ogg"
+        );
+        assert_eq!(
+            choose_audio_extension(
+                b"",
+                Some("application/octet-stream"),
+                Some("audio/aac"),
+                url
+            ),
+            "aac"
+        );
+        assert_eq!(choose_audio_extension(b"", None, None, url), "mp3");
+        assert_eq!(
+            choose_audio_extension(b"", None, None, "https://cdn.example.com/e/123"),
+            FALLBACK_AUDIO_EXTENSION
+        );
+    }
+
+    #[test]
+    fn test_is_audio_file() {
+        assert!(is_audio_file(Path::new("/music/episode.OPUS")));
+        assert!(is_audio_file(Path::new("episode.m4b")));
+        assert!(!is_audio_file(Path::new("cover.jpg")));
+        assert!(!is_audio_file(Path::new("episode.part")));
+    }
+}
@@ -0,0 +1,474 @@
+// Audio probing module for PodPico
+// Reads duration, bitrate and sample rate from MP3, MP4 and Ogg container headers
+
+use crate::audio_format::{sniff_audio_extension, SNIFF_HEADER_LEN};
+use crate::error::PodPicoError;
+use crate::mp4;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes read after the ID3 tag when looking for the first MPEG frame
+const MP3_SCAN_LEN: usize = 64 * 1024;
+
+/// Bytes read from the end of an Ogg file when looking for the last page
+const OGG_TAIL_LEN: u64 = 64 * 1024;
+
+/// Opus granule positions always count 48 kHz samples
+const OPUS_GRANULE_RATE: u32 = 48_000;
+
+/// Measured properties of an audio file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioProperties {
+    pub duration_seconds: f64,
+    /// Average bitrate over the whole file, in bits per second
+    pub bitrate_bps: u32,
+    pub sample_rate: u32,
+}
+
+impl AudioProperties {
+    /// Builds properties from a duration and the number of audio bytes it covers
+    pub(crate) fn from_audio_bytes(
+        duration_seconds: f64,
+        audio_bytes: u64,
+        sample_rate: u32,
+    ) -> Self {
+        let bitrate_bps = if duration_seconds > 0.0 {
+            (audio_bytes as f64 * 8.0 / duration_seconds).round() as u32
+        } else {
+            0
+        };
+        Self {
+            duration_seconds,
+            bitrate_bps,
+            sample_rate,
+        }
+    }
+}
+
+/// Measures the audio file at `path` from its container headers, without decoding
+pub fn probe_audio_file(path: &Path) -> Result<AudioProperties, PodPicoError> {
+    let mut file = File::open(path)?;
+    let mut header = vec![0u8; SNIFF_HEADER_LEN];
+    let read = read_up_to(&mut file, &mut header)?;
+    header.truncate(read);
+
+    match sniff_audio_extension(&header) {
+        Some("mp3") => probe_mp3(&mut file),
+        Some("m4a") | Some("m4b") => mp4::probe_audio(path),
+        Some("ogg") | Some("opus") => probe_ogg(&mut file),
+        Some(other) => Err(PodPicoError::UnsupportedFormat(format!(
+            "Measuring .{} files is not supported",
+            other
+        ))),
+        None => Err(PodPicoError::UnsupportedFormat(
+            "Unrecognised audio format".to_string(),
+        )),
+    }
+}
+
+/// Fields of an MPEG audio frame header
+#[derive(Debug, Clone, Copy)]
+struct MpegFrame {
+    /// 1 for MPEG-1, 2 for MPEG-2 and MPEG-2.5
+    version: u8,
+    layer: u8,
+    bitrate_kbps: u32,
+    sample_rate: u32,
+    mono: bool,
+    samples_per_frame: u32,
+    frame_len: usize,
+}
+
+fn parse_mpeg_frame(bytes: &[u8]) -> Option<MpegFrame> {
+    if bytes.len() < 4 || bytes[0] != 0xFF || bytes[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+
+    let version_bits = (bytes[1] >> 3) & 0x03;
+    let layer = match (bytes[1] >> 1) & 0x03 {
+        1 => 3,
+        2 => 2,
+        3 => 1,
+        _ => return None,
+    };
+    let bitrate_index = (bytes[2] >> 4) as usize;
+    let sample_rate_index = ((bytes[2] >> 2) & 0x03) as usize;
+    if bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+        return None;
+    }
+
+    let (version, sample_rate_divisor) = match version_bits {
+        3 => (1, 1),
+        2 => (2, 2),
+        0 => (2, 4),
+        _ => return None,
+    };
+    let bitrate_kbps = match (version, layer) {
+        (1, 1) => [
+            32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+        ],
+        (1, 2) => [
+            32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+        ],
+        (1, _) => [
+            32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+        ],
+        (_, 1) => [
+            32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+        ],
+        _ => [8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    }[bitrate_index - 1];
+    let sample_rate = [44_100, 48_000, 32_000][sample_rate_index] / sample_rate_divisor;
+
+    let samples_per_frame = match (version, layer) {
+        (_, 1) => 384,
+        (2, 3) => 576,
+        _ => 1152,
+    };
+    let padding = ((bytes[2] >> 1) & 0x01) as usize;
+    let frame_len = if layer == 1 {
+        (12 * bitrate_kbps as usize * 1000 / sample_rate as usize + padding) * 4
+    } else {
+        samples_per_frame as usize / 8 * bitrate_kbps as usize * 1000 / sample_rate as usize
+            + padding
+    };
+
+    Some(MpegFrame {
+        version,
+        layer,
+        bitrate_kbps,
+        sample_rate,
+        mono: bytes[3] >> 6 == 3,
+        samples_per_frame,
+        frame_len,
+    })
+}
+
+fn probe_mp3(file: &mut File) -> Result<AudioProperties, PodPicoError> {
+    let file_len = file.metadata()?.len();
+    let audio_start = id3v2_len(file)?;
+
+    let mut buffer = vec![0u8; MP3_SCAN_LEN];
+    file.seek(SeekFrom::Start(audio_start))?;
+    let read = read_up_to(file, &mut buffer)?;
+    buffer.truncate(read);
+
+    let (offset, frame) = find_first_frame(&buffer)
+        .ok_or_else(|| PodPicoError::UnsupportedFormat("No MPEG audio frames found".to_string()))?;
+    let first_frame_start = audio_start + offset as u64;
+
+    // A trailing ID3v1 tag isn't audio
+    let mut audio_end = file_len;
+    if file_len >= first_frame_start + 128 {
+        let mut tail = [0u8; 3];
+        file.seek(SeekFrom::Start(file_len - 128))?;
+        file.read_exact(&mut tail)?;
+        if &tail == b"TAG" {
+            audio_end -= 128;
+        }
+    }
+    let audio_bytes = audio_end.saturating_sub(first_frame_start);
+
+    // VBR files describe the whole stream in their first frame
+    if let Some((frames, bytes)) = vbr_header(&buffer[offset..], &frame) {
+        let duration = frames as f64 * frame.samples_per_frame as f64 / frame.sample_rate as f64;
+        let bytes = bytes.map_or(audio_bytes, u64::from);
+        return Ok(AudioProperties::from_audio_bytes(
+            duration,
+            bytes,
+            frame.sample_rate,
+        ));
+    }
+
+    // Constant bitrate: the size of the audio data gives the duration
+    let bitrate_bps = frame.bitrate_kbps * 1000;
+    Ok(AudioProperties {
+        duration_seconds: audio_bytes as f64 * 8.0 / bitrate_bps as f64,
+        bitrate_bps,
+        sample_rate: frame.sample_rate,
+    })
+}
+
+/// Total length of the ID3v2 tag at the start of the file, 0 when there is none
+fn id3v2_len(file: &mut File) -> Result<u64, PodPicoError> {
+    let mut header = [0u8; 10];
+    file.seek(SeekFrom::Start(0))?;
+    if read_up_to(file, &mut header)? < 10 || &header[..3] != b"ID3" {
+        return Ok(0);
+    }
+
+    // Synchsafe integer: 7 bits per byte
+    let size = header[6..10]
+        .iter()
+        .fold(0u64, |size, byte| (size << 7) | u64::from(byte & 0x7F));
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    Ok(10 + size + footer)
+}
+
+/// Finds the first frame header that is followed by another valid frame header,
+/// which rules out stray sync bytes in leftover tag data
+fn find_first_frame(buffer: &[u8]) -> Option<(usize, MpegFrame)> {
+    (0..buffer.len().saturating_sub(4)).find_map(|offset| {
+        let frame = parse_mpeg_frame(&buffer[offset..])?;
+        let next = offset + frame.frame_len;
+        let confirmed = match buffer.get(next..) {
+            Some(rest) if rest.len() >= 4 => parse_mpeg_frame(rest).is_some_and(|next| {
+                next.version == frame.version
+                    && next.layer == frame.layer
+                    && next.sample_rate == frame.sample_rate
+            }),
+            // The file ends within the scanned buffer
+            _ => true,
+        };
+        confirmed.then_some((offset, frame))
+    })
+}
+
+/// Reads the Xing/Info or VBRI header from the first frame:
+/// the number of frames and, when present, the number of audio bytes
+fn vbr_header(frame_bytes: &[u8], frame: &MpegFrame) -> Option<(u32, Option<u32>)> {
+    let side_info_len = match (frame.version, frame.mono) {
+        (1, false) => 32,
+        (1, true) | (_, false) => 17,
+        (_, true) => 9,
+    };
+    let xing = frame_bytes.get(4 + side_info_len..)?;
+    if xing.starts_with(b"Xing") || xing.starts_with(b"Info") {
+        let flags = read_u32_be(xing, 4)?;
+        if flags & 0x01 == 0 {
+            return None;
+        }
+        let frames = read_u32_be(xing, 8)?;
+        let bytes = if flags & 0x02 != 0 {
+            read_u32_be(xing, 12)
+        } else {
+            None
+        };
+        return (frames > 0).then_some((frames, bytes));
+    }
+
+    let vbri = frame_bytes.get(4 + 32..)?;
+    if vbri.starts_with(b"VBRI") {
+        let bytes = read_u32_be(vbri, 10)?;
+        let frames = read_u32_be(vbri, 14)?;
+        return (frames > 0).then_some((frames, Some(bytes)));
+    }
+    None
+}
+
+fn probe_ogg(file: &mut File) -> Result<AudioProperties, PodPicoError> {
+    let file_len = file.metadata()?.len();
+    let mut first_page = vec![0u8; 512];
+    file.seek(SeekFrom::Start(0))?;
+    let read = read_up_to(file, &mut first_page)?;
+    first_page.truncate(read);
+
+    let packet = ogg_first_packet(&first_page).ok_or_else(malformed_ogg)?;
+    let (granule_rate, pre_skip, sample_rate) = if packet.starts_with(b"\x01vorbis") {
+        let sample_rate = read_u32_le(packet, 12).ok_or_else(malformed_ogg)?;
+        (sample_rate, 0, sample_rate)
+    } else if packet.starts_with(b"OpusHead") {
+        let pre_skip = packet
+            .get(10..12)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .ok_or_else(malformed_ogg)?;
+        let input_rate = read_u32_le(packet, 12).ok_or_else(malformed_ogg)?;
+        // Opus always decodes at 48 kHz; the input rate is informational and may be 0
+        let sample_rate = if input_rate > 0 {
+            input_rate
+        } else {
+            OPUS_GRANULE_RATE
+        };
+        (OPUS_GRANULE_RATE, u64::from(pre_skip), sample_rate)
+    } else {
+        return Err(PodPicoError::UnsupportedFormat(
+            "Only Vorbis and Opus streams in Ogg files are supported".to_string(),
+        ));
+    };
+    if granule_rate == 0 {
+        return Err(malformed_ogg());
+    }
+
+    let tail_start = file_len.saturating_sub(OGG_TAIL_LEN);
+    let mut tail = vec![0u8; (file_len - tail_start) as usize];
+    file.seek(SeekFrom::Start(tail_start))?;
+    file.read_exact(&mut tail)?;
+    let last_granule = last_ogg_granule(&tail).ok_or_else(malformed_ogg)?;
+
+    let duration = last_granule.saturating_sub(pre_skip) as f64 / granule_rate as f64;
+    Ok(AudioProperties::from_audio_bytes(
+        duration,
+        file_len,
+        sample_rate,
+    ))
+}
+
+/// The first packet of the first Ogg page, which identifies the codec
+fn ogg_first_packet(page: &[u8]) -> Option<&[u8]> {
+    if !page.starts_with(b"OggS") {
+        return None;
+    }
+    let segment_count = *page.get(26)? as usize;
+    let segments = page.get(27..27 + segment_count)?;
+    // Packets end at the first lacing value below 255
+    let packet_len: usize = segments
+        .iter()
+        .position(|&lacing| lacing < 255)
+        .map(|end| segments[..=end].iter().map(|&lacing| lacing as usize).sum())?;
+    let start = 27 + segment_count;
+    page.get(start..start + packet_len)
+}
+
+/// Granule position of the last page in `tail` that has one
+fn last_ogg_granule(tail: &[u8]) -> Option<u64> {
+    tail.windows(4)
+        .enumerate()
+        .rev()
+        .filter(|(_, window)| *window == b"OggS")
+        .filter_map(|(offset, _)| {
+            let granule = tail.get(offset + 6..offset + 14)?;
+            let granule = i64::from_le_bytes(granule.try_into().ok()?);
+            // -1 marks pages on which no packet ends
+            (granule >= 0).then_some(granule as u64)
+        })
+        .next()
+}
+
+fn malformed_ogg() -> PodPicoError {
+    PodPicoError::UnsupportedFormat("Malformed Ogg stream".to_string())
+}
+
+fn read_u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes
+        .get(at..at + 4)
+        .map(|value| u32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+fn read_u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes
+        .get(at..at + 4)
+        .map(|value| u32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+/// Fills as much of `buffer` as the file allows, returning the number of bytes read
+fn read_up_to(file: &mut File, buffer: &mut [u8]) -> Result<usize, PodPicoError> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// MPEG-1 Layer III, 128 kbps, 44.1 kHz, stereo, no padding: 417 bytes per frame
+    const CBR_FRAME_HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x00];
+
+    fn cbr_frame() -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&CBR_FRAME_HEADER);
+        frame
+    }
+
+    fn write_file(name: &str, bytes: &[u8]) -> (tempfile::TempDir, std::path::PathBuf) {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        (temp_dir, path)
+    }
+
+    #[test]
+    fn test_probe_cbr_mp3_after_id3_tag() {
+        let mut bytes = b"ID3\x04\x00\x00\x00\x00\x00\x14".to_vec();
+        bytes.extend_from_slice(&[0u8; 20]);
+        // 10 seconds of 128 kbps audio
+        let frames = 10 * 128_000 / 8 / 417;
+        for _ in 0..frames {
+            bytes.extend_from_slice(&cbr_frame());
+        }
+
+        let (_dir, path) = write_file("cbr.mp3", &bytes);
+        let properties = probe_audio_file(&path).unwrap();
+
+        assert_eq!(properties.bitrate_bps, 128_000);
+        assert_eq!(properties.sample_rate, 44_100);
+        assert!((properties.duration_seconds - 10.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_probe_vbr_mp3_uses_xing_header() {
+        let mut first = cbr_frame();
+        // Side information of a stereo MPEG-1 frame is 32 bytes
+        first[36..40].copy_from_slice(b"Xing");
+        first[40..44].copy_from_slice(&3u32.to_be_bytes());
+        // 38.28 frames per second at 44.1 kHz: 3828 frames are 100 seconds
+        first[44..48].copy_from_slice(&3828u32.to_be_bytes());
+        first[48..52].copy_from_slice(&800_000u32.to_be_bytes());
+        let mut bytes = first;
+        bytes.extend_from_slice(&cbr_frame());
+
+        let (_dir, path) = write_file("vbr.mp3", &bytes);
+        let properties = probe_audio_file(&path).unwrap();
+
+        assert!((properties.duration_seconds - 100.0).abs() < 0.01);
+        assert!((properties.bitrate_bps as i64 - 64_000).abs() < 10);
+    }
+
+    fn ogg_page(granule: i64, packet: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS\x00\x02".to_vec();
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&[0u8; 12]); // serial, sequence, checksum
+        page.push(1);
+        page.push(packet.len() as u8);
+        page.extend_from_slice(packet);
+        page
+    }
+
+    #[test]
+    fn test_probe_opus_duration_from_last_granule() {
+        let mut head = b"OpusHead\x01\x02".to_vec();
+        head.extend_from_slice(&312u16.to_le_bytes());
+        head.extend_from_slice(&44_100u32.to_le_bytes());
+        head.extend_from_slice(&[0, 0, 0]);
+        let mut bytes = ogg_page(0, &head);
+        bytes.extend_from_slice(&ogg_page(-1, &[0u8; 100]));
+        bytes.extend_from_slice(&ogg_page(48_000 * 90 + 312, &[0u8; 100]));
+
+        let (_dir, path) = write_file("episode.opus", &bytes);
+        let properties = probe_audio_file(&path).unwrap();
+
+        assert!((properties.duration_seconds - 90.0).abs() < 0.001);
+        assert_eq!(properties.sample_rate, 44_100);
+    }
+
+    #[test]
+    fn test_probe_vorbis_sample_rate() {
+        let mut head = b"\x01vorbis\x00\x00\x00\x00\x02".to_vec();
+        head.extend_from_slice(&22_050u32.to_le_bytes());
+        head.extend_from_slice(&[0u8; 14]);
+        let mut bytes = ogg_page(0, &head);
+        bytes.extend_from_slice(&ogg_page(22_050 * 30, &[0u8; 100]));
+
+        let (_dir, path) = write_file("episode.ogg", &bytes);
+        let properties = probe_audio_file(&path).unwrap();
+
+        assert!((properties.duration_seconds - 30.0).abs() < 0.001);
+        assert_eq!(properties.sample_rate, 22_050);
+    }
+
+    #[test]
+    fn test_unknown_content_is_rejected() {
+        let (_dir, path) = write_file("episode.mp3", b"<html>Not audio</html>");
+        assert!(matches!(
+            probe_audio_file(&path),
+            Err(PodPicoError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,271 @@
+// Chapters module for PodPico
+// Reads episode chapters and turns them into segments, so episodes can be split
+// into several files for devices without bookmark support
+
+use crate::error::PodPicoError;
+use id3::{Tag, TagLike};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How episodes are split on transfer: not at all, at their chapters (falling
+/// back to fixed intervals when there are none) or at fixed intervals only
+pub const SPLIT_MODES: &[&str] = &["off", "chapters", "interval"];
+
+pub const DEFAULT_SPLIT_INTERVAL_MINUTES: u32 = 15;
+
+/// Chapters shorter than this are merged into the previous segment
+const MIN_SEGMENT_SECONDS: f64 = 60.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub start_seconds: f64,
+    pub title: Option<String>,
+}
+
+/// Part of an episode written to its own file; the last one runs to the end
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub start_seconds: f64,
+    pub end_seconds: Option<f64>,
+    pub title: Option<String>,
+}
+
+pub fn validate_split_settings(mode: &str, interval_minutes: u32) -> Result<(), PodPicoError> {
+    if !SPLIT_MODES.contains(&mode) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Split mode must be one of {}, got '{}'",
+            SPLIT_MODES.join(", "),
+            mode
+        )));
+    }
+    if !(1..=180).contains(&interval_minutes) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Split interval must be between 1 and 180 minutes, got {}",
+            interval_minutes
+        )));
+    }
+    Ok(())
+}
+
+/// Chapters from the ID3 `CHAP` frames of an MP3 file; empty when it has none
+pub fn read_id3_chapters(path: &Path) -> Result<Vec<Chapter>, PodPicoError> {
+    let tag = match Tag::read_from_path(path) {
+        Ok(tag) => tag,
+        Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => return Ok(Vec::new()),
+        Err(e) => return Err(PodPicoError::UnsupportedFormat(e.to_string())),
+    };
+
+    Ok(tag
+        .chapters()
+        .map(|chapter| Chapter {
+            start_seconds: f64::from(chapter.start_time) / 1000.0,
+            title: chapter.title().map(str::to_string),
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct ChaptersDocument {
+    chapters: Vec<JsonChapter>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonChapter {
+    start_time: f64,
+    title: Option<String>,
+    /// Chapters with `toc: false` only carry artwork or links, not a section
+    toc: Option<bool>,
+}
+
+/// Parses a `podcast:chapters` JSON document
+pub fn parse_chapters_json(json: &str) -> Result<Vec<Chapter>, PodPicoError> {
+    let document: ChaptersDocument = serde_json::from_str(json)
+        .map_err(|e| PodPicoError::UnsupportedFormat(format!("Invalid chapters JSON: {}", e)))?;
+
+    Ok(document
+        .chapters
+        .into_iter()
+        .filter(|chapter| chapter.toc != Some(false))
+        .map(|chapter| Chapter {
+            start_seconds: chapter.start_time,
+            title: chapter.title,
+        })
+        .collect())
+}
+
+/// Downloads and parses the `podcast:chapters` document at `url`
+pub async fn fetch_chapters_json(url: &str) -> Result<Vec<Chapter>, PodPicoError> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new());
+    let json = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    parse_chapters_json(&json)
+}
+
+/// Untitled chapters every `interval_seconds`
+pub fn interval_chapters(duration_seconds: f64, interval_seconds: f64) -> Vec<Chapter> {
+    (0..)
+        .map(|index| f64::from(index) * interval_seconds)
+        .take_while(|start| *start < duration_seconds)
+        .map(|start_seconds| Chapter {
+            start_seconds,
+            title: None,
+        })
+        .collect()
+}
+
+/// Turns chapters into consecutive segments covering the whole episode.
+/// Chapters shorter than a minute are merged into the one before them, and
+/// chapters past `duration_seconds` (when known) are ignored.
+pub fn segments_from_chapters(chapters: &[Chapter], duration_seconds: Option<f64>) -> Vec<Segment> {
+    let mut chapters: Vec<&Chapter> = chapters
+        .iter()
+        .filter(|chapter| {
+            chapter.start_seconds >= 0.0
+                && duration_seconds.is_none_or(|duration| chapter.start_seconds < duration)
+        })
+        .collect();
+    chapters.sort_by(|a, b| a.start_seconds.total_cmp(&b.start_seconds));
+
+    let mut starts: Vec<(f64, Option<String>)> = Vec::new();
+    for chapter in chapters {
+        match starts.last() {
+            Some((start, _)) if chapter.start_seconds - start < MIN_SEGMENT_SECONDS => {}
+            // Audio before the first chapter belongs to it unless it is long enough
+            // for a segment of its own
+            None if chapter.start_seconds < MIN_SEGMENT_SECONDS => {
+                starts.push((0.0, chapter.title.clone()))
+            }
+            None => {
+                starts.push((0.0, None));
+                starts.push((chapter.start_seconds, chapter.title.clone()));
+            }
+            Some(_) => starts.push((chapter.start_seconds, chapter.title.clone())),
+        }
+    }
+    if let (Some(duration), true) = (duration_seconds, starts.len() > 1) {
+        if duration - starts[starts.len() - 1].0 < MIN_SEGMENT_SECONDS {
+            starts.pop();
+        }
+    }
+
+    let ends: Vec<Option<f64>> = starts
+        .iter()
+        .skip(1)
+        .map(|(start, _)| Some(*start))
+        .chain([None])
+        .collect();
+    starts
+        .into_iter()
+        .zip(ends)
+        .map(|((start_seconds, title), end_seconds)| Segment {
+            start_seconds,
+            end_seconds,
+            title,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(start_seconds: f64, title: &str) -> Chapter {
+        Chapter {
+            start_seconds,
+            title: Some(title.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_chapters_json_skips_non_toc_chapters() {
+        let json = r#"{
+            "version": "1.2.0",
+            "chapters": [
+                {"startTime": 0, "title": "Intro"},
+                {"startTime": 95.5, "title": "Sponsor image", "toc": false},
+                {"startTime": 120, "title": "Interview", "img": "https://example.com/a.jpg"}
+            ]
+        }"#;
+
+        assert_eq!(
+            parse_chapters_json(json).unwrap(),
+            vec![chapter(0.0, "Intro"), chapter(120.0, "Interview")]
+        );
+        assert!(parse_chapters_json("<chapters/>").is_err());
+    }
+
+    #[test]
+    fn test_segments_merge_short_chapters() {
+        let chapters = vec![
+            chapter(600.0, "Interview"),
+            chapter(20.0, "Intro"),
+            chapter(630.0, "Ad break"),
+            chapter(1780.0, "Outro"),
+        ];
+
+        let segments = segments_from_chapters(&chapters, Some(1800.0));
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    start_seconds: 0.0,
+                    end_seconds: Some(600.0),
+                    title: Some("Intro".to_string()),
+                },
+                Segment {
+                    start_seconds: 600.0,
+                    end_seconds: None,
+                    title: Some("Interview".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interval_chapters_cover_the_episode() {
+        let segments = segments_from_chapters(&interval_chapters(2000.0, 900.0), Some(2000.0));
+        let bounds: Vec<(f64, Option<f64>)> = segments
+            .iter()
+            .map(|segment| (segment.start_seconds, segment.end_seconds))
+            .collect();
+        assert_eq!(
+            bounds,
+            vec![(0.0, Some(900.0)), (900.0, Some(1800.0)), (1800.0, None)]
+        );
+    }
+
+    #[test]
+    fn test_read_id3_chapters() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("episode.mp3");
+        std::fs::write(&path, [0xFF, 0xFB, 0x90, 0x64, 0, 0, 0, 0]).unwrap();
+        assert!(read_id3_chapters(&path).unwrap().is_empty());
+
+        let mut tag = Tag::new();
+        let mut intro = id3::frame::Chapter {
+            element_id: "ch0".to_string(),
+            start_time: 0,
+            end_time: 90_000,
+            start_offset: 0xFFFF_FFFF,
+            end_offset: 0xFFFF_FFFF,
+            frames: Vec::new(),
+        };
+        intro.set_title("Intro");
+        tag.add_frame(intro);
+        tag.write_to_path(&path, id3::Version::Id3v23).unwrap();
+
+        assert_eq!(
+            read_id3_chapters(&path).unwrap(),
+            vec![chapter(0.0, "Intro")]
+        );
+    }
+}
@@ -19,28 +19,117 @@ impl ConfigManager {
 
     pub async fn load_config(&self) -> Result<AppConfig, PodPicoError> {
         log::info!("Loading configuration from: {:?}", self.config_path);
-        // TODO: Load configuration from file or return defaults
-        Ok(AppConfig {
-            download_directory: "../episodes".to_string(),
-            max_concurrent_downloads: 3,
-            auto_download_new_episodes: false,
-            check_for_updates_interval: 3600,
-            default_episode_status: "new".to_string(),
-        })
+
+        if !self.config_path.exists() {
+            return Ok(AppConfig::default());
+        }
+
+        let contents = fs::read_to_string(&self.config_path).await?;
+        Ok(serde_json::from_str(&contents)?)
     }
 
-    pub async fn save_config(&self, _config: &AppConfig) -> Result<(), PodPicoError> {
+    pub async fn save_config(&self, config: &AppConfig) -> Result<(), PodPicoError> {
         log::info!("Saving configuration to: {:?}", self.config_path);
-        // TODO: Save configuration to file
-        Err(PodPicoError::Generic("Not implemented yet".to_string()))
+
+        // Write to a temporary file first so a crash never leaves a truncated config
+        let temp_path = self.config_path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(config)?).await?;
+        fs::rename(&temp_path, &self.config_path).await?;
+        Ok(())
     }
 
     pub async fn initialize(&self) -> Result<(), PodPicoError> {
         log::info!("Initializing configuration manager");
-        // TODO: Create config directory if it doesn't exist
         if let Some(parent) = self.config_path.parent() {
             fs::create_dir_all(parent).await?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_load_config_defaults_when_missing() {
+        let temp_dir = tempdir().unwrap();
+        let manager = ConfigManager::new(&temp_dir.path().join("config.json").to_string_lossy());
+
+        let config = manager.load_config().await.unwrap();
+        assert_eq!(
+            config.download_directory,
+            AppConfig::default().download_directory
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_config_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let manager = ConfigManager::new(
+            &temp_dir
+                .path()
+                .join("nested")
+                .join("config.json")
+                .to_string_lossy(),
+        );
+        manager.initialize().await.unwrap();
+
+        let config = AppConfig {
+            download_directory: "/srv/podcasts".to_string(),
+            ..AppConfig::default()
+        };
+        manager.save_config(&config).await.unwrap();
+
+        let loaded = manager.load_config().await.unwrap();
+        assert_eq!(loaded.download_directory, "/srv/podcasts");
+    }
+
+    #[tokio::test]
+    async fn test_load_config_fills_in_missing_fields() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            r#"{"download_directory": "/music", "max_concurrent_downloads": 2,
+                "auto_download_new_episodes": true, "check_for_updates_interval": 60,
+                "default_episode_status": "new"}"#,
+        )
+        .unwrap();
+
+        let config = ConfigManager::new(&config_path.to_string_lossy())
+            .load_config()
+            .await
+            .unwrap();
+        assert_eq!(config.download_directory, "/music");
+        assert!(config.auto_download_new_episodes);
+        assert_eq!(
+            config.filename_template,
+            crate::file_manager::DEFAULT_FILENAME_TEMPLATE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_huge_disk_space_reserve_saturates() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            r#"{"download_directory": "/music", "max_concurrent_downloads": 2,
+                "auto_download_new_episodes": false, "check_for_updates_interval": 60,
+                "default_episode_status": "new", "min_free_disk_space_mb": 18446744073709551615}"#,
+        )
+        .unwrap();
+
+        let config = ConfigManager::new(&config_path.to_string_lossy())
+            .load_config()
+            .await
+            .unwrap();
+        assert_eq!(config.disk_space_reserve_bytes(), u64::MAX);
+        assert_eq!(
+            AppConfig::default().disk_space_reserve_bytes(),
+            crate::file_manager::DEFAULT_DISK_SPACE_RESERVE_BYTES
+        );
+    }
+}
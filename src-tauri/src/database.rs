@@ -2,9 +2,26 @@
 // Handles SQLite database operations for podcasts, episodes, and related data
 // User Stories #1-11: Podcast and Episode Management
 
-use crate::commands::{Episode, Podcast};
+use crate::commands::{
+    AutoDownloadRule, DeviceProfile, DownloadRecord, Episode, Podcast, PodcastListeningStats,
+    SyncRule, TranscodeProfile, UsbDevice,
+};
 use crate::error::PodPicoError;
 use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+
+/// Columns of episode `e` (joined with its podcast `p`) for reading an
+/// `Episode`, apart from `on_device`
+const EPISODE_COLUMNS: &str =
+    "e.id, e.podcast_id, p.name as podcast_name, e.title, e.description, \
+    e.episode_url, e.published_date, e.duration, e.file_size, e.local_file_path, e.status, \
+    e.downloaded, e.starred, e.listened_at, e.enclosure_type, e.artwork_url, e.artwork_path, \
+    e.artwork_thumbnail_path, e.audio_duration, e.audio_bitrate, e.audio_sample_rate, \
+    e.loudness_lufs, e.true_peak_dbtp, e.device_duration, e.trimmed_silence, e.chapters_url";
+
+/// Whether episode `e` is on a device: it has a transfer that wasn't removed,
+/// or the flag set before transfers were recorded per device says so
+const ON_DEVICE_SQL: &str = "(COALESCE(e.on_device, false) OR EXISTS (SELECT 1 FROM episode_transfers t WHERE t.episode_id = e.id AND t.removed_at IS NULL))";
 
 pub struct DatabaseManager {
     pool: SqlitePool,
@@ -35,7 +52,10 @@ impl DatabaseManager {
                 rss_url TEXT UNIQUE NOT NULL,
                 description TEXT,
                 artwork_url TEXT,
+                artwork_path TEXT,
+                artwork_thumbnail_path TEXT,
                 website_url TEXT,
+                playback_speed REAL DEFAULT 1.0,
                 last_updated DATETIME,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
@@ -63,6 +83,20 @@ impl DatabaseManager {
                 status TEXT CHECK(status IN ('new', 'unlistened', 'listened')) DEFAULT 'new',
                 downloaded BOOLEAN DEFAULT FALSE,
                 on_device BOOLEAN DEFAULT FALSE,
+                starred BOOLEAN DEFAULT FALSE,
+                listened_at DATETIME,
+                enclosure_type TEXT,
+                artwork_url TEXT,
+                artwork_path TEXT,
+                artwork_thumbnail_path TEXT,
+                audio_duration INTEGER,
+                audio_bitrate INTEGER,
+                audio_sample_rate INTEGER,
+                loudness_lufs REAL,
+                true_peak_dbtp REAL,
+                device_duration INTEGER,
+                trimmed_silence INTEGER,
+                chapters_url TEXT,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 FOREIGN KEY (podcast_id) REFERENCES podcasts (id) ON DELETE CASCADE
@@ -77,8 +111,11 @@ impl DatabaseManager {
             r#"
             CREATE TABLE IF NOT EXISTS usb_devices (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stable_id TEXT,
                 device_name TEXT NOT NULL,
                 device_path TEXT NOT NULL,
+                filesystem_uuid TEXT,
+                label TEXT,
                 last_connected DATETIME DEFAULT CURRENT_TIMESTAMP
             )
         "#,
@@ -95,6 +132,8 @@ impl DatabaseManager {
                 device_id INTEGER NOT NULL,
                 transferred_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 file_path_on_device TEXT,
+                file_size INTEGER,
+                removed_at DATETIME,
                 FOREIGN KEY (episode_id) REFERENCES episodes (id) ON DELETE CASCADE,
                 FOREIGN KEY (device_id) REFERENCES usb_devices (id) ON DELETE CASCADE,
                 UNIQUE(episode_id, device_id)
@@ -104,10 +143,233 @@ impl DatabaseManager {
         .execute(&self.pool)
         .await?;
 
+        // Files of episodes split into several segments on a device
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS episode_transfer_segments (
+                transfer_id INTEGER NOT NULL,
+                segment_number INTEGER NOT NULL,
+                file_path_on_device TEXT NOT NULL,
+                PRIMARY KEY (transfer_id, segment_number),
+                FOREIGN KEY (transfer_id) REFERENCES episode_transfers (id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Download attempts, kept for troubleshooting. The history outlives
+        // its episode; tables from before that had the episode as a foreign
+        // key and are rebuilt without it.
+        let episode_foreign_keys: Vec<(i64,)> =
+            sqlx::query_as("SELECT id FROM pragma_foreign_key_list('downloads')")
+                .fetch_all(&self.pool)
+                .await?;
+        let rebuild_downloads = !episode_foreign_keys.is_empty();
+        if rebuild_downloads {
+            log::info!("Keeping download history of removed episodes");
+            sqlx::query("ALTER TABLE downloads RENAME TO downloads_with_episode_key")
+                .execute(&self.pool)
+                .await?;
+        }
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS downloads (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                episode_id INTEGER NOT NULL,
+                episode_url TEXT NOT NULL,
+                started_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                finished_at DATETIME,
+                downloaded_bytes INTEGER DEFAULT 0,
+                total_bytes INTEGER,
+                average_speed_bps REAL,
+                status TEXT CHECK(status IN ('in_progress', 'completed', 'failed')) DEFAULT 'in_progress',
+                error TEXT
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        if rebuild_downloads {
+            sqlx::query("INSERT INTO downloads SELECT * FROM downloads_with_episode_key")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DROP TABLE downloads_with_episode_key")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_downloads_episode_id ON downloads (episode_id)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Downloads still running when the application last exited were cut short
+        sqlx::query(
+            r#"
+            UPDATE downloads
+            SET status = 'failed', finished_at = CURRENT_TIMESTAMP,
+                error = 'Interrupted by application exit'
+            WHERE status = 'in_progress'
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Per-podcast auto-download policy applied after feed refreshes
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS podcast_download_rules (
+                podcast_id INTEGER PRIMARY KEY,
+                mode TEXT CHECK(mode IN ('off', 'latest', 'all_new', 'title_match')) DEFAULT 'off',
+                latest_count INTEGER,
+                title_pattern TEXT,
+                max_age_days INTEGER,
+                max_size_bytes INTEGER,
+                FOREIGN KEY (podcast_id) REFERENCES podcasts (id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Per-device transcoding applied when transferring episodes
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS device_transcode_profiles (
+                device_id TEXT PRIMARY KEY,
+                codec TEXT CHECK(codec IN ('off', 'mp3', 'aac', 'opus')) DEFAULT 'off',
+                bitrate_kbps INTEGER NOT NULL,
+                mono BOOLEAN DEFAULT FALSE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Per-device folder layout and file naming
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS device_profiles (
+                device_id TEXT PRIMARY KEY,
+                root_folder TEXT NOT NULL,
+                podcast_subfolders BOOLEAN DEFAULT FALSE,
+                filename_template TEXT NOT NULL,
+                max_path_length INTEGER NOT NULL,
+                allowed_codecs TEXT NOT NULL DEFAULT '',
+                fat32 BOOLEAN DEFAULT TRUE,
+                character_set TEXT CHECK(character_set IN ('strict', 'ascii', 'fat')) DEFAULT 'strict'
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Per-device rules deciding which episodes a sync puts on the device,
+        // in priority order
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS device_sync_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                device_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                kind TEXT CHECK(kind IN ('latest_unlistened', 'starred', 'fill')) NOT NULL,
+                podcast_id INTEGER,
+                count INTEGER,
+                fill_percent INTEGER,
+                FOREIGN KEY (podcast_id) REFERENCES podcasts (id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Schema upgrades for databases created by earlier versions
+        self.ensure_column("episodes", "starred", "BOOLEAN DEFAULT FALSE")
+            .await?;
+        self.ensure_column("episodes", "enclosure_type", "TEXT")
+            .await?;
+        for (table, column) in [
+            ("podcasts", "artwork_path"),
+            ("podcasts", "artwork_thumbnail_path"),
+            ("episodes", "artwork_url"),
+            ("episodes", "artwork_path"),
+            ("episodes", "artwork_thumbnail_path"),
+            ("episodes", "chapters_url"),
+            ("usb_devices", "stable_id"),
+            ("usb_devices", "filesystem_uuid"),
+            ("usb_devices", "label"),
+        ] {
+            self.ensure_column(table, column, "TEXT").await?;
+        }
+        self.ensure_column("episode_transfers", "file_size", "INTEGER")
+            .await?;
+        self.ensure_column("episode_transfers", "removed_at", "DATETIME")
+            .await?;
+        sqlx::query(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_usb_devices_stable_id ON usb_devices (stable_id)",
+        )
+        .execute(&self.pool)
+        .await?;
+        for column in [
+            "audio_duration",
+            "audio_bitrate",
+            "audio_sample_rate",
+            "device_duration",
+            "trimmed_silence",
+        ] {
+            self.ensure_column("episodes", column, "INTEGER").await?;
+        }
+        for column in ["loudness_lufs", "true_peak_dbtp"] {
+            self.ensure_column("episodes", column, "REAL").await?;
+        }
+        self.ensure_column("podcasts", "playback_speed", "REAL DEFAULT 1.0")
+            .await?;
+        if self
+            .ensure_column("episodes", "listened_at", "DATETIME")
+            .await?
+        {
+            // Best available approximation for episodes listened before the column existed
+            sqlx::query(
+                "UPDATE episodes SET listened_at = updated_at WHERE status = 'listened' AND listened_at IS NULL",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
         log::info!("Database tables created successfully");
         Ok(())
     }
 
+    /// Adds a column to an existing table unless it is already present.
+    /// Returns true when the column was added.
+    async fn ensure_column(
+        &self,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<bool, PodPicoError> {
+        let columns: Vec<(String,)> =
+            sqlx::query_as(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                .fetch_all(&self.pool)
+                .await?;
+
+        if columns.iter().any(|(name,)| name == column) {
+            return Ok(false);
+        }
+
+        log::info!("Adding column {}.{} to existing database", table, column);
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(true)
+    }
+
     pub async fn add_podcast(
         &self,
         name: &str,
@@ -138,7 +400,7 @@ impl DatabaseManager {
 
     pub async fn get_podcast_by_id(&self, podcast_id: i64) -> Result<Podcast, PodPicoError> {
         let row = sqlx::query(r#"
-            SELECT p.id, p.name, p.rss_url, p.description, p.artwork_url, p.website_url, p.last_updated,
+            SELECT p.id, p.name, p.rss_url, p.description, p.artwork_url, p.artwork_path, p.artwork_thumbnail_path, p.website_url, p.playback_speed, p.last_updated,
                    COUNT(e.id) as episode_count,
                    COUNT(CASE WHEN e.status = 'new' THEN 1 END) as new_episode_count
             FROM podcasts p
@@ -156,7 +418,10 @@ impl DatabaseManager {
             rss_url: row.get("rss_url"),
             description: row.get("description"),
             artwork_url: row.get("artwork_url"),
+            artwork_path: row.get("artwork_path"),
+            artwork_thumbnail_path: row.get("artwork_thumbnail_path"),
             website_url: row.get("website_url"),
+            playback_speed: row.get("playback_speed"),
             last_updated: row.get("last_updated"),
             episode_count: row.get("episode_count"),
             new_episode_count: row.get("new_episode_count"),
@@ -167,7 +432,7 @@ impl DatabaseManager {
         log::info!("Retrieving podcasts from database (User Story #2, #7)");
 
         let rows = sqlx::query(r#"
-            SELECT p.id, p.name, p.rss_url, p.description, p.artwork_url, p.website_url, p.last_updated,
+            SELECT p.id, p.name, p.rss_url, p.description, p.artwork_url, p.artwork_path, p.artwork_thumbnail_path, p.website_url, p.playback_speed, p.last_updated,
                    COUNT(e.id) as episode_count,
                    COUNT(CASE WHEN e.status = 'new' THEN 1 END) as new_episode_count
             FROM podcasts p
@@ -186,7 +451,10 @@ impl DatabaseManager {
                 rss_url: row.get("rss_url"),
                 description: row.get("description"),
                 artwork_url: row.get("artwork_url"),
+                artwork_path: row.get("artwork_path"),
+                artwork_thumbnail_path: row.get("artwork_thumbnail_path"),
                 website_url: row.get("website_url"),
+                playback_speed: row.get("playback_speed"),
                 last_updated: row.get("last_updated"),
                 episode_count: row.get("episode_count"),
                 new_episode_count: row.get("new_episode_count"),
@@ -196,6 +464,29 @@ impl DatabaseManager {
         Ok(podcasts)
     }
 
+    pub async fn set_podcast_playback_speed(
+        &self,
+        podcast_id: i64,
+        playback_speed: f64,
+    ) -> Result<(), PodPicoError> {
+        log::info!(
+            "Setting playback speed of podcast {} to {}",
+            podcast_id,
+            playback_speed
+        );
+
+        let result = sqlx::query("UPDATE podcasts SET playback_speed = ? WHERE id = ?")
+            .bind(playback_speed)
+            .bind(podcast_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(PodPicoError::PodcastNotFound(podcast_id));
+        }
+
+        Ok(())
+    }
+
     pub async fn remove_podcast(&self, podcast_id: i64) -> Result<(), PodPicoError> {
         log::info!(
             "Removing podcast from database: {} (User Story #4)",
@@ -219,59 +510,40 @@ impl DatabaseManager {
             podcast_id
         );
 
-        let rows = if let Some(podcast_id) = podcast_id {
+        let episodes = if let Some(podcast_id) = podcast_id {
             // User Story #2: Get episodes for specific podcast
-            sqlx::query(
+            sqlx::query_as::<_, Episode>(&format!(
                 r#"
-                SELECT e.id, e.podcast_id, p.name as podcast_name, e.title, e.description, 
-                       e.episode_url, e.published_date, e.duration, e.file_size, 
-                       e.local_file_path, e.status, e.downloaded, e.on_device
+                SELECT {columns}, {on_device} as on_device
                 FROM episodes e
                 JOIN podcasts p ON e.podcast_id = p.id
                 WHERE e.podcast_id = ?
                 ORDER BY e.published_date DESC
             "#,
-            )
+                columns = EPISODE_COLUMNS,
+                on_device = ON_DEVICE_SQL
+            ))
             .bind(podcast_id)
             .fetch_all(&self.pool)
             .await?
         } else {
             // User Story #7: Get all new episodes across all podcasts (Combined Inbox)
-            sqlx::query(
+            sqlx::query_as::<_, Episode>(&format!(
                 r#"
-                SELECT e.id, e.podcast_id, p.name as podcast_name, e.title, e.description, 
-                       e.episode_url, e.published_date, e.duration, e.file_size, 
-                       e.local_file_path, e.status, e.downloaded, e.on_device
+                SELECT {columns}, {on_device} as on_device
                 FROM episodes e
                 JOIN podcasts p ON e.podcast_id = p.id
                 WHERE e.status = 'new'
                 ORDER BY e.published_date DESC
             "#,
-            )
+                columns = EPISODE_COLUMNS,
+                on_device = ON_DEVICE_SQL
+            ))
             .fetch_all(&self.pool)
             .await?
         };
 
-        let episodes: Vec<Episode> = rows
-            .into_iter()
-            .map(|row| Episode {
-                id: row.get("id"),
-                podcast_id: row.get("podcast_id"),
-                podcast_name: row.get("podcast_name"),
-                title: row.get("title"),
-                description: row.get("description"),
-                episode_url: row.get("episode_url"),
-                published_date: row.get("published_date"),
-                duration: row.get("duration"),
-                file_size: row.get("file_size"),
-                local_file_path: row.get("local_file_path"),
-                status: row.get("status"),
-                downloaded: row.get("downloaded"),
-                on_device: row.get("on_device"),
-            })
-            .collect();
-
-        Ok(episodes)
+        self.with_device_ids(episodes).await
     }
 
     pub async fn update_episode_status(
@@ -285,14 +557,18 @@ impl DatabaseManager {
             status
         );
 
+        // listened_at keeps the first time an episode was marked listened, for retention
         sqlx::query(
             r#"
             UPDATE episodes 
-            SET status = ?, updated_at = CURRENT_TIMESTAMP 
+            SET status = ?,
+                listened_at = CASE WHEN ? = 'listened' THEN COALESCE(listened_at, CURRENT_TIMESTAMP) ELSE NULL END,
+                updated_at = CURRENT_TIMESTAMP 
             WHERE id = ?
         "#,
         )
         .bind(status)
+        .bind(status)
         .bind(episode_id)
         .execute(&self.pool)
         .await?;
@@ -328,6 +604,35 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Rewrites the local file path of several episodes in a single transaction
+    pub async fn update_local_file_paths(
+        &self,
+        paths: &[(i64, String)],
+    ) -> Result<(), PodPicoError> {
+        log::info!("Updating local file paths of {} episodes", paths.len());
+
+        let mut tx = self.pool.begin().await?;
+        for (episode_id, local_file_path) in paths {
+            sqlx::query(
+                r#"
+                UPDATE episodes 
+                SET local_file_path = ?, updated_at = CURRENT_TIMESTAMP 
+                WHERE id = ?
+            "#,
+            )
+            .bind(local_file_path)
+            .bind(episode_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Sets the legacy on-device flag, which stands for transfers made before
+    /// they were recorded per device. Transfers are tracked with
+    /// `record_episode_transfer`; an episode is on a device if either says so.
     pub async fn update_episode_on_device_status(
         &self,
         episode_id: i64,
@@ -354,27 +659,191 @@ impl DatabaseManager {
         Ok(())
     }
 
+    pub async fn update_episode_starred(
+        &self,
+        episode_id: i64,
+        starred: bool,
+    ) -> Result<(), PodPicoError> {
+        log::info!("Updating episode {} starred to: {}", episode_id, starred);
+
+        let result = sqlx::query(
+            r#"
+            UPDATE episodes 
+            SET starred = ?, updated_at = CURRENT_TIMESTAMP 
+            WHERE id = ?
+        "#,
+        )
+        .bind(starred)
+        .bind(episode_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(PodPicoError::EpisodeNotFound(episode_id));
+        }
+
+        Ok(())
+    }
+
+    /// Stores the URL of the episode's `podcast:chapters` document
+    pub async fn update_episode_chapters_url(
+        &self,
+        episode_id: i64,
+        chapters_url: &str,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query("UPDATE episodes SET chapters_url = ? WHERE id = ?")
+            .bind(chapters_url)
+            .bind(episode_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records the MIME type the feed declared for the episode's enclosure
+    pub async fn update_episode_enclosure_type(
+        &self,
+        episode_id: i64,
+        enclosure_type: &str,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query("UPDATE episodes SET enclosure_type = ? WHERE id = ?")
+            .bind(enclosure_type)
+            .bind(episode_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns all episodes that have a downloaded file, across all podcasts
+    pub async fn get_downloaded_episodes(&self) -> Result<Vec<Episode>, PodPicoError> {
+        let episodes = sqlx::query_as::<_, Episode>(&format!(
+            r#"
+            SELECT {columns}, {on_device} as on_device
+            FROM episodes e
+            JOIN podcasts p ON e.podcast_id = p.id
+            WHERE e.downloaded = true AND e.local_file_path IS NOT NULL
+            ORDER BY p.name, e.published_date DESC
+        "#,
+            columns = EPISODE_COLUMNS,
+            on_device = ON_DEVICE_SQL
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.with_device_ids(episodes).await
+    }
+
+    /// Returns every episode of every podcast, whatever its status
+    pub async fn get_all_episodes(&self) -> Result<Vec<Episode>, PodPicoError> {
+        let episodes = sqlx::query_as::<_, Episode>(&format!(
+            r#"
+            SELECT {columns}, {on_device} as on_device
+            FROM episodes e
+            JOIN podcasts p ON e.podcast_id = p.id
+            ORDER BY p.name, e.published_date DESC
+        "#,
+            columns = EPISODE_COLUMNS,
+            on_device = ON_DEVICE_SQL
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.with_device_ids(episodes).await
+    }
+
     #[allow(clippy::too_many_arguments)]
     /// User Story #11: Get episodes that are marked as on device
     /// Returns episodes with on_device = true for sync functionality
     pub async fn get_episodes_on_device(&self) -> Result<Vec<Episode>, PodPicoError> {
         log::info!("Getting episodes marked as on device (User Story #11)");
 
-        let episodes = sqlx::query_as::<_, Episode>(
+        let episodes = sqlx::query_as::<_, Episode>(&format!(
+            r#"
+            SELECT {columns}, {on_device} as on_device
+            FROM episodes e
+            JOIN podcasts p ON e.podcast_id = p.id
+            WHERE {on_device}
+            ORDER BY p.name, e.published_date DESC
+        "#,
+            columns = EPISODE_COLUMNS,
+            on_device = ON_DEVICE_SQL
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.with_device_ids(episodes).await
+    }
+
+    /// Episodes currently on the device with the given stable ID
+    pub async fn get_device_episodes(&self, device_id: &str) -> Result<Vec<Episode>, PodPicoError> {
+        let episodes = sqlx::query_as::<_, Episode>(&format!(
             r#"
-            SELECT 
-                e.id, e.podcast_id, p.name as podcast_name, e.title, e.description, 
-                e.episode_url, e.published_date, e.duration, e.file_size, 
-                e.local_file_path, e.status, e.downloaded, e.on_device
+            SELECT {columns}, true as on_device
             FROM episodes e
             JOIN podcasts p ON e.podcast_id = p.id
-            WHERE e.on_device = true
+            JOIN episode_transfers t ON t.episode_id = e.id AND t.removed_at IS NULL
+            JOIN usb_devices d ON d.id = t.device_id
+            WHERE d.stable_id = ?
             ORDER BY p.name, e.published_date DESC
+        "#,
+            columns = EPISODE_COLUMNS
+        ))
+        .bind(device_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.with_device_ids(episodes).await
+    }
+
+    /// Size in bytes of each episode on the device with the given stable ID,
+    /// as recorded when it was transferred
+    pub async fn get_device_transfer_sizes(
+        &self,
+        device_id: &str,
+    ) -> Result<HashMap<i64, u64>, PodPicoError> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT t.episode_id, t.file_size
+            FROM episode_transfers t
+            JOIN usb_devices d ON d.id = t.device_id
+            WHERE d.stable_id = ? AND t.removed_at IS NULL AND t.file_size IS NOT NULL
+        "#,
+        )
+        .bind(device_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(episode_id, size)| (episode_id, size.max(0) as u64))
+            .collect())
+    }
+
+    /// Fills in which devices each episode is on, by stable device ID
+    async fn with_device_ids(
+        &self,
+        mut episodes: Vec<Episode>,
+    ) -> Result<Vec<Episode>, PodPicoError> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            r#"
+            SELECT t.episode_id, d.stable_id
+            FROM episode_transfers t
+            JOIN usb_devices d ON d.id = t.device_id
+            WHERE t.removed_at IS NULL AND d.stable_id IS NOT NULL
+            ORDER BY d.stable_id
         "#,
         )
         .fetch_all(&self.pool)
         .await?;
 
+        let mut device_ids: HashMap<i64, Vec<String>> = HashMap::new();
+        for (episode_id, device_id) in rows {
+            device_ids.entry(episode_id).or_default().push(device_id);
+        }
+        for episode in &mut episodes {
+            episode.device_ids = device_ids.remove(&episode.id).unwrap_or_default();
+        }
         Ok(episodes)
     }
 
@@ -383,13 +852,14 @@ impl DatabaseManager {
     pub async fn get_on_device_episode_filenames(&self) -> Result<Vec<String>, PodPicoError> {
         log::info!("Getting on-device episode filenames for consistency check (User Story #11)");
 
-        let rows: Vec<(Option<String>,)> = sqlx::query_as(
+        let rows: Vec<(Option<String>,)> = sqlx::query_as(&format!(
             r#"
-            SELECT local_file_path 
-            FROM episodes 
-            WHERE on_device = true AND local_file_path IS NOT NULL
+            SELECT e.local_file_path
+            FROM episodes e
+            WHERE {on_device} AND e.local_file_path IS NOT NULL
         "#,
-        )
+            on_device = ON_DEVICE_SQL
+        ))
         .fetch_all(&self.pool)
         .await?;
 
@@ -442,171 +912,1253 @@ impl DatabaseManager {
         Ok(result.last_insert_rowid())
     }
 
-    /// User Story #12: Search for episodes within a podcast
-    /// Acceptance Criteria: Search results appear within 2 seconds with highlighted text
-    pub async fn search_episodes(
-        &self,
-        podcast_id: i64,
-        search_query: &str,
-    ) -> Result<Vec<Episode>, PodPicoError> {
-        log::info!(
-            "Searching episodes in podcast {} for query: '{}' (User Story #12)",
-            podcast_id,
-            search_query
-        );
+    /// Returns the enclosure URLs already stored for a podcast, used to detect new feed items
+    pub async fn get_episode_urls(&self, podcast_id: i64) -> Result<HashSet<String>, PodPicoError> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT episode_url FROM episodes WHERE podcast_id = ?")
+                .bind(podcast_id)
+                .fetch_all(&self.pool)
+                .await?;
 
-        // Use LIKE with wildcards for case-insensitive search across title and description
-        let search_pattern = format!("%{}%", search_query.to_lowercase());
+        Ok(rows.into_iter().map(|(url,)| url).collect())
+    }
 
-        let rows = sqlx::query(
+    pub async fn update_podcast_last_updated(&self, podcast_id: i64) -> Result<(), PodPicoError> {
+        sqlx::query(
             r#"
-            SELECT e.id, e.podcast_id, p.name as podcast_name, e.title, e.description, 
-                   e.episode_url, e.published_date, e.duration, e.file_size, 
-                   e.local_file_path, e.status, e.downloaded, e.on_device
-            FROM episodes e
-            JOIN podcasts p ON e.podcast_id = p.id
-            WHERE e.podcast_id = ? 
-              AND (LOWER(e.title) LIKE ? OR LOWER(e.description) LIKE ?)
-            ORDER BY e.published_date DESC
+            UPDATE podcasts
+            SET last_updated = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
         "#,
         )
         .bind(podcast_id)
-        .bind(&search_pattern)
-        .bind(&search_pattern)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stores the feed's podcast artwork URL; cached copies of a previous URL are dropped
+    pub async fn update_podcast_artwork_url(
+        &self,
+        podcast_id: i64,
+        artwork_url: Option<&str>,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query(
+            r#"
+            UPDATE podcasts
+            SET artwork_url = ?, artwork_path = NULL, artwork_thumbnail_path = NULL,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ? AND artwork_url IS NOT ?
+        "#,
+        )
+        .bind(artwork_url)
+        .bind(podcast_id)
+        .bind(artwork_url)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_podcast_artwork_cache(
+        &self,
+        podcast_id: i64,
+        artwork_path: &str,
+        thumbnail_path: &str,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query(
+            "UPDATE podcasts SET artwork_path = ?, artwork_thumbnail_path = ? WHERE id = ?",
+        )
+        .bind(artwork_path)
+        .bind(thumbnail_path)
+        .bind(podcast_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records the episode's own artwork (`itunes:image`) from the feed
+    pub async fn update_episode_artwork_url(
+        &self,
+        episode_id: i64,
+        artwork_url: &str,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query("UPDATE episodes SET artwork_url = ? WHERE id = ?")
+            .bind(artwork_url)
+            .bind(episode_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_episode_artwork_cache(
+        &self,
+        episode_id: i64,
+        artwork_path: &str,
+        thumbnail_path: &str,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query(
+            "UPDATE episodes SET artwork_path = ?, artwork_thumbnail_path = ? WHERE id = ?",
+        )
+        .bind(artwork_path)
+        .bind(thumbnail_path)
+        .bind(episode_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Looks up a single episode regardless of its status
+    pub async fn get_episode_by_id(&self, episode_id: i64) -> Result<Episode, PodPicoError> {
+        let episode = sqlx::query_as::<_, Episode>(&format!(
+            r#"
+            SELECT {columns}, {on_device} as on_device
+            FROM episodes e
+            JOIN podcasts p ON e.podcast_id = p.id
+            WHERE e.id = ?
+        "#,
+            columns = EPISODE_COLUMNS,
+            on_device = ON_DEVICE_SQL
+        ))
+        .bind(episode_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(PodPicoError::EpisodeNotFound(episode_id))?;
+
+        let mut episodes = self.with_device_ids(vec![episode]).await?;
+        Ok(episodes.remove(0))
+    }
+
+    /// Stores the duration (seconds), bitrate (bits per second) and sample rate
+    /// measured from the downloaded file
+    pub async fn update_episode_audio_properties(
+        &self,
+        episode_id: i64,
+        duration: i32,
+        bitrate: i32,
+        sample_rate: i32,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query(
+            "UPDATE episodes SET audio_duration = ?, audio_bitrate = ?, audio_sample_rate = ? WHERE id = ?",
+        )
+        .bind(duration)
+        .bind(bitrate)
+        .bind(sample_rate)
+        .bind(episode_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stores the EBU R128 loudness measured from the downloaded file
+    pub async fn update_episode_loudness(
+        &self,
+        episode_id: i64,
+        loudness_lufs: f64,
+        true_peak_dbtp: f64,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query("UPDATE episodes SET loudness_lufs = ?, true_peak_dbtp = ? WHERE id = ?")
+            .bind(loudness_lufs)
+            .bind(true_peak_dbtp)
+            .bind(episode_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records the duration of the copy prepared for devices, which differs
+    /// from the original when the podcast has a playback speed or silences
+    /// were trimmed, along with the seconds trimmed
+    pub async fn update_episode_device_duration(
+        &self,
+        episode_id: i64,
+        device_duration: Option<i32>,
+        trimmed_silence: Option<i32>,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query("UPDATE episodes SET device_duration = ?, trimmed_silence = ? WHERE id = ?")
+            .bind(device_duration)
+            .bind(trimmed_silence)
+            .bind(episode_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Listening time per podcast, using measured durations where available and
+    /// the feed's `itunes:duration` otherwise
+    pub async fn get_listening_stats(&self) -> Result<Vec<PodcastListeningStats>, PodPicoError> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT p.id as podcast_id, p.name as podcast_name,
+                   COUNT(e.id) as episode_count,
+                   COALESCE(SUM(COALESCE(e.audio_duration, e.duration)), 0) as total_seconds,
+                   COALESCE(SUM(CASE WHEN e.status = 'listened'
+                       THEN COALESCE(e.audio_duration, e.duration) END), 0) as listened_seconds,
+                   COALESCE(SUM(CASE WHEN e.status != 'listened' AND e.downloaded = true
+                       THEN COALESCE(e.audio_duration, e.duration) END), 0) as downloaded_unlistened_seconds,
+                   COALESCE(SUM(CASE WHEN {on_device}
+                       THEN COALESCE(e.audio_duration, e.duration) END), 0) as on_device_seconds,
+                   COUNT(e.id) - COUNT(COALESCE(e.audio_duration, e.duration)) as unknown_duration_count
+            FROM podcasts p
+            LEFT JOIN episodes e ON e.podcast_id = p.id
+            GROUP BY p.id, p.name
+            ORDER BY p.name
+        "#,
+            on_device = ON_DEVICE_SQL
+        ))
         .fetch_all(&self.pool)
         .await?;
 
-        let episodes: Vec<Episode> = rows
+        Ok(rows
             .into_iter()
-            .map(|row| Episode {
-                id: row.get("id"),
+            .map(|row| PodcastListeningStats {
                 podcast_id: row.get("podcast_id"),
                 podcast_name: row.get("podcast_name"),
-                title: row.get("title"),
-                description: row.get("description"),
+                episode_count: row.get("episode_count"),
+                total_seconds: row.get("total_seconds"),
+                listened_seconds: row.get("listened_seconds"),
+                downloaded_unlistened_seconds: row.get("downloaded_unlistened_seconds"),
+                on_device_seconds: row.get("on_device_seconds"),
+                unknown_duration_count: row.get("unknown_duration_count"),
+            })
+            .collect())
+    }
+
+    /// Records the start of a download attempt and returns its history id
+    pub async fn start_download_record(
+        &self,
+        episode_id: i64,
+        episode_url: &str,
+    ) -> Result<i64, PodPicoError> {
+        let result = sqlx::query("INSERT INTO downloads (episode_id, episode_url) VALUES (?, ?)")
+            .bind(episode_id)
+            .bind(episode_url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Completes a download history record; a download with an error is recorded as failed
+    pub async fn finish_download_record(
+        &self,
+        download_id: i64,
+        downloaded_bytes: i64,
+        total_bytes: Option<i64>,
+        average_speed_bps: f64,
+        error: Option<&str>,
+    ) -> Result<(), PodPicoError> {
+        let status = if error.is_some() {
+            "failed"
+        } else {
+            "completed"
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE downloads
+            SET finished_at = CURRENT_TIMESTAMP, downloaded_bytes = ?, total_bytes = ?,
+                average_speed_bps = ?, status = ?, error = ?
+            WHERE id = ?
+        "#,
+        )
+        .bind(downloaded_bytes)
+        .bind(total_bytes)
+        .bind(average_speed_bps)
+        .bind(status)
+        .bind(error)
+        .bind(download_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Most recent download attempts first, optionally for a single episode
+    pub async fn get_download_history(
+        &self,
+        episode_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<DownloadRecord>, PodPicoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT d.id, d.episode_id, e.title AS episode_title, p.name AS podcast_name,
+                   d.episode_url, d.started_at, d.finished_at, d.downloaded_bytes,
+                   d.total_bytes, d.average_speed_bps, d.status, d.error
+            FROM downloads d
+            LEFT JOIN episodes e ON d.episode_id = e.id
+            LEFT JOIN podcasts p ON e.podcast_id = p.id
+            WHERE ? IS NULL OR d.episode_id = ?
+            ORDER BY d.started_at DESC, d.id DESC
+            LIMIT ?
+        "#,
+        )
+        .bind(episode_id)
+        .bind(episode_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DownloadRecord {
+                id: row.get("id"),
+                episode_id: row.get("episode_id"),
+                episode_title: row.get("episode_title"),
+                podcast_name: row.get("podcast_name"),
                 episode_url: row.get("episode_url"),
-                published_date: row.get("published_date"),
-                duration: row.get("duration"),
-                file_size: row.get("file_size"),
-                local_file_path: row.get("local_file_path"),
+                started_at: row.get("started_at"),
+                finished_at: row.get("finished_at"),
+                downloaded_bytes: row.get("downloaded_bytes"),
+                total_bytes: row.get("total_bytes"),
+                average_speed_bps: row.get("average_speed_bps"),
                 status: row.get("status"),
-                downloaded: row.get("downloaded"),
-                on_device: row.get("on_device"),
+                error: row.get("error"),
             })
-            .collect();
+            .collect())
+    }
+
+    /// Returns the auto-download rule stored for a podcast, if any
+    pub async fn get_auto_download_rule(
+        &self,
+        podcast_id: i64,
+    ) -> Result<Option<AutoDownloadRule>, PodPicoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT podcast_id, mode, latest_count, title_pattern, max_age_days, max_size_bytes
+            FROM podcast_download_rules
+            WHERE podcast_id = ?
+        "#,
+        )
+        .bind(podcast_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| AutoDownloadRule {
+            podcast_id: row.get("podcast_id"),
+            mode: row.get("mode"),
+            latest_count: row.get("latest_count"),
+            title_pattern: row.get("title_pattern"),
+            max_age_days: row.get("max_age_days"),
+            max_size_bytes: row.get("max_size_bytes"),
+        }))
+    }
 
+    pub async fn set_auto_download_rule(
+        &self,
+        rule: &AutoDownloadRule,
+    ) -> Result<(), PodPicoError> {
         log::info!(
-            "Found {} episodes matching search query '{}' (User Story #12)",
-            episodes.len(),
-            search_query
+            "Setting auto-download rule for podcast {}: {}",
+            rule.podcast_id,
+            rule.mode
         );
 
-        Ok(episodes)
+        sqlx::query(
+            r#"
+            INSERT INTO podcast_download_rules
+                (podcast_id, mode, latest_count, title_pattern, max_age_days, max_size_bytes)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(podcast_id) DO UPDATE SET
+                mode = excluded.mode,
+                latest_count = excluded.latest_count,
+                title_pattern = excluded.title_pattern,
+                max_age_days = excluded.max_age_days,
+                max_size_bytes = excluded.max_size_bytes
+        "#,
+        )
+        .bind(rule.podcast_id)
+        .bind(&rule.mode)
+        .bind(rule.latest_count)
+        .bind(&rule.title_pattern)
+        .bind(rule.max_age_days)
+        .bind(rule.max_size_bytes)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub async fn get_transcode_profile(
+        &self,
+        device_id: &str,
+    ) -> Result<Option<TranscodeProfile>, PodPicoError> {
+        let row = sqlx::query(
+            "SELECT device_id, codec, bitrate_kbps, mono FROM device_transcode_profiles WHERE device_id = ?",
+        )
+        .bind(device_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| TranscodeProfile {
+            device_id: row.get("device_id"),
+            codec: row.get("codec"),
+            bitrate_kbps: row.get("bitrate_kbps"),
+            mono: row.get("mono"),
+        }))
+    }
+
+    pub async fn set_transcode_profile(
+        &self,
+        profile: &TranscodeProfile,
+    ) -> Result<(), PodPicoError> {
+        log::info!(
+            "Setting transcode profile for device {}: {} {} kbps",
+            profile.device_id,
+            profile.codec,
+            profile.bitrate_kbps
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO device_transcode_profiles (device_id, codec, bitrate_kbps, mono)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(device_id) DO UPDATE SET
+                codec = excluded.codec,
+                bitrate_kbps = excluded.bitrate_kbps,
+                mono = excluded.mono
+        "#,
+        )
+        .bind(&profile.device_id)
+        .bind(&profile.codec)
+        .bind(profile.bitrate_kbps)
+        .bind(profile.mono)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_device_profile(
+        &self,
+        device_id: &str,
+    ) -> Result<Option<DeviceProfile>, PodPicoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT device_id, root_folder, podcast_subfolders, filename_template,
+                   max_path_length, allowed_codecs, fat32, character_set
+            FROM device_profiles WHERE device_id = ?
+        "#,
+        )
+        .bind(device_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| DeviceProfile {
+            device_id: row.get("device_id"),
+            root_folder: row.get("root_folder"),
+            podcast_subfolders: row.get("podcast_subfolders"),
+            filename_template: row.get("filename_template"),
+            max_path_length: row.get("max_path_length"),
+            allowed_codecs: row
+                .get::<String, _>("allowed_codecs")
+                .split(',')
+                .filter(|codec| !codec.is_empty())
+                .map(str::to_string)
+                .collect(),
+            fat32: row.get("fat32"),
+            character_set: row.get("character_set"),
+        }))
+    }
+
+    pub async fn set_device_profile(&self, profile: &DeviceProfile) -> Result<(), PodPicoError> {
+        log::info!(
+            "Setting device profile for device {}: {}/{}",
+            profile.device_id,
+            profile.root_folder,
+            profile.filename_template
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO device_profiles (device_id, root_folder, podcast_subfolders, filename_template,
+                                         max_path_length, allowed_codecs, fat32, character_set)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(device_id) DO UPDATE SET
+                root_folder = excluded.root_folder,
+                podcast_subfolders = excluded.podcast_subfolders,
+                filename_template = excluded.filename_template,
+                max_path_length = excluded.max_path_length,
+                allowed_codecs = excluded.allowed_codecs,
+                fat32 = excluded.fat32,
+                character_set = excluded.character_set
+        "#,
+        )
+        .bind(&profile.device_id)
+        .bind(&profile.root_folder)
+        .bind(profile.podcast_subfolders)
+        .bind(&profile.filename_template)
+        .bind(profile.max_path_length)
+        .bind(profile.allowed_codecs.join(","))
+        .bind(profile.fat32)
+        .bind(&profile.character_set)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_sync_rules(&self, device_id: &str) -> Result<Vec<SyncRule>, PodPicoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT kind, podcast_id, count, fill_percent
+            FROM device_sync_rules
+            WHERE device_id = ?
+            ORDER BY position
+        "#,
+        )
+        .bind(device_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SyncRule {
+                kind: row.get("kind"),
+                podcast_id: row.get("podcast_id"),
+                count: row.get("count"),
+                fill_percent: row.get("fill_percent"),
+            })
+            .collect())
+    }
+
+    /// Replaces a device's sync rules; their order is their priority
+    pub async fn set_sync_rules(
+        &self,
+        device_id: &str,
+        rules: &[SyncRule],
+    ) -> Result<(), PodPicoError> {
+        log::info!(
+            "Setting {} sync rules for device {}",
+            rules.len(),
+            device_id
+        );
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM device_sync_rules WHERE device_id = ?")
+            .bind(device_id)
+            .execute(&mut *tx)
+            .await?;
+        for (position, rule) in rules.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO device_sync_rules (device_id, position, kind, podcast_id, count, fill_percent)
+                VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            )
+            .bind(device_id)
+            .bind(position as i64)
+            .bind(&rule.kind)
+            .bind(rule.podcast_id)
+            .bind(rule.count)
+            .bind(rule.fill_percent)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Returns the row id of `device`, found by its stable ID so it is the same
+    /// wherever the device is mounted. Devices are added on first use; known
+    /// ones get their current name, mount point and connection time.
+    pub async fn register_usb_device(&self, device: &UsbDevice) -> Result<i64, PodPicoError> {
+        // Rows from before stable IDs are matched by their mount point once
+        let existing: Option<(i64,)> = sqlx::query_as(
+            r#"
+            SELECT id FROM usb_devices
+            WHERE stable_id = ? OR (stable_id IS NULL AND device_path = ?)
+            ORDER BY stable_id IS NULL
+            LIMIT 1
+        "#,
+        )
+        .bind(&device.id)
+        .bind(&device.path)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some((id,)) = existing {
+            sqlx::query(
+                r#"
+                UPDATE usb_devices
+                SET stable_id = ?, device_name = ?, device_path = ?, filesystem_uuid = ?,
+                    label = ?, last_connected = CURRENT_TIMESTAMP
+                WHERE id = ?
+            "#,
+            )
+            .bind(&device.id)
+            .bind(&device.name)
+            .bind(&device.path)
+            .bind(&device.filesystem_uuid)
+            .bind(&device.label)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+            self.adopt_legacy_device_settings(device).await?;
+            return Ok(id);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO usb_devices (stable_id, device_name, device_path, filesystem_uuid, label)
+            VALUES (?, ?, ?, ?, ?)
+        "#,
+        )
+        .bind(&device.id)
+        .bind(&device.name)
+        .bind(&device.path)
+        .bind(&device.filesystem_uuid)
+        .bind(&device.label)
+        .execute(&self.pool)
+        .await?;
+        self.adopt_legacy_device_settings(device).await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Row id of the device with the given stable ID, if it was ever registered
+    pub async fn find_usb_device(&self, stable_id: &str) -> Result<Option<i64>, PodPicoError> {
+        let row: Option<(i64,)> = sqlx::query_as("SELECT id FROM usb_devices WHERE stable_id = ?")
+            .bind(stable_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|(id,)| id))
+    }
+
+    /// Where the device with the given stable ID was last mounted; `None` once
+    /// another device has been seen at that mount point since
+    pub async fn get_usb_device_path(
+        &self,
+        stable_id: &str,
+    ) -> Result<Option<String>, PodPicoError> {
+        let row: Option<(String,)> = sqlx::query_as(
+            r#"
+            SELECT d.device_path FROM usb_devices d
+            WHERE d.stable_id = ? AND NOT EXISTS (
+                SELECT 1 FROM usb_devices o
+                WHERE o.device_path = d.device_path AND o.id != d.id
+                  AND o.last_connected > d.last_connected
+            )
+        "#,
+        )
+        .bind(stable_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(path,)| path))
+    }
+
+    /// Moves a transcode profile saved under the device's ID from before stable
+    /// IDs over to its stable ID, unless the stable ID has one already
+    async fn adopt_legacy_device_settings(&self, device: &UsbDevice) -> Result<(), PodPicoError> {
+        if device.legacy_id.is_empty() || device.legacy_id == device.id {
+            return Ok(());
+        }
+        sqlx::query(
+            "UPDATE OR IGNORE device_transcode_profiles SET device_id = ? WHERE device_id = ?",
+        )
+        .bind(&device.id)
+        .bind(&device.legacy_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records that an episode was transferred to a device as the given files
+    /// (`file_size` bytes in total), replacing any earlier transfer of it.
+    /// Split episodes are one transfer with a segment per file.
+    pub async fn record_episode_transfer(
+        &self,
+        episode_id: i64,
+        device_id: i64,
+        files_on_device: &[String],
+        file_size: i64,
+    ) -> Result<(), PodPicoError> {
+        let mut tx = self.pool.begin().await?;
+
+        let (transfer_id,): (i64,) = sqlx::query_as(
+            r#"
+            INSERT INTO episode_transfers (episode_id, device_id, file_path_on_device, file_size)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(episode_id, device_id) DO UPDATE SET
+                transferred_at = CURRENT_TIMESTAMP,
+                file_path_on_device = excluded.file_path_on_device,
+                file_size = excluded.file_size,
+                removed_at = NULL
+            RETURNING id
+        "#,
+        )
+        .bind(episode_id)
+        .bind(device_id)
+        .bind(files_on_device.first())
+        .bind(file_size)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM episode_transfer_segments WHERE transfer_id = ?")
+            .bind(transfer_id)
+            .execute(&mut *tx)
+            .await?;
+        if files_on_device.len() > 1 {
+            for (index, file) in files_on_device.iter().enumerate() {
+                sqlx::query(
+                    "INSERT INTO episode_transfer_segments (transfer_id, segment_number, file_path_on_device) VALUES (?, ?, ?)",
+                )
+                .bind(transfer_id)
+                .bind(index as i64 + 1)
+                .bind(file)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Files an episode was transferred to a device as, in segment order;
+    /// empty when it isn't recorded as being on the device
+    pub async fn get_episode_transfer_files(
+        &self,
+        episode_id: i64,
+        device_id: i64,
+    ) -> Result<Vec<String>, PodPicoError> {
+        let rows: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT t.file_path_on_device, s.file_path_on_device
+            FROM episode_transfers t
+            LEFT JOIN episode_transfer_segments s ON s.transfer_id = t.id
+            WHERE t.episode_id = ? AND t.device_id = ? AND t.removed_at IS NULL
+            ORDER BY s.segment_number
+        "#,
+        )
+        .bind(episode_id)
+        .bind(device_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(file, segment)| segment.or(file))
+            .collect())
+    }
+
+    /// Records that an episode was removed from a device. The transfer row is
+    /// kept with its removal time.
+    pub async fn record_episode_removal(
+        &self,
+        episode_id: i64,
+        device_id: i64,
+    ) -> Result<(), PodPicoError> {
+        sqlx::query(
+            "UPDATE episode_transfers SET removed_at = CURRENT_TIMESTAMP WHERE episode_id = ? AND device_id = ? AND removed_at IS NULL",
+        )
+            .bind(episode_id)
+            .bind(device_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// User Story #12: Search for episodes within a podcast
+    /// Acceptance Criteria: Search results appear within 2 seconds with highlighted text
+    pub async fn search_episodes(
+        &self,
+        podcast_id: i64,
+        search_query: &str,
+    ) -> Result<Vec<Episode>, PodPicoError> {
+        log::info!(
+            "Searching episodes in podcast {} for query: '{}' (User Story #12)",
+            podcast_id,
+            search_query
+        );
+
+        // Use LIKE with wildcards for case-insensitive search across title and description
+        let search_pattern = format!("%{}%", search_query.to_lowercase());
+
+        let episodes = sqlx::query_as::<_, Episode>(&format!(
+            r#"
+            SELECT {columns}, {on_device} as on_device
+            FROM episodes e
+            JOIN podcasts p ON e.podcast_id = p.id
+            WHERE e.podcast_id = ? 
+              AND (LOWER(e.title) LIKE ? OR LOWER(e.description) LIKE ?)
+            ORDER BY e.published_date DESC
+        "#,
+            columns = EPISODE_COLUMNS,
+            on_device = ON_DEVICE_SQL
+        ))
+        .bind(podcast_id)
+        .bind(&search_pattern)
+        .bind(&search_pattern)
+        .fetch_all(&self.pool)
+        .await?;
+
+        log::info!(
+            "Found {} episodes matching search query '{}' (User Story #12)",
+            episodes.len(),
+            search_query
+        );
+
+        self.with_device_ids(episodes).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_test_db() -> DatabaseManager {
+        // Use in-memory SQLite database for testing
+        let db_url = "sqlite::memory:";
+
+        let db = DatabaseManager::new(db_url).await.unwrap();
+        db.initialize().await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_database_initialization() {
+        // Test that database tables are created correctly
+        let db = create_test_db().await;
+
+        // Verify tables exist by attempting basic operations
+        let podcasts = db.get_podcasts().await.unwrap();
+        assert_eq!(podcasts.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_user_story_1_add_podcast() {
+        // User Story #1: Add new podcast subscription via RSS URL
+        let db = create_test_db().await;
+
+        let podcast = db
+            .add_podcast(
+                "Test Podcast",
+                "https://example.com/feed.xml",
+                Some("A test podcast"),
+                Some("https://example.com/art.jpg"),
+                Some("https://example.com"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(podcast.name, "Test Podcast");
+        assert_eq!(podcast.rss_url, "https://example.com/feed.xml");
+        assert_eq!(podcast.description, Some("A test podcast".to_string()));
+        assert_eq!(
+            podcast.artwork_url,
+            Some("https://example.com/art.jpg".to_string())
+        );
+        assert_eq!(podcast.website_url, Some("https://example.com".to_string()));
+        assert_eq!(podcast.episode_count, 0);
+        assert_eq!(podcast.new_episode_count, 0);
+        assert!(podcast.id > 0);
+    }
+
+    #[tokio::test]
+    async fn test_add_podcast_duplicate_url() {
+        // Test that duplicate RSS URLs are rejected
+        let db = create_test_db().await;
+
+        // Add first podcast
+        db.add_podcast(
+            "Test Podcast 1",
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Try to add duplicate URL
+        let result = db
+            .add_podcast(
+                "Test Podcast 2",
+                "https://example.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_podcasts() {
+        // User Story #2, #7: Get all podcasts with episode counts
+        let db = create_test_db().await;
+
+        // Add multiple podcasts
+        let _podcast1 = db
+            .add_podcast(
+                "Podcast 1",
+                "https://example1.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let _podcast2 = db
+            .add_podcast(
+                "Podcast 2",
+                "https://example2.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let podcasts = db.get_podcasts().await.unwrap();
+        assert_eq!(podcasts.len(), 2);
+
+        // Should be sorted by name
+        assert_eq!(podcasts[0].name, "Podcast 1");
+        assert_eq!(podcasts[1].name, "Podcast 2");
+    }
+
+    #[tokio::test]
+    async fn test_user_story_4_remove_podcast() {
+        // User Story #4: Remove podcast subscriptions
+        let db = create_test_db().await;
+
+        let podcast = db
+            .add_podcast(
+                "Test Podcast",
+                "https://example.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Add an episode to test cascade deletion
+        let _episode_id = db
+            .add_episode(
+                podcast.id,
+                "Test Episode",
+                Some("Description"),
+                "https://example.com/episode.mp3",
+                Some("2023-01-01T00:00:00Z"),
+                Some(1800),
+                Some(25000000),
+            )
+            .await
+            .unwrap();
+
+        // Remove podcast
+        db.remove_podcast(podcast.id).await.unwrap();
+
+        // Verify podcast is removed
+        let podcasts = db.get_podcasts().await.unwrap();
+        assert_eq!(podcasts.len(), 0);
+
+        // Verify episodes are cascade deleted
+        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        assert_eq!(episodes.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_nonexistent_podcast() {
+        let db = create_test_db().await;
+
+        // Removing non-existent podcast should not error (returns 0 affected rows)
+        let result = db.remove_podcast(999).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_add_episode() {
+        let db = create_test_db().await;
+
+        let podcast = db
+            .add_podcast(
+                "Test Podcast",
+                "https://example.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let episode_id = db
+            .add_episode(
+                podcast.id,
+                "Test Episode",
+                Some("Episode description"),
+                "https://example.com/episode.mp3",
+                Some("2023-01-01T00:00:00Z"),
+                Some(1800),     // 30 minutes
+                Some(25000000), // ~25MB
+            )
+            .await
+            .unwrap();
+
+        assert!(episode_id > 0);
+
+        // Verify episode was added
+        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        assert_eq!(episodes.len(), 1);
+
+        let episode = &episodes[0];
+        assert_eq!(episode.title, "Test Episode");
+        assert_eq!(episode.description, Some("Episode description".to_string()));
+        assert_eq!(episode.episode_url, "https://example.com/episode.mp3");
+        assert_eq!(episode.duration, Some(1800));
+        assert_eq!(episode.file_size, Some(25000000));
+        assert_eq!(episode.status, "new");
+        assert!(!episode.downloaded);
+        assert!(!episode.on_device);
+    }
+
+    #[tokio::test]
+    async fn test_user_story_2_get_episodes_by_podcast() {
+        // User Story #2: View all episodes of specific podcast
+        let db = create_test_db().await;
+
+        let podcast1 = db
+            .add_podcast(
+                "Podcast 1",
+                "https://example1.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let podcast2 = db
+            .add_podcast(
+                "Podcast 2",
+                "https://example2.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Add episodes to podcast 1
+        db.add_episode(
+            podcast1.id,
+            "Episode 1-1",
+            None,
+            "https://example.com/ep1.mp3",
+            Some("2023-01-01T00:00:00Z"),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        db.add_episode(
+            podcast1.id,
+            "Episode 1-2",
+            None,
+            "https://example.com/ep2.mp3",
+            Some("2023-01-02T00:00:00Z"),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Add episode to podcast 2
+        db.add_episode(
+            podcast2.id,
+            "Episode 2-1",
+            None,
+            "https://example.com/ep3.mp3",
+            Some("2023-01-03T00:00:00Z"),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Get episodes for podcast 1 only
+        let episodes = db.get_episodes(Some(podcast1.id)).await.unwrap();
+        assert_eq!(episodes.len(), 2);
+
+        // Should be ordered by published date DESC
+        assert_eq!(episodes[0].title, "Episode 1-2");
+        assert_eq!(episodes[1].title, "Episode 1-1");
+
+        // All episodes should be from podcast 1
+        for episode in &episodes {
+            assert_eq!(episode.podcast_id, podcast1.id);
+            assert_eq!(episode.podcast_name, "Podcast 1");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_story_7_get_all_new_episodes() {
+        // User Story #7: View all new episodes across podcasts (Combined Inbox)
+        let db = create_test_db().await;
+
+        let podcast1 = db
+            .add_podcast(
+                "Podcast 1",
+                "https://example1.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let podcast2 = db
+            .add_podcast(
+                "Podcast 2",
+                "https://example2.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Add episodes with different statuses
+        db.add_episode(
+            podcast1.id,
+            "New Episode 1",
+            None,
+            "https://example.com/ep1.mp3",
+            Some("2023-01-03T00:00:00Z"),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let _episode2_id = db
+            .add_episode(
+                podcast2.id,
+                "New Episode 2",
+                None,
+                "https://example.com/ep2.mp3",
+                Some("2023-01-02T00:00:00Z"),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let episode3_id = db
+            .add_episode(
+                podcast1.id,
+                "Old Episode",
+                None,
+                "https://example.com/ep3.mp3",
+                Some("2023-01-01T00:00:00Z"),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Mark one episode as listened
+        db.update_episode_status(episode3_id, "listened")
+            .await
+            .unwrap();
 
-    async fn create_test_db() -> DatabaseManager {
-        // Use in-memory SQLite database for testing
-        let db_url = "sqlite::memory:";
+        // Get all new episodes (no podcast filter)
+        let new_episodes = db.get_episodes(None).await.unwrap();
+        assert_eq!(new_episodes.len(), 2); // Only episodes with "new" status
 
-        let db = DatabaseManager::new(db_url).await.unwrap();
-        db.initialize().await.unwrap();
-        db
-    }
+        // Should be ordered by published date DESC
+        assert_eq!(new_episodes[0].title, "New Episode 1");
+        assert_eq!(new_episodes[1].title, "New Episode 2");
 
-    #[tokio::test]
-    async fn test_database_initialization() {
-        // Test that database tables are created correctly
-        let db = create_test_db().await;
+        // Episodes should have correct podcast names
+        assert_eq!(new_episodes[0].podcast_name, "Podcast 1");
+        assert_eq!(new_episodes[1].podcast_name, "Podcast 2");
 
-        // Verify tables exist by attempting basic operations
-        let podcasts = db.get_podcasts().await.unwrap();
-        assert_eq!(podcasts.len(), 0);
+        // All should have "new" status
+        for episode in &new_episodes {
+            assert_eq!(episode.status, "new");
+        }
     }
 
     #[tokio::test]
-    async fn test_user_story_1_add_podcast() {
-        // User Story #1: Add new podcast subscription via RSS URL
+    async fn test_user_story_5_update_episode_status() {
+        // User Story #5: Mark episodes as "listened"
         let db = create_test_db().await;
 
         let podcast = db
             .add_podcast(
                 "Test Podcast",
                 "https://example.com/feed.xml",
-                Some("A test podcast"),
-                Some("https://example.com/art.jpg"),
-                Some("https://example.com"),
+                None,
+                None,
+                None,
             )
             .await
             .unwrap();
-
-        assert_eq!(podcast.name, "Test Podcast");
-        assert_eq!(podcast.rss_url, "https://example.com/feed.xml");
-        assert_eq!(podcast.description, Some("A test podcast".to_string()));
-        assert_eq!(
-            podcast.artwork_url,
-            Some("https://example.com/art.jpg".to_string())
-        );
-        assert_eq!(podcast.website_url, Some("https://example.com".to_string()));
-        assert_eq!(podcast.episode_count, 0);
-        assert_eq!(podcast.new_episode_count, 0);
-        assert!(podcast.id > 0);
-    }
-
-    #[tokio::test]
-    async fn test_add_podcast_duplicate_url() {
-        // Test that duplicate RSS URLs are rejected
-        let db = create_test_db().await;
-
-        // Add first podcast
-        db.add_podcast(
-            "Test Podcast 1",
-            "https://example.com/feed.xml",
-            None,
-            None,
-            None,
-        )
-        .await
-        .unwrap();
-
-        // Try to add duplicate URL
-        let result = db
-            .add_podcast(
-                "Test Podcast 2",
-                "https://example.com/feed.xml",
+        let episode_id = db
+            .add_episode(
+                podcast.id,
+                "Test Episode",
+                None,
+                "https://example.com/ep.mp3",
                 None,
                 None,
                 None,
             )
-            .await;
+            .await
+            .unwrap();
 
-        assert!(result.is_err());
+        // Initially should be "new"
+        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        assert_eq!(episodes[0].status, "new");
+
+        // Update to "listened"
+        db.update_episode_status(episode_id, "listened")
+            .await
+            .unwrap();
+
+        // Verify status changed
+        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        assert_eq!(episodes[0].status, "listened");
+
+        // Update to "unlistened"
+        db.update_episode_status(episode_id, "unlistened")
+            .await
+            .unwrap();
+
+        // Verify status changed again
+        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        assert_eq!(episodes[0].status, "unlistened");
     }
 
     #[tokio::test]
-    async fn test_get_podcasts() {
-        // User Story #2, #7: Get all podcasts with episode counts
+    async fn test_listened_at_and_starred_tracking() {
         let db = create_test_db().await;
 
-        // Add multiple podcasts
-        let _podcast1 = db
+        let podcast = db
             .add_podcast(
-                "Podcast 1",
-                "https://example1.com/feed.xml",
+                "Test Podcast",
+                "https://example.com/feed.xml",
                 None,
                 None,
                 None,
             )
             .await
             .unwrap();
-        let _podcast2 = db
-            .add_podcast(
-                "Podcast 2",
-                "https://example2.com/feed.xml",
+        let episode_id = db
+            .add_episode(
+                podcast.id,
+                "Test Episode",
+                None,
+                "https://example.com/ep.mp3",
                 None,
                 None,
                 None,
@@ -614,23 +2166,38 @@ mod tests {
             .await
             .unwrap();
 
-        let podcasts = db.get_podcasts().await.unwrap();
-        assert_eq!(podcasts.len(), 2);
+        db.update_episode_status(episode_id, "listened")
+            .await
+            .unwrap();
+        db.update_episode_starred(episode_id, true).await.unwrap();
 
-        // Should be sorted by name
-        assert_eq!(podcasts[0].name, "Podcast 1");
-        assert_eq!(podcasts[1].name, "Podcast 2");
+        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        assert!(episodes[0].listened_at.is_some());
+        assert!(episodes[0].starred);
+
+        db.update_episode_status(episode_id, "unlistened")
+            .await
+            .unwrap();
+        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        assert!(episodes[0].listened_at.is_none());
+
+        let result = db.update_episode_starred(999, true).await;
+        assert!(matches!(result, Err(PodPicoError::EpisodeNotFound(999))));
     }
 
     #[tokio::test]
-    async fn test_user_story_4_remove_podcast() {
-        // User Story #4: Remove podcast subscriptions
+    async fn test_download_history() {
         let db = create_test_db().await;
-
         let podcast = db
-            .add_podcast(
-                "Test Podcast",
-                "https://example.com/feed.xml",
+            .add_podcast("History", "https://example.com/feed.xml", None, None, None)
+            .await
+            .unwrap();
+        let episode_id = db
+            .add_episode(
+                podcast.id,
+                "Flaky Episode",
+                None,
+                "https://example.com/flaky.mp3",
                 None,
                 None,
                 None,
@@ -638,299 +2205,463 @@ mod tests {
             .await
             .unwrap();
 
-        // Add an episode to test cascade deletion
-        let _episode_id = db
+        let failed = db
+            .start_download_record(episode_id, "https://example.com/flaky.mp3")
+            .await
+            .unwrap();
+        db.finish_download_record(failed, 512, Some(2048), 256.0, Some("HTTP error 503"))
+            .await
+            .unwrap();
+        let completed = db
+            .start_download_record(episode_id, "https://example.com/flaky.mp3")
+            .await
+            .unwrap();
+        db.finish_download_record(completed, 2048, Some(2048), 1024.0, None)
+            .await
+            .unwrap();
+        let interrupted = db
+            .start_download_record(episode_id, "https://example.com/flaky.mp3")
+            .await
+            .unwrap();
+
+        // Simulates a restart while the last download was running
+        db.initialize().await.unwrap();
+
+        let history = db.get_download_history(Some(episode_id), 10).await.unwrap();
+        assert_eq!(
+            history.iter().map(|record| record.id).collect::<Vec<_>>(),
+            vec![interrupted, completed, failed]
+        );
+        assert_eq!(history[0].status, "failed");
+        assert_eq!(
+            history[0].error.as_deref(),
+            Some("Interrupted by application exit")
+        );
+        assert_eq!(history[1].status, "completed");
+        assert_eq!(history[1].downloaded_bytes, 2048);
+        assert_eq!(history[1].average_speed_bps, Some(1024.0));
+        assert_eq!(history[2].error.as_deref(), Some("HTTP error 503"));
+        assert_eq!(history[2].episode_title.as_deref(), Some("Flaky Episode"));
+        assert_eq!(history[2].podcast_name.as_deref(), Some("History"));
+        assert!(history[2].finished_at.is_some());
+
+        assert_eq!(db.get_download_history(None, 1).await.unwrap().len(), 1);
+        assert!(db
+            .get_download_history(Some(episode_id + 1), 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_download_history_outlives_removed_podcast() {
+        // A database from before the history outlived its episodes
+        let db = create_test_db().await;
+        sqlx::query("DROP TABLE downloads")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            CREATE TABLE downloads (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                episode_id INTEGER NOT NULL,
+                episode_url TEXT NOT NULL,
+                started_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                finished_at DATETIME,
+                downloaded_bytes INTEGER DEFAULT 0,
+                total_bytes INTEGER,
+                average_speed_bps REAL,
+                status TEXT CHECK(status IN ('in_progress', 'completed', 'failed')) DEFAULT 'in_progress',
+                error TEXT,
+                FOREIGN KEY (episode_id) REFERENCES episodes (id) ON DELETE CASCADE
+            )
+        "#,
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        let podcast = db
+            .add_podcast("Gone", "https://example.com/gone.xml", None, None, None)
+            .await
+            .unwrap();
+        let episode_id = db
             .add_episode(
                 podcast.id,
-                "Test Episode",
-                Some("Description"),
-                "https://example.com/episode.mp3",
-                Some("2023-01-01T00:00:00Z"),
-                Some(1800),
-                Some(25000000),
+                "Gone Episode",
+                None,
+                "https://example.com/gone.mp3",
+                None,
+                None,
+                None,
             )
             .await
             .unwrap();
+        let download = db
+            .start_download_record(episode_id, "https://example.com/gone.mp3")
+            .await
+            .unwrap();
+        db.finish_download_record(download, 2048, Some(2048), 1024.0, None)
+            .await
+            .unwrap();
 
-        // Remove podcast
+        db.initialize().await.unwrap();
         db.remove_podcast(podcast.id).await.unwrap();
+        assert!(db.get_episode_by_id(episode_id).await.is_err());
+
+        let history = db.get_download_history(None, 10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, download);
+        assert_eq!(history[0].episode_title, None);
+        assert_eq!(history[0].podcast_name, None);
+        assert_eq!(history[0].episode_url, "https://example.com/gone.mp3");
+        assert_eq!(history[0].status, "completed");
+    }
 
-        // Verify podcast is removed
-        let podcasts = db.get_podcasts().await.unwrap();
-        assert_eq!(podcasts.len(), 0);
+    fn usb_device(id: &str, path: &str) -> UsbDevice {
+        UsbDevice {
+            id: id.to_string(),
+            name: "PLAYER".to_string(),
+            path: path.to_string(),
+            total_space: 1_000_000,
+            available_space: 500_000,
+            is_connected: true,
+            filesystem_uuid: Some(id.to_string()),
+            label: Some("PLAYER".to_string()),
+            legacy_id: format!("PLAYER_{}", path.replace('/', "_")),
+        }
+    }
 
-        // Verify episodes are cascade deleted
-        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
-        assert_eq!(episodes.len(), 0);
+    #[tokio::test]
+    async fn test_device_keeps_its_row_across_mount_points() {
+        let db = create_test_db().await;
+        let first = db
+            .register_usb_device(&usb_device("1A2B-3C4D", "/media/user/PLAYER"))
+            .await
+            .unwrap();
+        let remounted = db
+            .register_usb_device(&usb_device("1A2B-3C4D", "/run/media/user/PLAYER1"))
+            .await
+            .unwrap();
+        assert_eq!(first, remounted);
+
+        // An identical stick at the old mount point is a different device
+        let twin = db
+            .register_usb_device(&usb_device("5E6F-7A8B", "/media/user/PLAYER"))
+            .await
+            .unwrap();
+        assert_ne!(twin, first);
+
+        let (path,): (String,) = sqlx::query_as("SELECT device_path FROM usb_devices WHERE id = ?")
+            .bind(first)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(path, "/run/media/user/PLAYER1");
+    }
+
+    #[tokio::test]
+    async fn test_device_path_is_where_it_was_last_seen() {
+        let db = create_test_db().await;
+        db.register_usb_device(&usb_device("1A2B-3C4D", "/media/user/PLAYER"))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_usb_device_path("1A2B-3C4D")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("/media/user/PLAYER")
+        );
+        assert_eq!(db.get_usb_device_path("unknown").await.unwrap(), None);
+
+        // Another device mounted at the same place since
+        db.register_usb_device(&usb_device("5E6F-7A8B", "/media/user/PLAYER"))
+            .await
+            .unwrap();
+        sqlx::query("UPDATE usb_devices SET last_connected = '2025-01-01 00:00:00' WHERE stable_id = '1A2B-3C4D'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(db.get_usb_device_path("1A2B-3C4D").await.unwrap(), None);
+        assert!(db.get_usb_device_path("5E6F-7A8B").await.unwrap().is_some());
     }
 
-    #[tokio::test]
-    async fn test_remove_nonexistent_podcast() {
-        let db = create_test_db().await;
+    #[tokio::test]
+    async fn test_legacy_transcode_profile_moves_to_stable_id() {
+        let db = create_test_db().await;
+        let device = usb_device("1A2B-3C4D", "/media/user/PLAYER");
+        assert_eq!(device.legacy_id, "PLAYER__media_user_PLAYER");
+        db.set_transcode_profile(&TranscodeProfile {
+            device_id: device.legacy_id.clone(),
+            codec: "mp3".to_string(),
+            bitrate_kbps: 48,
+            mono: true,
+        })
+        .await
+        .unwrap();
+
+        db.register_usb_device(&device).await.unwrap();
 
-        // Removing non-existent podcast should not error (returns 0 affected rows)
-        let result = db.remove_podcast(999).await;
-        assert!(result.is_ok());
+        let profile = db
+            .get_transcode_profile("1A2B-3C4D")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(profile.bitrate_kbps, 48);
+        assert!(db
+            .get_transcode_profile(&device.legacy_id)
+            .await
+            .unwrap()
+            .is_none());
     }
 
     #[tokio::test]
-    async fn test_add_episode() {
+    async fn test_split_transfer_is_one_record() {
         let db = create_test_db().await;
-
         let podcast = db
-            .add_podcast(
-                "Test Podcast",
-                "https://example.com/feed.xml",
+            .add_podcast("Split", "https://example.com/feed.xml", None, None, None)
+            .await
+            .unwrap();
+        let episode = db
+            .add_episode(
+                podcast.id,
+                "Three Hours",
+                None,
+                "https://example.com/1.mp3",
                 None,
                 None,
                 None,
             )
             .await
             .unwrap();
-
-        let episode_id = db
-            .add_episode(
-                podcast.id,
-                "Test Episode",
-                Some("Episode description"),
-                "https://example.com/episode.mp3",
-                Some("2023-01-01T00:00:00Z"),
-                Some(1800),     // 30 minutes
-                Some(25000000), // ~25MB
-            )
+        let device = db
+            .register_usb_device(&usb_device("1A2B-3C4D", "/media/player"))
             .await
             .unwrap();
 
-        assert!(episode_id > 0);
+        let segments = vec![
+            "Three Hours - 01.mp3".to_string(),
+            "Three Hours - 02.mp3".to_string(),
+        ];
+        db.record_episode_transfer(episode, device, &segments, 2_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_episode_transfer_files(episode, device)
+                .await
+                .unwrap(),
+            segments
+        );
+        let (transfers,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM episode_transfers")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(transfers, 1);
 
-        // Verify episode was added
-        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
-        assert_eq!(episodes.len(), 1);
+        // Transferring again unsplit replaces the segments
+        let whole = vec!["Three Hours.mp3".to_string()];
+        db.record_episode_transfer(episode, device, &whole, 2_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_episode_transfer_files(episode, device)
+                .await
+                .unwrap(),
+            whole
+        );
 
-        let episode = &episodes[0];
-        assert_eq!(episode.title, "Test Episode");
-        assert_eq!(episode.description, Some("Episode description".to_string()));
-        assert_eq!(episode.episode_url, "https://example.com/episode.mp3");
-        assert_eq!(episode.duration, Some(1800));
-        assert_eq!(episode.file_size, Some(25000000));
-        assert_eq!(episode.status, "new");
-        assert!(!episode.downloaded);
-        assert!(!episode.on_device);
+        db.record_episode_removal(episode, device).await.unwrap();
+        assert!(db
+            .get_episode_transfer_files(episode, device)
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
-    async fn test_user_story_2_get_episodes_by_podcast() {
-        // User Story #2: View all episodes of specific podcast
+    async fn test_on_device_is_tracked_per_device() {
         let db = create_test_db().await;
-
-        let podcast1 = db
-            .add_podcast(
-                "Podcast 1",
-                "https://example1.com/feed.xml",
-                None,
-                None,
-                None,
-            )
+        let podcast = db
+            .add_podcast("Devices", "https://example.com/feed.xml", None, None, None)
             .await
             .unwrap();
-        let podcast2 = db
-            .add_podcast(
-                "Podcast 2",
-                "https://example2.com/feed.xml",
+        let episode = db
+            .add_episode(
+                podcast.id,
+                "Shared",
+                None,
+                "https://example.com/1.mp3",
                 None,
                 None,
                 None,
             )
             .await
             .unwrap();
+        let car = db
+            .register_usb_device(&usb_device("1A2B-3C4D", "/media/car"))
+            .await
+            .unwrap();
+        let player = db
+            .register_usb_device(&usb_device("5E6F-7A8B", "/media/player"))
+            .await
+            .unwrap();
 
-        // Add episodes to podcast 1
-        db.add_episode(
-            podcast1.id,
-            "Episode 1-1",
-            None,
-            "https://example.com/ep1.mp3",
-            Some("2023-01-01T00:00:00Z"),
-            None,
-            None,
-        )
-        .await
-        .unwrap();
-        db.add_episode(
-            podcast1.id,
-            "Episode 1-2",
-            None,
-            "https://example.com/ep2.mp3",
-            Some("2023-01-02T00:00:00Z"),
-            None,
-            None,
-        )
-        .await
-        .unwrap();
+        let files = vec!["Shared.mp3".to_string()];
+        db.record_episode_transfer(episode, car, &files, 1_000)
+            .await
+            .unwrap();
+        db.record_episode_transfer(episode, player, &files, 1_000)
+            .await
+            .unwrap();
+        db.record_episode_removal(episode, car).await.unwrap();
 
-        // Add episode to podcast 2
-        db.add_episode(
-            podcast2.id,
-            "Episode 2-1",
-            None,
-            "https://example.com/ep3.mp3",
-            Some("2023-01-03T00:00:00Z"),
-            None,
-            None,
+        let stored = db.get_episode_by_id(episode).await.unwrap();
+        assert!(stored.on_device);
+        assert_eq!(stored.device_ids, vec!["5E6F-7A8B".to_string()]);
+        assert!(db
+            .get_device_episodes("1A2B-3C4D")
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(db.get_device_episodes("5E6F-7A8B").await.unwrap().len(), 1);
+
+        // The removal is kept in the history
+        let (removed,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM episode_transfers WHERE removed_at IS NOT NULL AND file_size = 1000",
         )
+        .fetch_one(&db.pool)
         .await
         .unwrap();
+        assert_eq!(removed, 1);
 
-        // Get episodes for podcast 1 only
-        let episodes = db.get_episodes(Some(podcast1.id)).await.unwrap();
-        assert_eq!(episodes.len(), 2);
-
-        // Should be ordered by published date DESC
-        assert_eq!(episodes[0].title, "Episode 1-2");
-        assert_eq!(episodes[1].title, "Episode 1-1");
-
-        // All episodes should be from podcast 1
-        for episode in &episodes {
-            assert_eq!(episode.podcast_id, podcast1.id);
-            assert_eq!(episode.podcast_name, "Podcast 1");
-        }
+        db.record_episode_removal(episode, player).await.unwrap();
+        let stored = db.get_episode_by_id(episode).await.unwrap();
+        assert!(!stored.on_device);
+        assert!(stored.device_ids.is_empty());
+        assert!(db.get_episodes_on_device().await.unwrap().is_empty());
     }
 
     #[tokio::test]
-    async fn test_user_story_7_get_all_new_episodes() {
-        // User Story #7: View all new episodes across podcasts (Combined Inbox)
+    async fn test_podcast_playback_speed() {
         let db = create_test_db().await;
-
-        let podcast1 = db
-            .add_podcast(
-                "Podcast 1",
-                "https://example1.com/feed.xml",
-                None,
-                None,
-                None,
-            )
-            .await
-            .unwrap();
-        let podcast2 = db
-            .add_podcast(
-                "Podcast 2",
-                "https://example2.com/feed.xml",
-                None,
-                None,
-                None,
-            )
+        let podcast = db
+            .add_podcast("Fast", "https://example.com/feed.xml", None, None, None)
             .await
             .unwrap();
+        assert_eq!(podcast.playback_speed, 1.0);
 
-        // Add episodes with different statuses
-        db.add_episode(
-            podcast1.id,
-            "New Episode 1",
-            None,
-            "https://example.com/ep1.mp3",
-            Some("2023-01-03T00:00:00Z"),
-            None,
-            None,
-        )
-        .await
-        .unwrap();
-        let _episode2_id = db
-            .add_episode(
-                podcast2.id,
-                "New Episode 2",
-                None,
-                "https://example.com/ep2.mp3",
-                Some("2023-01-02T00:00:00Z"),
-                None,
-                None,
-            )
+        db.set_podcast_playback_speed(podcast.id, 1.5)
             .await
             .unwrap();
-        let episode3_id = db
+        assert_eq!(
+            db.get_podcast_by_id(podcast.id)
+                .await
+                .unwrap()
+                .playback_speed,
+            1.5
+        );
+        assert!(matches!(
+            db.set_podcast_playback_speed(999, 1.5).await,
+            Err(PodPicoError::PodcastNotFound(999))
+        ));
+
+        let episode = db
             .add_episode(
-                podcast1.id,
-                "Old Episode",
+                podcast.id,
+                "Long Talk",
                 None,
-                "https://example.com/ep3.mp3",
-                Some("2023-01-01T00:00:00Z"),
+                "https://example.com/1.mp3",
                 None,
+                Some(3600),
                 None,
             )
             .await
             .unwrap();
-
-        // Mark one episode as listened
-        db.update_episode_status(episode3_id, "listened")
+        db.update_episode_device_duration(episode, Some(2300), Some(100))
             .await
             .unwrap();
-
-        // Get all new episodes (no podcast filter)
-        let new_episodes = db.get_episodes(None).await.unwrap();
-        assert_eq!(new_episodes.len(), 2); // Only episodes with "new" status
-
-        // Should be ordered by published date DESC
-        assert_eq!(new_episodes[0].title, "New Episode 1");
-        assert_eq!(new_episodes[1].title, "New Episode 2");
-
-        // Episodes should have correct podcast names
-        assert_eq!(new_episodes[0].podcast_name, "Podcast 1");
-        assert_eq!(new_episodes[1].podcast_name, "Podcast 2");
-
-        // All should have "new" status
-        for episode in &new_episodes {
-            assert_eq!(episode.status, "new");
-        }
+        let stored = db.get_episode_by_id(episode).await.unwrap();
+        assert_eq!(stored.device_duration, Some(2300));
+        assert_eq!(stored.trimmed_silence, Some(100));
     }
 
     #[tokio::test]
-    async fn test_user_story_5_update_episode_status() {
-        // User Story #5: Mark episodes as "listened"
+    async fn test_listening_stats_prefer_measured_duration() {
         let db = create_test_db().await;
-
         let podcast = db
-            .add_podcast(
-                "Test Podcast",
-                "https://example.com/feed.xml",
-                None,
-                None,
-                None,
-            )
+            .add_podcast("Stats", "https://example.com/feed.xml", None, None, None)
             .await
             .unwrap();
-        let episode_id = db
-            .add_episode(
-                podcast.id,
-                "Test Episode",
-                None,
-                "https://example.com/ep.mp3",
-                None,
-                None,
-                None,
-            )
+        let mut ids = Vec::new();
+        for (title, duration) in [
+            ("Listened", Some(600)),
+            ("Queued", Some(1200)),
+            ("Unknown", None),
+        ] {
+            let url = format!("https://example.com/{}.mp3", title);
+            ids.push(
+                db.add_episode(podcast.id, title, None, &url, None, duration, None)
+                    .await
+                    .unwrap(),
+            );
+        }
+        db.update_episode_status(ids[0], "listened").await.unwrap();
+        db.update_episode_downloaded_status(ids[1], true, Some("/episodes/queued.mp3"))
             .await
             .unwrap();
-
-        // Initially should be "new"
-        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
-        assert_eq!(episodes[0].status, "new");
-
-        // Update to "listened"
-        db.update_episode_status(episode_id, "listened")
+        // The feed claimed 20 minutes; the file is 25
+        db.update_episode_audio_properties(ids[1], 1500, 64_000, 44_100)
             .await
             .unwrap();
 
-        // Verify status changed
-        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
-        assert_eq!(episodes[0].status, "listened");
+        let episode = db.get_episode_by_id(ids[1]).await.unwrap();
+        assert_eq!(episode.duration, Some(1200));
+        assert_eq!(episode.audio_duration, Some(1500));
+        assert_eq!(episode.audio_bitrate, Some(64_000));
+        assert_eq!(episode.audio_sample_rate, Some(44_100));
+        assert_eq!(episode.playback_duration(), Some(1500));
+
+        let stats = db.get_listening_stats().await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].episode_count, 3);
+        assert_eq!(stats[0].total_seconds, 2100);
+        assert_eq!(stats[0].listened_seconds, 600);
+        assert_eq!(stats[0].downloaded_unlistened_seconds, 1500);
+        assert_eq!(stats[0].on_device_seconds, 0);
+        assert_eq!(stats[0].unknown_duration_count, 1);
+
+        assert!(matches!(
+            db.get_episode_by_id(999).await,
+            Err(PodPicoError::EpisodeNotFound(999))
+        ));
+    }
 
-        // Update to "unlistened"
-        db.update_episode_status(episode_id, "unlistened")
-            .await
-            .unwrap();
+    #[tokio::test]
+    async fn test_initialize_upgrades_existing_episodes_table() {
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE episodes (id INTEGER PRIMARY KEY, status TEXT, updated_at DATETIME)",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO episodes (status, updated_at) VALUES ('listened', '2023-01-01 00:00:00')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
 
-        // Verify status changed again
-        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
-        assert_eq!(episodes[0].status, "unlistened");
+        db.initialize().await.unwrap();
+
+        let (starred, listened_at): (bool, Option<String>) =
+            sqlx::query_as("SELECT starred, listened_at FROM episodes")
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+        assert!(!starred);
+        assert_eq!(listened_at.as_deref(), Some("2023-01-01 00:00:00"));
     }
 
     #[tokio::test]
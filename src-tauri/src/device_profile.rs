@@ -0,0 +1,333 @@
+// Device profile module for PodPico
+// Lays out episode files on a device and checks that the device can store and play them
+
+use crate::audio_format::codec_for_extension;
+use crate::commands::{DeviceProfile, Episode};
+use crate::error::PodPicoError;
+use crate::file_manager::{
+    render_filename_template, sanitize_path_component, validate_filename_template,
+};
+use std::path::Path;
+
+pub const DEFAULT_DEVICE_ROOT_FOLDER: &str = "PodPico";
+
+/// Uses the same placeholders as download filename templates and must end in `.{ext}`
+pub const DEFAULT_DEVICE_FILENAME_TEMPLATE: &str = "{title}.{ext}";
+
+/// Longest path, in characters and relative to the device root, most players handle
+pub const DEFAULT_MAX_PATH_LENGTH: i64 = 255;
+
+/// Characters kept in folder and file names: `strict` keeps letters, digits,
+/// spaces, `-` and `_`, `ascii` additionally drops non-ASCII letters and `fat`
+/// only replaces what FAT filesystems forbid
+pub const CHARACTER_SETS: &[&str] = &["strict", "ascii", "fat"];
+
+pub const DEVICE_CODECS: &[&str] = &["mp3", "aac", "opus", "vorbis", "flac", "wav"];
+
+/// Largest file a FAT32 filesystem can store
+pub const FAT32_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024 - 1;
+
+/// File names are never shortened below this to fit the path length limit
+const MIN_FILE_STEM_CHARS: usize = 8;
+
+impl DeviceProfile {
+    /// The layout devices got before they had profiles: every episode in
+    /// `PodPico/`, named after its title
+    pub fn default_for(device_id: &str) -> Self {
+        Self {
+            device_id: device_id.to_string(),
+            root_folder: DEFAULT_DEVICE_ROOT_FOLDER.to_string(),
+            podcast_subfolders: false,
+            filename_template: DEFAULT_DEVICE_FILENAME_TEMPLATE.to_string(),
+            max_path_length: DEFAULT_MAX_PATH_LENGTH,
+            allowed_codecs: Vec::new(),
+            fat32: true,
+            character_set: "strict".to_string(),
+        }
+    }
+
+    /// Path of an episode file relative to the device root, with `/` separators.
+    /// `part` is the segment number and count of split episodes. The file name
+    /// is shortened when the path would exceed the profile's length limit.
+    pub fn episode_path(
+        &self,
+        episode: &Episode,
+        basename: &str,
+        part: Option<(usize, usize)>,
+        extension: &str,
+    ) -> Result<String, PodPicoError> {
+        let template = if self.podcast_subfolders {
+            format!("{{podcast}}/{}", self.filename_template)
+        } else {
+            self.filename_template.clone()
+        };
+        let rendered = render_filename_template(&template, episode, basename, extension);
+
+        let mut components: Vec<String> = rendered
+            .iter()
+            .map(|component| component.to_string_lossy().to_string())
+            .collect();
+        let file_name = components.pop().unwrap_or_default();
+        let stem = file_name
+            .strip_suffix(&format!(".{}", extension))
+            .unwrap_or(&file_name);
+        let stem = self.restrict(stem);
+        let suffix = match part {
+            Some((number, count)) if count > 1 => format!(" - {:02}", number),
+            _ => String::new(),
+        };
+
+        let folders: String = self
+            .root_folder
+            .split('/')
+            .filter(|folder| !folder.is_empty())
+            .map(str::to_string)
+            .chain(components.iter().map(|folder| self.restrict(folder)))
+            .map(|folder| format!("{}/", folder))
+            .collect();
+        let fixed_chars =
+            folders.chars().count() + suffix.chars().count() + extension.chars().count() + 1;
+        let max_chars = usize::try_from(self.max_path_length).unwrap_or_default();
+        if fixed_chars + MIN_FILE_STEM_CHARS > max_chars {
+            return Err(PodPicoError::FileTransferFailed(format!(
+                "Folder '{}' leaves no room for file names within the device's {} character path limit",
+                folders, max_chars
+            )));
+        }
+
+        let stem: String = stem.chars().take(max_chars - fixed_chars).collect();
+        Ok(format!(
+            "{}{}{}.{}",
+            folders,
+            stem.trim_end(),
+            suffix,
+            extension
+        ))
+    }
+
+    /// Checks that the device plays the file's format and its filesystem can
+    /// hold a file that size
+    pub fn check_file(&self, path: &Path) -> Result<(), PodPicoError> {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !self.allowed_codecs.is_empty() {
+            let codec = codec_for_extension(&extension).unwrap_or("unknown");
+            if !self.allowed_codecs.iter().any(|allowed| allowed == codec) {
+                return Err(PodPicoError::UnsupportedFormat(format!(
+                    "Device {} doesn't play {} files ({}); set a transcode profile that converts them",
+                    self.device_id, codec, extension
+                )));
+            }
+        }
+
+        let size = std::fs::metadata(path)?.len();
+        if self.fat32 && size > FAT32_MAX_FILE_SIZE {
+            return Err(PodPicoError::FileTransferFailed(format!(
+                "{} is {} bytes, larger than a FAT32 device can store",
+                path.display(),
+                size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Applies the profile's character set to one folder or file name
+    fn restrict(&self, name: &str) -> String {
+        let keep = |c: char| match self.character_set.as_str() {
+            "fat" => true,
+            "ascii" => c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_',
+            _ => c.is_alphanumeric() || c == ' ' || c == '-' || c == '_',
+        };
+        let restricted: String = name
+            .chars()
+            .map(|c| if keep(c) { c } else { '_' })
+            .collect();
+        restricted.trim().to_string()
+    }
+}
+
+pub fn validate_device_profile(profile: &DeviceProfile) -> Result<(), PodPicoError> {
+    for folder in profile.root_folder.split('/').filter(|f| !f.is_empty()) {
+        if folder == "." || folder == ".." || sanitize_path_component(folder, false) != folder {
+            return Err(PodPicoError::InvalidFilenameTemplate(format!(
+                "root folder '{}' contains characters devices can't store",
+                profile.root_folder
+            )));
+        }
+    }
+
+    validate_filename_template(&profile.filename_template)?;
+    if !profile.filename_template.ends_with(".{ext}") {
+        return Err(PodPicoError::InvalidFilenameTemplate(
+            "device filename templates must end in '.{ext}'".to_string(),
+        ));
+    }
+    if !profile.filename_template.contains("{title}")
+        && !profile.filename_template.contains("{episode_id}")
+    {
+        return Err(PodPicoError::InvalidFilenameTemplate(
+            "device filename templates need {title} or {episode_id} to tell episodes apart"
+                .to_string(),
+        ));
+    }
+
+    if !(32..=1024).contains(&profile.max_path_length) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Maximum path length must be between 32 and 1024 characters, got {}",
+            profile.max_path_length
+        )));
+    }
+    if let Some(codec) = profile
+        .allowed_codecs
+        .iter()
+        .find(|codec| !DEVICE_CODECS.contains(&codec.as_str()))
+    {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Codec must be one of {}, got '{}'",
+            DEVICE_CODECS.join(", "),
+            codec
+        )));
+    }
+    if !CHARACTER_SETS.contains(&profile.character_set.as_str()) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Character set must be one of {}, got '{}'",
+            CHARACTER_SETS.join(", "),
+            profile.character_set
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(title: &str) -> Episode {
+        Episode {
+            id: 7,
+            podcast_id: 1,
+            podcast_name: "Tech: Weekly".to_string(),
+            title: title.to_string(),
+            description: None,
+            episode_url: "https://example.com/ep7.mp3".to_string(),
+            published_date: Some("Tue, 03 Jun 2025 10:00:00 GMT".to_string()),
+            duration: None,
+            file_size: None,
+            local_file_path: None,
+            status: "new".to_string(),
+            downloaded: true,
+            on_device: false,
+            starred: false,
+            listened_at: None,
+            enclosure_type: None,
+            artwork_url: None,
+            artwork_path: None,
+            artwork_thumbnail_path: None,
+            audio_duration: None,
+            audio_bitrate: None,
+            audio_sample_rate: None,
+            loudness_lufs: None,
+            true_peak_dbtp: None,
+            device_duration: None,
+            trimmed_silence: None,
+            chapters_url: None,
+            device_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_default_profile_keeps_flat_layout() {
+        let profile = DeviceProfile::default_for("player");
+        let episode = episode("Rust & You: Part 1");
+
+        assert_eq!(
+            profile.episode_path(&episode, "ep7", None, "mp3").unwrap(),
+            "PodPico/Rust _ You_ Part 1.mp3"
+        );
+        assert_eq!(
+            profile
+                .episode_path(&episode, "ep7", Some((2, 3)), "mp3")
+                .unwrap(),
+            "PodPico/Rust _ You_ Part 1 - 02.mp3"
+        );
+    }
+
+    #[test]
+    fn test_profile_layout_and_path_limit() {
+        let profile = DeviceProfile {
+            root_folder: "Audio/Podcasts".to_string(),
+            podcast_subfolders: true,
+            filename_template: "{date} {title}.{ext}".to_string(),
+            max_path_length: 50,
+            character_set: "ascii".to_string(),
+            ..DeviceProfile::default_for("player")
+        };
+
+        let path = profile
+            .episode_path(
+                &episode("Café culture and a very long title"),
+                "ep7",
+                None,
+                "m4a",
+            )
+            .unwrap();
+        assert_eq!(path, "Audio/Podcasts/Tech_ Weekly/2025-06-03 Caf_ cu.m4a");
+        assert_eq!(path.chars().count(), 50);
+
+        let cramped = DeviceProfile {
+            max_path_length: 32,
+            ..profile
+        };
+        assert!(cramped
+            .episode_path(&episode("Title"), "ep7", None, "m4a")
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_file_rejects_unplayable_codecs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("episode.opus");
+        std::fs::write(&path, b"OggS").unwrap();
+
+        let mut profile = DeviceProfile::default_for("player");
+        assert!(profile.check_file(&path).is_ok());
+        profile.allowed_codecs = vec!["mp3".to_string(), "aac".to_string()];
+        assert!(matches!(
+            profile.check_file(&path),
+            Err(PodPicoError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_device_profile() {
+        let profile = DeviceProfile::default_for("player");
+        assert!(validate_device_profile(&profile).is_ok());
+
+        for invalid in [
+            DeviceProfile {
+                root_folder: "../outside".to_string(),
+                ..profile.clone()
+            },
+            DeviceProfile {
+                filename_template: "{podcast}.{ext}".to_string(),
+                ..profile.clone()
+            },
+            DeviceProfile {
+                filename_template: "{title}".to_string(),
+                ..profile.clone()
+            },
+            DeviceProfile {
+                allowed_codecs: vec!["wma".to_string()],
+                ..profile.clone()
+            },
+            DeviceProfile {
+                character_set: "latin1".to_string(),
+                ..profile.clone()
+            },
+        ] {
+            assert!(validate_device_profile(&invalid).is_err());
+        }
+    }
+}
@@ -0,0 +1,378 @@
+// Device sync module for PodPico
+// Works out which episodes a device should hold from its sync rules and what
+// has to be copied or removed to get there
+
+use crate::audio_format::codec_for_extension;
+use crate::commands::{DeviceProfile, Episode, SyncItem, SyncRule, TranscodeProfile};
+use crate::device_profile::FAT32_MAX_FILE_SIZE;
+use crate::episode_manager::parse_published_date;
+use crate::error::PodPicoError;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// `latest_unlistened` keeps the newest unlistened episodes of each podcast,
+/// `starred` every starred episode and `fill` tops the device up to a share of
+/// its capacity with the newest unlistened episodes of any podcast
+pub const SYNC_RULE_KINDS: &[&str] = &["latest_unlistened", "starred", "fill"];
+
+/// Changes a sync makes to one device
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// Episodes to copy, in priority order
+    pub copies: Vec<SyncItem>,
+    pub removals: Vec<SyncItem>,
+    pub skipped: Vec<SyncItem>,
+    /// Free space on the device once the removals and copies are done
+    pub projected_free_space: u64,
+}
+
+pub fn validate_sync_rule(rule: &SyncRule) -> Result<(), PodPicoError> {
+    match rule.kind.as_str() {
+        "latest_unlistened" if rule.count.is_none_or(|count| count < 1) => Err(
+            PodPicoError::Generic("Latest unlistened rules need a count of at least 1".to_string()),
+        ),
+        "fill"
+            if rule
+                .fill_percent
+                .is_none_or(|percent| !(1..=100).contains(&percent)) =>
+        {
+            Err(PodPicoError::Generic(
+                "Fill rules need a fill_percent between 1 and 100".to_string(),
+            ))
+        }
+        kind if !SYNC_RULE_KINDS.contains(&kind) => Err(PodPicoError::Generic(format!(
+            "Sync rule kind must be one of {}, got '{}'",
+            SYNC_RULE_KINDS.join(", "),
+            kind
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Why the device can't take an episode, judged from its downloaded file and
+/// the device's profiles; `None` when it can
+pub fn unsupported_reason(
+    episode: &Episode,
+    size: u64,
+    profile: &DeviceProfile,
+    transcode: &TranscodeProfile,
+) -> Option<String> {
+    let extension = episode
+        .local_file_path
+        .as_deref()
+        .and_then(|path| Path::new(path).extension())
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let codec = if transcode.is_enabled() {
+        transcode.codec.as_str()
+    } else {
+        codec_for_extension(&extension).unwrap_or("unknown")
+    };
+    if !profile.allowed_codecs.is_empty()
+        && !profile
+            .allowed_codecs
+            .iter()
+            .any(|allowed| allowed == codec)
+    {
+        return Some(format!("unsupported format: device doesn't play {}", codec));
+    }
+    if profile.fat32 && !transcode.is_enabled() && size > FAT32_MAX_FILE_SIZE {
+        return Some("file too large for a FAT32 device".to_string());
+    }
+    None
+}
+
+/// Plans a sync of `device_id` given every episode in the library. Episodes
+/// the rules no longer want are removed first; wanted episodes are then copied
+/// in rule order while they fit. Episodes already on the device stay there.
+pub fn plan_sync(
+    rules: &[SyncRule],
+    episodes: &[Episode],
+    device_id: &str,
+    total_space: u64,
+    available_space: u64,
+    size_of: impl Fn(&Episode) -> u64,
+    unsupported: impl Fn(&Episode) -> Option<String>,
+) -> SyncPlan {
+    let on_device = |episode: &Episode| episode.device_ids.iter().any(|id| id == device_id);
+    let wanted = wanted_episodes(
+        rules,
+        episodes,
+        &on_device,
+        total_space,
+        available_space,
+        &size_of,
+    );
+    let wanted_ids: HashSet<i64> = wanted.iter().map(|(episode, _)| episode.id).collect();
+
+    let mut plan = SyncPlan::default();
+    let mut free_space = available_space;
+    for episode in episodes
+        .iter()
+        .filter(|episode| on_device(episode) && !wanted_ids.contains(&episode.id))
+    {
+        let size = size_of(episode);
+        free_space = free_space.saturating_add(size);
+        plan.removals.push(sync_item(
+            episode,
+            size,
+            "no longer wanted by any sync rule".to_string(),
+        ));
+    }
+
+    for (episode, reason) in wanted {
+        if on_device(episode) {
+            continue;
+        }
+        let size = size_of(episode);
+        let skip_reason = if !episode.downloaded || episode.local_file_path.is_none() {
+            Some("not downloaded".to_string())
+        } else if let Some(reason) = unsupported(episode) {
+            Some(reason)
+        } else if size > free_space {
+            Some(format!(
+                "insufficient space: needs {} bytes, {} bytes free",
+                size, free_space
+            ))
+        } else {
+            None
+        };
+
+        match skip_reason {
+            Some(skip_reason) => plan.skipped.push(sync_item(
+                episode,
+                size,
+                format!("{} ({})", skip_reason, reason),
+            )),
+            None => {
+                free_space -= size;
+                plan.copies.push(sync_item(episode, size, reason));
+            }
+        }
+    }
+
+    plan.projected_free_space = free_space;
+    plan
+}
+
+/// Episodes the rules ask for, in priority order, with the rule that asked
+fn wanted_episodes<'a>(
+    rules: &[SyncRule],
+    episodes: &'a [Episode],
+    on_device: &dyn Fn(&Episode) -> bool,
+    total_space: u64,
+    available_space: u64,
+    size_of: &dyn Fn(&Episode) -> u64,
+) -> Vec<(&'a Episode, String)> {
+    let mut newest_first: Vec<&Episode> = episodes.iter().collect();
+    newest_first.sort_by_key(|episode| {
+        let published = episode
+            .published_date
+            .as_deref()
+            .and_then(parse_published_date);
+        std::cmp::Reverse((published, episode.id))
+    });
+    let unlistened = newest_first
+        .iter()
+        .copied()
+        .filter(|episode| episode.status != "listened");
+
+    let mut wanted: Vec<(&Episode, String)> = Vec::new();
+    let mut seen = HashSet::new();
+    for rule in rules {
+        let count = rule.count.unwrap_or_default().max(0) as usize;
+        let selected: Vec<(&Episode, String)> = match rule.kind.as_str() {
+            "latest_unlistened" => {
+                let mut per_podcast: HashMap<i64, usize> = HashMap::new();
+                unlistened
+                    .clone()
+                    .filter(|episode| rule.podcast_id.is_none_or(|id| id == episode.podcast_id))
+                    .filter(|episode| {
+                        let taken = per_podcast.entry(episode.podcast_id).or_default();
+                        *taken += 1;
+                        *taken <= count
+                    })
+                    .map(|episode| (episode, format!("latest {} unlistened", count)))
+                    .collect()
+            }
+            "starred" => newest_first
+                .iter()
+                .filter(|episode| episode.starred)
+                .map(|episode| (*episode, "starred".to_string()))
+                .collect(),
+            "fill" => {
+                // Space the device is already using for other files counts
+                // towards the target
+                let percent = rule.fill_percent.unwrap_or_default().clamp(0, 100) as u64;
+                let target = total_space / 100 * percent;
+                let ours_on_device: u64 = episodes
+                    .iter()
+                    .filter(|episode| on_device(episode))
+                    .map(size_of)
+                    .sum();
+                let mut used = total_space
+                    .saturating_sub(available_space)
+                    .saturating_sub(ours_on_device)
+                    + wanted
+                        .iter()
+                        .map(|(episode, _)| size_of(episode))
+                        .sum::<u64>();
+
+                let mut filled = Vec::new();
+                for episode in unlistened
+                    .clone()
+                    .filter(|episode| episode.downloaded && !seen.contains(&episode.id))
+                {
+                    let size = size_of(episode);
+                    if used + size <= target {
+                        used += size;
+                        filled.push((episode, format!("fill to {}%", percent)));
+                    }
+                }
+                filled
+            }
+            _ => Vec::new(),
+        };
+
+        for (episode, reason) in selected {
+            if seen.insert(episode.id) {
+                wanted.push((episode, reason));
+            }
+        }
+    }
+    wanted
+}
+
+fn sync_item(episode: &Episode, size: u64, reason: String) -> SyncItem {
+    SyncItem {
+        episode_id: episode.id,
+        title: episode.title.clone(),
+        podcast_name: episode.podcast_name.clone(),
+        size_bytes: size,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(id: i64, podcast_id: i64, day: u32) -> Episode {
+        Episode {
+            id,
+            podcast_id,
+            podcast_name: format!("Podcast {}", podcast_id),
+            title: format!("Episode {}", id),
+            description: None,
+            episode_url: format!("https://example.com/{}.mp3", id),
+            published_date: Some(format!("2025-06-{:02}T10:00:00Z", day)),
+            duration: None,
+            file_size: Some(100),
+            local_file_path: Some(format!("/downloads/{}.mp3", id)),
+            status: "unlistened".to_string(),
+            downloaded: true,
+            on_device: false,
+            starred: false,
+            listened_at: None,
+            enclosure_type: None,
+            artwork_url: None,
+            artwork_path: None,
+            artwork_thumbnail_path: None,
+            audio_duration: None,
+            audio_bitrate: None,
+            audio_sample_rate: None,
+            loudness_lufs: None,
+            true_peak_dbtp: None,
+            device_duration: None,
+            trimmed_silence: None,
+            chapters_url: None,
+            device_ids: Vec::new(),
+        }
+    }
+
+    fn rule(kind: &str, count: Option<i64>, fill_percent: Option<i64>) -> SyncRule {
+        SyncRule {
+            kind: kind.to_string(),
+            podcast_id: None,
+            count,
+            fill_percent,
+        }
+    }
+
+    fn ids(items: &[SyncItem]) -> Vec<i64> {
+        items.iter().map(|item| item.episode_id).collect()
+    }
+
+    fn size(episode: &Episode) -> u64 {
+        episode.file_size.unwrap_or_default() as u64
+    }
+
+    #[test]
+    fn test_plan_copies_latest_and_removes_unwanted() {
+        let mut episodes = vec![
+            episode(1, 1, 1),
+            episode(2, 1, 2),
+            episode(3, 1, 3),
+            episode(4, 2, 1),
+        ];
+        episodes[0].device_ids = vec!["player".to_string()];
+        episodes[2].device_ids = vec!["player".to_string()];
+        episodes[3].starred = true;
+        episodes[3].status = "listened".to_string();
+
+        let rules = vec![
+            rule("latest_unlistened", Some(2), None),
+            rule("starred", None, None),
+        ];
+        let plan = plan_sync(&rules, &episodes, "player", 10_000, 1_000, size, |_| None);
+
+        // Episode 3 is wanted and already there
+        assert_eq!(ids(&plan.removals), vec![1]);
+        assert_eq!(ids(&plan.copies), vec![2, 4]);
+        assert_eq!(plan.copies[1].reason, "starred");
+        assert!(plan.skipped.is_empty());
+        assert_eq!(plan.projected_free_space, 1_000 + 100 - 200);
+    }
+
+    #[test]
+    fn test_plan_skips_what_does_not_fit() {
+        let mut episodes = vec![episode(1, 1, 3), episode(2, 1, 2), episode(3, 1, 1)];
+        episodes[0].file_size = Some(500);
+        episodes[2].downloaded = false;
+        episodes[2].local_file_path = None;
+
+        let rules = vec![rule("latest_unlistened", Some(3), None)];
+        let plan = plan_sync(&rules, &episodes, "player", 1_000, 300, size, |episode| {
+            (episode.id == 2).then(|| "unsupported format: device doesn't play opus".to_string())
+        });
+
+        assert!(plan.copies.is_empty());
+        assert_eq!(ids(&plan.skipped), vec![1, 2, 3]);
+        assert!(plan.skipped[0].reason.starts_with("insufficient space"));
+        assert!(plan.skipped[1].reason.starts_with("unsupported format"));
+        assert!(plan.skipped[2].reason.starts_with("not downloaded"));
+        assert_eq!(plan.projected_free_space, 300);
+    }
+
+    #[test]
+    fn test_fill_rule_tops_up_to_target() {
+        let episodes: Vec<Episode> = (1..=6).map(|id| episode(id, id, id as u32)).collect();
+
+        // 200 bytes of other files on a 1000 byte device filled to 50%
+        let rules = vec![rule("fill", None, Some(50))];
+        let plan = plan_sync(&rules, &episodes, "player", 1_000, 800, size, |_| None);
+
+        assert_eq!(ids(&plan.copies), vec![6, 5, 4]);
+        assert_eq!(plan.copies[0].reason, "fill to 50%");
+        assert_eq!(plan.projected_free_space, 500);
+    }
+
+    #[test]
+    fn test_validate_sync_rule() {
+        assert!(validate_sync_rule(&rule("starred", None, None)).is_ok());
+        assert!(validate_sync_rule(&rule("latest_unlistened", Some(5), None)).is_ok());
+        assert!(validate_sync_rule(&rule("latest_unlistened", None, None)).is_err());
+        assert!(validate_sync_rule(&rule("fill", None, Some(120))).is_err());
+        assert!(validate_sync_rule(&rule("everything", None, None)).is_err());
+    }
+}
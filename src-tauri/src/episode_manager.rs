@@ -1,8 +1,28 @@
 // Episode management module for PodPico
 // Coordinates episode operations between database, file manager, and other modules
 
-use crate::commands::Episode;
+use crate::artwork::{ArtworkCache, CachedArtwork};
+use crate::audio_probe::{probe_audio_file, AudioProperties};
+use crate::commands::{
+    AutoDownloadRule, CleanupItem, CleanupReport, Episode, FileMatch, ImportReport, ImportedFile,
+    LibraryRelocationProgress, LibraryRelocationReport, LibraryScanReport, MissingFile,
+    RetentionPolicy, UntrackedFile,
+};
+use crate::database::DatabaseManager;
 use crate::error::PodPicoError;
+use crate::file_manager::{
+    file_sha256, list_audio_files, move_file, unused_path, FileManager, FileMove,
+};
+use crate::loudness::{measure_loudness, write_replaygain_tags, LoudnessMeasurement};
+use crate::rss_manager::RssManager;
+use crate::tagging::{plain_text_description, write_episode_tags, EpisodeTags};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Cover images larger than this are embedded as their thumbnail instead
+const MAX_EMBEDDED_ARTWORK_BYTES: usize = 1024 * 1024;
 
 pub struct EpisodeManager {
     // This will coordinate between other managers
@@ -19,13 +39,418 @@ impl EpisodeManager {
         Self {}
     }
 
+    /// Fetches the podcast feed, stores episodes that are not in the database yet
+    /// and returns them
     pub async fn process_new_episodes(
         &self,
+        db: &DatabaseManager,
+        rss_manager: &RssManager,
         podcast_id: i64,
     ) -> Result<Vec<Episode>, PodPicoError> {
         log::info!("Processing new episodes for podcast: {}", podcast_id);
-        // TODO: Coordinate RSS fetching, database updates, and file operations
-        Err(PodPicoError::Generic("Not implemented yet".to_string()))
+
+        let podcast = db
+            .get_podcast_by_id(podcast_id)
+            .await
+            .map_err(|_| PodPicoError::PodcastNotFound(podcast_id))?;
+
+        let channel = rss_manager.fetch_feed(&podcast.rss_url).await?;
+        let items = rss_manager.extract_episodes(&channel).await?;
+
+        let new_episode_ids = self.save_feed_episodes(db, podcast_id, &items).await?;
+        db.update_podcast_last_updated(podcast_id).await?;
+
+        let (_, _, artwork_url) = rss_manager.extract_podcast_info(&channel).await?;
+        db.update_podcast_artwork_url(podcast_id, artwork_url.as_deref())
+            .await?;
+
+        let new_episodes: Vec<Episode> = db
+            .get_episodes(Some(podcast_id))
+            .await?
+            .into_iter()
+            .filter(|episode| new_episode_ids.contains(&episode.id))
+            .collect();
+
+        log::info!(
+            "Found {} new episodes for podcast {}",
+            new_episodes.len(),
+            podcast_id
+        );
+        Ok(new_episodes)
+    }
+
+    /// Stores feed items whose enclosure URL is not yet known for the podcast.
+    /// Returns the IDs of the inserted episodes.
+    pub async fn save_feed_episodes(
+        &self,
+        db: &DatabaseManager,
+        podcast_id: i64,
+        items: &[rss::Item],
+    ) -> Result<Vec<i64>, PodPicoError> {
+        let mut known_urls = db.get_episode_urls(podcast_id).await?;
+        let mut inserted = Vec::new();
+
+        for item in items {
+            // Extract episode information
+            let episode_title = item.title().unwrap_or("Untitled Episode").to_string();
+            let episode_description = item.description().map(|s| s.to_string());
+
+            // Get the audio file URL from enclosure (not link)
+            let episode_url = item
+                .enclosure()
+                .map(|enc| enc.url().to_string())
+                .unwrap_or_else(|| item.link().unwrap_or("").to_string());
+
+            if episode_url.is_empty() || known_urls.contains(&episode_url) {
+                continue;
+            }
+
+            // Try to parse duration from iTunes extension
+            let duration = item
+                .itunes_ext()
+                .and_then(|itunes| itunes.duration())
+                .and_then(parse_itunes_duration);
+
+            // Enclosure length is the best size estimate available before download
+            let file_size = item
+                .enclosure()
+                .and_then(|enc| enc.length().trim().parse::<i64>().ok())
+                .filter(|length| *length > 0);
+
+            let enclosure_type = item
+                .enclosure()
+                .map(|enc| enc.mime_type().trim().to_string())
+                .filter(|mime_type| !mime_type.is_empty());
+
+            // Parse published date
+            let published_date = item.pub_date().map(|s| s.to_string());
+
+            let episode_id = db
+                .add_episode(
+                    podcast_id,
+                    &episode_title,
+                    episode_description.as_deref(),
+                    &episode_url,
+                    published_date.as_deref(),
+                    duration,
+                    file_size,
+                )
+                .await
+                .map_err(|e| {
+                    log::warn!("Failed to save episode '{}': {}", episode_title, e);
+                    e
+                })?;
+
+            if let Some(enclosure_type) = &enclosure_type {
+                db.update_episode_enclosure_type(episode_id, enclosure_type)
+                    .await?;
+            }
+            if let Some(chapters_url) = item
+                .extensions()
+                .get("podcast")
+                .and_then(|podcast| podcast.get("chapters"))
+                .and_then(|chapters| chapters.first())
+                .and_then(|chapters| chapters.attrs().get("url"))
+            {
+                db.update_episode_chapters_url(episode_id, chapters_url)
+                    .await?;
+            }
+            if let Some(artwork_url) = item.itunes_ext().and_then(|itunes| itunes.image()) {
+                db.update_episode_artwork_url(episode_id, artwork_url)
+                    .await?;
+            }
+
+            known_urls.insert(episode_url);
+            inserted.push(episode_id);
+        }
+
+        Ok(inserted)
+    }
+
+    /// Caches the podcast's artwork and the artwork of its episodes that isn't cached
+    /// yet (or whose cached file has gone missing). Artwork is optional, so failures
+    /// are logged and skipped. Returns how many images were cached.
+    pub async fn cache_artwork(
+        &self,
+        db: &DatabaseManager,
+        artwork_cache: &ArtworkCache,
+        podcast_id: i64,
+    ) -> Result<usize, PodPicoError> {
+        let podcast = db.get_podcast_by_id(podcast_id).await?;
+        let episodes = db.get_episodes(Some(podcast_id)).await?;
+
+        // Episodes often reuse the podcast cover, so each URL is fetched once
+        let mut fetched: HashMap<String, Option<CachedArtwork>> = HashMap::new();
+        let mut cached_count = 0;
+
+        if let Some(url) = podcast
+            .artwork_url
+            .as_deref()
+            .filter(|_| needs_caching(podcast.artwork_path.as_deref()))
+        {
+            if let Some(cached) = fetch_artwork(artwork_cache, &mut fetched, url).await {
+                db.update_podcast_artwork_cache(
+                    podcast_id,
+                    &cached.image_path,
+                    &cached.thumbnail_path,
+                )
+                .await?;
+                cached_count += 1;
+            }
+        }
+
+        for episode in &episodes {
+            let Some(url) = episode
+                .artwork_url
+                .as_deref()
+                .filter(|_| needs_caching(episode.artwork_path.as_deref()))
+            else {
+                continue;
+            };
+            if let Some(cached) = fetch_artwork(artwork_cache, &mut fetched, url).await {
+                db.update_episode_artwork_cache(
+                    episode.id,
+                    &cached.image_path,
+                    &cached.thumbnail_path,
+                )
+                .await?;
+                cached_count += 1;
+            }
+        }
+
+        Ok(cached_count)
+    }
+
+    /// Writes title, podcast, date, track number, description and artwork from the
+    /// database into the episode's downloaded file. The track number is the
+    /// episode's position within its podcast, oldest first.
+    pub async fn tag_episode_file(
+        &self,
+        db: &DatabaseManager,
+        episode_id: i64,
+        id3_version: id3::Version,
+    ) -> Result<(), PodPicoError> {
+        let episode = db.get_episode_by_id(episode_id).await?;
+        let episodes = db.get_episodes(Some(episode.podcast_id)).await?;
+        let file_path = episode
+            .local_file_path
+            .clone()
+            .filter(|_| episode.downloaded)
+            .ok_or_else(|| {
+                PodPicoError::Generic(format!("Episode {} is not downloaded", episode_id))
+            })?;
+        let podcast = db.get_podcast_by_id(episode.podcast_id).await?;
+
+        let mut siblings: Vec<(Option<DateTime<Utc>>, i64)> = episodes
+            .iter()
+            .map(|other| {
+                let published = other
+                    .published_date
+                    .as_deref()
+                    .and_then(parse_published_date);
+                (published, other.id)
+            })
+            .collect();
+        siblings.sort();
+        let track = siblings
+            .iter()
+            .position(|(_, id)| *id == episode_id)
+            .map(|index| index as u32 + 1);
+
+        let artwork = match episode.artwork_path.as_deref() {
+            Some(_) => embeddable_artwork(
+                episode.artwork_path.as_deref(),
+                episode.artwork_thumbnail_path.as_deref(),
+            ),
+            None => embeddable_artwork(
+                podcast.artwork_path.as_deref(),
+                podcast.artwork_thumbnail_path.as_deref(),
+            ),
+        }
+        .await;
+
+        let tags = EpisodeTags {
+            title: episode.title.clone(),
+            podcast: podcast.name.clone(),
+            published: episode
+                .published_date
+                .as_deref()
+                .and_then(parse_published_date)
+                .map(|date| date.date_naive()),
+            track,
+            comment: episode
+                .description
+                .as_deref()
+                .map(plain_text_description)
+                .filter(|text| !text.is_empty()),
+            artwork,
+        };
+
+        log::info!("Writing tags to {}", file_path);
+        tokio::task::spawn_blocking(move || {
+            write_episode_tags(Path::new(&file_path), &tags, id3_version)
+        })
+        .await
+        .map_err(|e| PodPicoError::Generic(e.to_string()))?
+    }
+
+    /// Measures duration, bitrate and sample rate of the episode's downloaded file
+    /// and stores them with the episode
+    pub async fn probe_episode_file(
+        &self,
+        db: &DatabaseManager,
+        episode_id: i64,
+    ) -> Result<AudioProperties, PodPicoError> {
+        let episode = db.get_episode_by_id(episode_id).await?;
+        let file_path = episode
+            .local_file_path
+            .filter(|_| episode.downloaded)
+            .ok_or_else(|| {
+                PodPicoError::Generic(format!("Episode {} is not downloaded", episode_id))
+            })?;
+
+        let properties =
+            tokio::task::spawn_blocking(move || probe_audio_file(Path::new(&file_path)))
+                .await
+                .map_err(|e| PodPicoError::Generic(e.to_string()))??;
+
+        db.update_episode_audio_properties(
+            episode_id,
+            properties.duration_seconds.round() as i32,
+            properties.bitrate_bps as i32,
+            properties.sample_rate as i32,
+        )
+        .await?;
+        Ok(properties)
+    }
+
+    /// Measures downloaded episodes that have no measured duration yet, e.g. files
+    /// downloaded by earlier versions. Returns how many were measured; files that
+    /// can't be read are logged and skipped.
+    pub async fn probe_downloaded_episodes(
+        &self,
+        db: &DatabaseManager,
+    ) -> Result<usize, PodPicoError> {
+        let mut probed = 0;
+        for episode in db.get_downloaded_episodes().await? {
+            if episode.audio_duration.is_some() {
+                continue;
+            }
+            match self.probe_episode_file(db, episode.id).await {
+                Ok(_) => probed += 1,
+                Err(e) => log::warn!("Failed to measure episode {}: {}", episode.id, e),
+            }
+        }
+        Ok(probed)
+    }
+
+    /// Measures the EBU R128 loudness of the episode's downloaded file with
+    /// `encoder` and stores it; with `write_replaygain` the result is also written
+    /// into the file as ReplayGain tags
+    pub async fn analyze_episode_loudness(
+        &self,
+        db: &DatabaseManager,
+        episode_id: i64,
+        encoder: &Path,
+        write_replaygain: bool,
+    ) -> Result<LoudnessMeasurement, PodPicoError> {
+        let episode = db.get_episode_by_id(episode_id).await?;
+        let file_path = episode
+            .local_file_path
+            .filter(|_| episode.downloaded)
+            .ok_or_else(|| {
+                PodPicoError::Generic(format!("Episode {} is not downloaded", episode_id))
+            })?;
+
+        let measurement = measure_loudness(encoder, Path::new(&file_path)).await?;
+        db.update_episode_loudness(
+            episode_id,
+            measurement.integrated_lufs,
+            measurement.true_peak_dbtp,
+        )
+        .await?;
+
+        if write_replaygain {
+            tokio::task::spawn_blocking(move || {
+                write_replaygain_tags(Path::new(&file_path), &measurement)
+            })
+            .await
+            .map_err(|e| PodPicoError::Generic(e.to_string()))??;
+        }
+        Ok(measurement)
+    }
+
+    /// Picks which of the given new episodes should be downloaded automatically.
+    /// Episodes with an unknown size or publish date are not excluded by the
+    /// size and age limits.
+    pub fn select_auto_downloads(
+        &self,
+        rule: &AutoDownloadRule,
+        new_episodes: &[Episode],
+        now: DateTime<Utc>,
+    ) -> Vec<i64> {
+        if rule.mode == "off" {
+            return Vec::new();
+        }
+
+        let title_regex = match (rule.mode.as_str(), rule.title_pattern.as_deref()) {
+            ("title_match", Some(pattern)) => match regex::Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    log::warn!(
+                        "Invalid auto-download title pattern for podcast {}: {}",
+                        rule.podcast_id,
+                        e
+                    );
+                    return Vec::new();
+                }
+            },
+            ("title_match", None) => return Vec::new(),
+            _ => None,
+        };
+
+        let mut candidates: Vec<(&Episode, Option<DateTime<Utc>>)> = new_episodes
+            .iter()
+            .filter(|episode| !episode.downloaded)
+            .map(|episode| {
+                let published = episode
+                    .published_date
+                    .as_deref()
+                    .and_then(parse_published_date);
+                (episode, published)
+            })
+            .filter(|(_, published)| match (rule.max_age_days, published) {
+                (Some(max_age_days), Some(published)) => {
+                    now.signed_duration_since(*published).num_days() <= max_age_days
+                }
+                _ => true,
+            })
+            .filter(
+                |(episode, _)| match (rule.max_size_bytes, episode.file_size) {
+                    (Some(max_size), Some(size)) => size <= max_size,
+                    _ => true,
+                },
+            )
+            .filter(|(episode, _)| {
+                title_regex
+                    .as_ref()
+                    .is_none_or(|regex| regex.is_match(&episode.title))
+            })
+            .collect();
+
+        // Newest first; episodes without a parseable date go last
+        candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let limit = match rule.mode.as_str() {
+            "latest" => rule.latest_count.unwrap_or(1).max(0) as usize,
+            _ => candidates.len(),
+        };
+
+        candidates
+            .into_iter()
+            .take(limit)
+            .map(|(episode, _)| episode.id)
+            .collect()
     }
 
     pub async fn update_episode_status(
@@ -38,9 +463,1741 @@ impl EpisodeManager {
         Err(PodPicoError::Generic("Not implemented yet".to_string()))
     }
 
-    pub async fn cleanup_old_episodes(&self) -> Result<(), PodPicoError> {
-        log::info!("Cleaning up old episodes");
-        // TODO: Implement cleanup logic based on configuration
-        Ok(())
+    /// Removes downloaded files according to the retention policy. With `dry_run`
+    /// nothing is deleted and the report lists what would be removed.
+    pub async fn cleanup_old_episodes(
+        &self,
+        db: &DatabaseManager,
+        file_manager: &FileManager,
+        policy: &RetentionPolicy,
+        dry_run: bool,
+        now: DateTime<Utc>,
+    ) -> Result<CleanupReport, PodPicoError> {
+        log::info!("Cleaning up old episodes (dry run: {})", dry_run);
+
+        let mut report = CleanupReport {
+            dry_run,
+            ..CleanupReport::default()
+        };
+        if !policy.is_enabled() {
+            return Ok(report);
+        }
+
+        let downloaded = db.get_downloaded_episodes().await?;
+        for (episode, reason) in self.select_for_cleanup(policy, &downloaded, now) {
+            let file_path = episode.local_file_path.clone().unwrap_or_default();
+            let file_size = match tokio::fs::metadata(&file_path).await {
+                Ok(metadata) => metadata.len(),
+                Err(_) => episode.file_size.unwrap_or(0).max(0) as u64,
+            };
+
+            if !dry_run {
+                if let Err(e) = file_manager
+                    .delete_episode_by_path(episode.id, &file_path)
+                    .await
+                {
+                    report
+                        .errors
+                        .push(format!("Failed to delete {}: {}", file_path, e));
+                    continue;
+                }
+                if let Err(e) = db
+                    .update_episode_downloaded_status(episode.id, false, None)
+                    .await
+                {
+                    report.errors.push(format!(
+                        "Deleted {} but failed to update episode {}: {}",
+                        file_path, episode.id, e
+                    ));
+                }
+            }
+
+            report.freed_bytes += file_size;
+            report.removed.push(CleanupItem {
+                episode_id: episode.id,
+                podcast_id: episode.podcast_id,
+                podcast_name: episode.podcast_name.clone(),
+                title: episode.title.clone(),
+                file_path,
+                file_size,
+                reason,
+            });
+        }
+
+        log::info!(
+            "Cleanup {} {} episodes ({} bytes)",
+            if dry_run { "would remove" } else { "removed" },
+            report.removed.len(),
+            report.freed_bytes
+        );
+        Ok(report)
+    }
+
+    /// Picks the downloaded episodes the retention policy allows to delete, with the reason.
+    /// Starred and on-device episodes are never selected, but still count towards
+    /// the per-podcast download limit.
+    pub fn select_for_cleanup<'a>(
+        &self,
+        policy: &RetentionPolicy,
+        downloaded: &'a [Episode],
+        now: DateTime<Utc>,
+    ) -> Vec<(&'a Episode, String)> {
+        let mut selected: Vec<(&Episode, String)> = Vec::new();
+
+        if let Some(days) = policy.delete_listened_after_days {
+            for episode in downloaded {
+                let listened_at = episode.listened_at.as_deref().and_then(parse_db_timestamp);
+                if episode.status == "listened"
+                    && listened_at
+                        .is_some_and(|at| now.signed_duration_since(at).num_days() >= days)
+                {
+                    selected.push((episode, format!("Listened more than {} days ago", days)));
+                }
+            }
+        }
+
+        if let Some(keep) = policy.keep_latest_downloads_per_podcast {
+            let mut by_podcast: HashMap<i64, Vec<&Episode>> = HashMap::new();
+            for episode in downloaded {
+                by_podcast
+                    .entry(episode.podcast_id)
+                    .or_default()
+                    .push(episode);
+            }
+
+            for episodes in by_podcast.values_mut() {
+                // Newest first; undated episodes go last, ties broken by insertion order
+                episodes.sort_by_cached_key(|episode| {
+                    let published = episode
+                        .published_date
+                        .as_deref()
+                        .and_then(parse_published_date);
+                    (std::cmp::Reverse(published), std::cmp::Reverse(episode.id))
+                });
+                for episode in episodes.iter().skip(keep.max(0) as usize) {
+                    if !selected.iter().any(|(e, _)| e.id == episode.id) {
+                        selected
+                            .push((episode, format!("Older than the latest {} downloads", keep)));
+                    }
+                }
+            }
+        }
+
+        selected.retain(|(episode, _)| !episode.starred && !episode.on_device);
+        selected.sort_by_key(|(episode, _)| episode.id);
+        selected
+    }
+
+    /// Moves every downloaded episode file from the file manager's download directory
+    /// to `new_directory`, keeping the layout below it, and rewrites the stored paths.
+    /// Either all files and paths are moved or everything is rolled back.
+    pub async fn relocate_library(
+        &self,
+        db: &DatabaseManager,
+        file_manager: &FileManager,
+        new_directory: &Path,
+    ) -> Result<LibraryRelocationReport, PodPicoError> {
+        let old_directory = file_manager.download_directory().to_path_buf();
+        log::info!(
+            "Relocating library from {} to {}",
+            old_directory.display(),
+            new_directory.display()
+        );
+
+        if file_manager.has_active_downloads().await {
+            return Err(PodPicoError::LibraryRelocationFailed(
+                "downloads are in progress".to_string(),
+            ));
+        }
+
+        fs::create_dir_all(new_directory).await?;
+        let old_canonical = fs::canonicalize(&old_directory)
+            .await
+            .unwrap_or_else(|_| old_directory.clone());
+        let new_canonical = fs::canonicalize(new_directory).await?;
+        if new_canonical.starts_with(&old_canonical) || old_canonical.starts_with(&new_canonical) {
+            return Err(PodPicoError::LibraryRelocationFailed(
+                "the new directory must not contain or be inside the current one".to_string(),
+            ));
+        }
+
+        // Work out where each downloaded file goes
+        let mut skipped = Vec::new();
+        let mut planned = Vec::new();
+        let mut total_bytes = 0;
+        for episode in db.get_downloaded_episodes().await? {
+            let Some(local_file_path) = episode.local_file_path.as_deref() else {
+                continue;
+            };
+            let from = PathBuf::from(local_file_path);
+            let relative = match from.strip_prefix(&old_directory) {
+                Ok(relative) => Some(relative.to_path_buf()),
+                Err(_) => fs::canonicalize(&from)
+                    .await
+                    .ok()
+                    .and_then(|path| Some(path.strip_prefix(&old_canonical).ok()?.to_path_buf())),
+            };
+
+            match (relative, fs::metadata(&from).await) {
+                (Some(relative), Ok(metadata)) => {
+                    total_bytes += metadata.len();
+                    planned.push((episode.id, from, relative, metadata.len()));
+                }
+                (None, _) => skipped.push(format!(
+                    "{}: {} is outside the library",
+                    episode.title, local_file_path
+                )),
+                (_, Err(_)) => {
+                    skipped.push(format!("{}: {} is missing", episode.title, local_file_path))
+                }
+            }
+        }
+
+        if !same_filesystem(&old_canonical, &new_canonical) {
+            file_manager
+                .check_disk_space(&new_canonical, total_bytes)
+                .await?;
+        }
+
+        let mut progress = LibraryRelocationProgress {
+            moved_files: 0,
+            total_files: planned.len(),
+            moved_bytes: 0,
+            total_bytes,
+            current_file: None,
+        };
+        file_manager.emit_relocation_progress(&progress);
+
+        let mut relocated = Vec::new();
+        for (episode_id, from, relative, size) in planned {
+            let result = async {
+                let target = new_directory.join(&relative);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                let to = unused_path(&target)?;
+                let how = move_file(&from, &to).await?;
+                Ok::<_, PodPicoError>(RelocatedFile {
+                    episode_id,
+                    from: from.clone(),
+                    to,
+                    how,
+                })
+            }
+            .await;
+
+            match result {
+                Ok(file) => {
+                    progress.moved_files += 1;
+                    progress.moved_bytes += size;
+                    progress.current_file = Some(file.to.to_string_lossy().to_string());
+                    file_manager.emit_relocation_progress(&progress);
+                    relocated.push(file);
+                }
+                Err(e) => {
+                    rollback_relocation(&relocated).await;
+                    return Err(PodPicoError::LibraryRelocationFailed(e.to_string()));
+                }
+            }
+        }
+
+        let paths: Vec<(i64, String)> = relocated
+            .iter()
+            .map(|file| (file.episode_id, file.to.to_string_lossy().to_string()))
+            .collect();
+        if let Err(e) = db.update_local_file_paths(&paths).await {
+            rollback_relocation(&relocated).await;
+            return Err(PodPicoError::LibraryRelocationFailed(e.to_string()));
+        }
+
+        // Committed: drop the originals of copied files and the emptied directories
+        for file in &relocated {
+            if file.how == FileMove::Copied {
+                if let Err(e) = fs::remove_file(&file.from).await {
+                    log::warn!("Failed to remove {}: {}", file.from.display(), e);
+                }
+            }
+            remove_empty_parents(&file.from, &old_directory).await;
+        }
+
+        log::info!(
+            "Relocated {} files ({} bytes) to {}",
+            progress.moved_files,
+            progress.moved_bytes,
+            new_directory.display()
+        );
+        Ok(LibraryRelocationReport {
+            old_directory: old_directory.to_string_lossy().to_string(),
+            new_directory: new_directory.to_string_lossy().to_string(),
+            moved_files: progress.moved_files,
+            moved_bytes: progress.moved_bytes,
+            skipped,
+        })
+    }
+
+    /// Reconciles the database with the download directory: downloaded episodes whose
+    /// file is gone are reset, and audio files no episode refers to are reported with
+    /// the episode they most likely belong to.
+    pub async fn scan_library(
+        &self,
+        db: &DatabaseManager,
+        file_manager: &FileManager,
+    ) -> Result<LibraryScanReport, PodPicoError> {
+        log::info!(
+            "Scanning library in {}",
+            file_manager.download_directory().display()
+        );
+
+        let episodes = db.get_all_episodes().await?;
+        let mut missing = Vec::new();
+        let mut tracked_paths = HashSet::new();
+        let mut tracked_by_size: HashMap<u64, Vec<(&Episode, PathBuf)>> = HashMap::new();
+
+        for episode in episodes.iter().filter(|episode| episode.downloaded) {
+            let Some(local_file_path) = episode.local_file_path.as_deref() else {
+                continue;
+            };
+            match fs::canonicalize(local_file_path).await {
+                Ok(path) => {
+                    if let Ok(metadata) = fs::metadata(&path).await {
+                        tracked_by_size
+                            .entry(metadata.len())
+                            .or_default()
+                            .push((episode, path.clone()));
+                    }
+                    tracked_paths.insert(path);
+                }
+                Err(_) => {
+                    log::warn!(
+                        "File of episode {} is missing: {}",
+                        episode.id,
+                        local_file_path
+                    );
+                    db.update_episode_downloaded_status(episode.id, false, None)
+                        .await?;
+                    missing.push(MissingFile {
+                        episode_id: episode.id,
+                        podcast_name: episode.podcast_name.clone(),
+                        title: episode.title.clone(),
+                        file_path: local_file_path.to_string(),
+                    });
+                }
+            }
+        }
+
+        let missing_ids: HashSet<i64> = missing.iter().map(|file| file.episode_id).collect();
+        let not_downloaded: Vec<&Episode> = episodes
+            .iter()
+            .filter(|episode| !episode.downloaded || missing_ids.contains(&episode.id))
+            .collect();
+
+        let mut untracked = Vec::new();
+        let mut reclaimable_bytes = 0;
+        for (path, size) in list_audio_files(file_manager.download_directory()).await? {
+            let canonical = fs::canonicalize(&path)
+                .await
+                .unwrap_or_else(|_| path.clone());
+            if tracked_paths.contains(&canonical) {
+                continue;
+            }
+
+            let suggested_match = match self
+                .find_duplicate(&canonical, size, &tracked_by_size)
+                .await
+            {
+                Some(episode) => Some(file_match(episode, "duplicate")),
+                None => self.match_file_to_episode(file_manager, &path, size, &not_downloaded),
+            };
+
+            reclaimable_bytes += size;
+            untracked.push(UntrackedFile {
+                file_path: path.to_string_lossy().to_string(),
+                file_size: size,
+                suggested_match,
+            });
+        }
+
+        log::info!(
+            "Library scan found {} missing and {} untracked files ({} bytes reclaimable)",
+            missing.len(),
+            untracked.len(),
+            reclaimable_bytes
+        );
+        Ok(LibraryScanReport {
+            missing,
+            untracked,
+            reclaimable_bytes,
+        })
+    }
+
+    /// Returns the downloaded episode whose file has the same contents as `path`
+    async fn find_duplicate<'a>(
+        &self,
+        path: &Path,
+        size: u64,
+        tracked_by_size: &HashMap<u64, Vec<(&'a Episode, PathBuf)>>,
+    ) -> Option<&'a Episode> {
+        let candidates = tracked_by_size.get(&size)?;
+        let hash = file_sha256(path).await.ok()?;
+        for (episode, tracked_path) in candidates {
+            if file_sha256(tracked_path).await.ok().as_ref() == Some(&hash) {
+                return Some(episode);
+            }
+        }
+        None
+    }
+
+    /// Guesses which episode an untracked file belongs to, first by the names the
+    /// current and legacy layouts would give the episode, then by a unique size match
+    fn match_file_to_episode(
+        &self,
+        file_manager: &FileManager,
+        path: &Path,
+        size: u64,
+        candidates: &[&Episode],
+    ) -> Option<FileMatch> {
+        let file_name = path.file_name()?.to_string_lossy().to_string();
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // The legacy layout stored files below a directory named after the podcast id
+        let podcast_hint = path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .and_then(|name| name.to_string_lossy().parse::<i64>().ok());
+
+        let mut by_name: Vec<&Episode> = candidates
+            .iter()
+            .copied()
+            .filter(|episode| {
+                file_manager.extract_filename_from_url(&episode.episode_url, episode.id)
+                    == file_name
+                    || file_manager
+                        .episode_file_path(episode, &extension)
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy() == file_name)
+            })
+            .collect();
+        by_name.sort_by_key(|episode| Some(episode.podcast_id) != podcast_hint);
+        if let Some(episode) = by_name.first() {
+            return Some(file_match(episode, "filename"));
+        }
+
+        let by_size: Vec<&Episode> = candidates
+            .iter()
+            .copied()
+            .filter(|episode| episode.file_size == Some(size as i64))
+            .collect();
+        match by_size.as_slice() {
+            [episode] => Some(file_match(episode, "size")),
+            _ => None,
+        }
+    }
+
+    /// Imports audio files found below `directory` (e.g. downloaded by other tools) for
+    /// subscribed episodes that aren't downloaded yet. Files are matched by enclosure
+    /// file name, then ID3 title, then duration, and copied or hard-linked into the library.
+    pub async fn import_files(
+        &self,
+        db: &DatabaseManager,
+        file_manager: &FileManager,
+        directory: &Path,
+        hard_link: bool,
+    ) -> Result<ImportReport, PodPicoError> {
+        log::info!("Importing audio files from {}", directory.display());
+
+        let episodes = db.get_all_episodes().await?;
+        let mut candidates: Vec<&Episode> = episodes
+            .iter()
+            .filter(|episode| !episode.downloaded)
+            .collect();
+        let mut report = ImportReport::default();
+
+        for (path, _) in list_audio_files(directory).await? {
+            if file_manager.is_in_library(&path).await {
+                continue;
+            }
+
+            let metadata = read_import_metadata(&path).await;
+            let Some((episode, reason)) =
+                self.match_import(file_manager, &path, &metadata, &candidates)
+            else {
+                report.unmatched.push(path.to_string_lossy().to_string());
+                continue;
+            };
+
+            let imported = match file_manager.import_file(episode, &path, hard_link).await {
+                Ok(imported) => imported,
+                Err(e) => {
+                    report.errors.push(e.to_string());
+                    continue;
+                }
+            };
+            let file_path = imported.to_string_lossy().to_string();
+            if let Err(e) = db
+                .update_episode_downloaded_status(episode.id, true, Some(&file_path))
+                .await
+            {
+                let _ = fs::remove_file(&imported).await;
+                report.errors.push(format!(
+                    "Failed to mark episode {} as downloaded: {}",
+                    episode.id, e
+                ));
+                continue;
+            }
+
+            candidates.retain(|candidate| candidate.id != episode.id);
+            report.imported.push(ImportedFile {
+                source_path: path.to_string_lossy().to_string(),
+                file_path,
+                episode_id: episode.id,
+                podcast_name: episode.podcast_name.clone(),
+                title: episode.title.clone(),
+                reason: reason.to_string(),
+            });
+        }
+
+        log::info!(
+            "Imported {} files, {} unmatched, {} errors",
+            report.imported.len(),
+            report.unmatched.len(),
+            report.errors.len()
+        );
+        Ok(report)
+    }
+
+    fn match_import<'a>(
+        &self,
+        file_manager: &FileManager,
+        path: &Path,
+        metadata: &ImportMetadata,
+        candidates: &[&'a Episode],
+    ) -> Option<(&'a Episode, &'static str)> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+        if let Some(episode) = candidates.iter().find(|episode| {
+            file_manager
+                .extract_filename_from_url(&episode.episode_url, episode.id)
+                .to_lowercase()
+                == file_name
+        }) {
+            return Some((episode, "filename"));
+        }
+
+        // An album tag naming a subscribed podcast narrows down the candidates
+        let album = metadata.album.as_deref().map(normalize_title);
+        let in_album: Vec<&Episode> = candidates
+            .iter()
+            .copied()
+            .filter(|episode| {
+                album
+                    .as_ref()
+                    .is_none_or(|album| *album == normalize_title(&episode.podcast_name))
+            })
+            .collect();
+        let candidates: Vec<&Episode> = if in_album.is_empty() {
+            candidates.to_vec()
+        } else {
+            in_album
+        };
+
+        if let Some(title) = metadata.title.as_deref().map(normalize_title) {
+            let matches: Vec<&Episode> = candidates
+                .iter()
+                .copied()
+                .filter(|episode| normalize_title(&episode.title) == title)
+                .collect();
+            if let [episode] = matches.as_slice() {
+                return Some((episode, "title"));
+            }
+        }
+
+        if let Some(duration) = metadata.duration_seconds {
+            let matches: Vec<&Episode> = candidates
+                .iter()
+                .copied()
+                .filter(|episode| {
+                    episode
+                        .duration
+                        .is_some_and(|d| (d as i64 - duration as i64).abs() <= 2)
+                })
+                .collect();
+            if let [episode] = matches.as_slice() {
+                return Some((episode, "duration"));
+            }
+        }
+
+        None
+    }
+}
+
+/// Tag data used to match imported files to episodes
+#[derive(Debug, Default)]
+struct ImportMetadata {
+    title: Option<String>,
+    album: Option<String>,
+    duration_seconds: Option<u32>,
+}
+
+async fn read_import_metadata(path: &Path) -> ImportMetadata {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || match id3::Tag::read_from_path(&path) {
+        Ok(tag) => {
+            use id3::TagLike;
+            ImportMetadata {
+                title: tag.title().map(str::to_string),
+                album: tag.album().map(str::to_string),
+                // TLEN is in milliseconds
+                duration_seconds: tag.duration().map(|ms| ms / 1000),
+            }
+        }
+        Err(_) => ImportMetadata::default(),
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Lowercases and keeps only words, so punctuation and spacing differences don't matter
+fn normalize_title(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn file_match(episode: &Episode, reason: &str) -> FileMatch {
+    FileMatch {
+        episode_id: episode.id,
+        podcast_name: episode.podcast_name.clone(),
+        title: episode.title.clone(),
+        reason: reason.to_string(),
+    }
+}
+
+/// A file moved during library relocation, kept so the move can be undone
+struct RelocatedFile {
+    episode_id: i64,
+    from: PathBuf,
+    to: PathBuf,
+    how: FileMove,
+}
+
+/// Puts already relocated files back where they were
+async fn rollback_relocation(relocated: &[RelocatedFile]) {
+    log::warn!("Rolling back relocation of {} files", relocated.len());
+    for file in relocated.iter().rev() {
+        let result = match file.how {
+            FileMove::Renamed => move_file(&file.to, &file.from).await.map(|_| ()),
+            FileMove::Copied => fs::remove_file(&file.to).await.map_err(Into::into),
+        };
+        if let Err(e) = result {
+            log::error!(
+                "Failed to restore {} from {}: {}",
+                file.from.display(),
+                file.to.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Removes the now empty directories between `file` and `root`, leaving `root` itself
+async fn remove_empty_parents(file: &Path, root: &Path) {
+    let mut directory = file.parent();
+    while let Some(current) = directory {
+        if current == root || !current.starts_with(root) {
+            break;
+        }
+        // remove_dir fails on non-empty directories, which ends the walk
+        if fs::remove_dir(current).await.is_err() {
+            break;
+        }
+        directory = current.parent();
+    }
+}
+
+#[cfg(unix)]
+fn same_filesystem(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_filesystem(_a: &Path, _b: &Path) -> bool {
+    false
+}
+
+fn needs_caching(artwork_path: Option<&str>) -> bool {
+    artwork_path.is_none_or(|path| !Path::new(path).exists())
+}
+
+async fn fetch_artwork(
+    artwork_cache: &ArtworkCache,
+    fetched: &mut HashMap<String, Option<CachedArtwork>>,
+    url: &str,
+) -> Option<CachedArtwork> {
+    if let Some(cached) = fetched.get(url) {
+        return cached.clone();
+    }
+
+    let cached = match artwork_cache.cache_artwork(url).await {
+        Ok(cached) => Some(cached),
+        Err(e) => {
+            log::warn!("Failed to cache artwork {}: {}", url, e);
+            None
+        }
+    };
+    fetched.insert(url.to_string(), cached.clone());
+    cached
+}
+
+/// Reads cached artwork for embedding in tags. Large or non-JPEG/PNG originals
+/// are replaced by the thumbnail, which is always a small PNG.
+async fn embeddable_artwork(
+    image_path: Option<&str>,
+    thumbnail_path: Option<&str>,
+) -> Option<Vec<u8>> {
+    if let Some(path) = image_path {
+        if let Ok(bytes) = fs::read(path).await {
+            let embeddable = bytes.starts_with(b"\x89PNG") || bytes.starts_with(b"\xFF\xD8\xFF");
+            if embeddable && bytes.len() <= MAX_EMBEDDED_ARTWORK_BYTES {
+                return Some(bytes);
+            }
+        }
+    }
+    fs::read(thumbnail_path?).await.ok()
+}
+
+/// Parses an `itunes:duration` value ("1:23:45", "23:45" or "45") into seconds
+pub fn parse_itunes_duration(duration: &str) -> Option<i32> {
+    let parts: Vec<&str> = duration.trim().split(':').collect();
+    match parts.len() {
+        1 => parts[0].parse::<i32>().ok(), // seconds
+        2 => {
+            // minutes:seconds
+            let minutes = parts[0].parse::<i32>().ok()?;
+            let seconds = parts[1].parse::<i32>().ok()?;
+            Some(minutes * 60 + seconds)
+        }
+        3 => {
+            // hours:minutes:seconds
+            let hours = parts[0].parse::<i32>().ok()?;
+            let minutes = parts[1].parse::<i32>().ok()?;
+            let seconds = parts[2].parse::<i32>().ok()?;
+            Some(hours * 3600 + minutes * 60 + seconds)
+        }
+        _ => None,
+    }
+}
+
+/// Parses a stored published date, which is RFC 2822 when it comes from a feed
+/// and RFC 3339 when written by other code paths
+pub fn parse_published_date(date: &str) -> Option<DateTime<Utc>> {
+    let date = date.trim();
+    // Feeds frequently carry a weekday that doesn't match the date, so ignore it
+    let without_weekday = date.split_once(", ").map_or(date, |(_, rest)| rest);
+
+    DateTime::parse_from_rfc2822(without_weekday)
+        .or_else(|_| DateTime::parse_from_rfc3339(date))
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+}
+
+/// Parses a timestamp written by SQLite's `CURRENT_TIMESTAMP` (UTC)
+fn parse_db_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S")
+        .map(|naive| naive.and_utc())
+        .ok()
+        .or_else(|| parse_published_date(timestamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    fn test_episode(id: i64, title: &str, published: &str, size: Option<i64>) -> Episode {
+        Episode {
+            id,
+            podcast_id: 1,
+            podcast_name: "Test Podcast".to_string(),
+            title: title.to_string(),
+            description: None,
+            episode_url: format!("https://example.com/{}.mp3", id),
+            published_date: Some(published.to_string()),
+            duration: None,
+            file_size: size,
+            local_file_path: None,
+            status: "new".to_string(),
+            downloaded: false,
+            on_device: false,
+            starred: false,
+            listened_at: None,
+            enclosure_type: None,
+            artwork_url: None,
+            artwork_path: None,
+            artwork_thumbnail_path: None,
+            audio_duration: None,
+            audio_bitrate: None,
+            audio_sample_rate: None,
+            loudness_lufs: None,
+            true_peak_dbtp: None,
+            device_duration: None,
+            trimmed_silence: None,
+            chapters_url: None,
+            device_ids: Vec::new(),
+        }
+    }
+
+    fn rule(mode: &str) -> AutoDownloadRule {
+        AutoDownloadRule {
+            podcast_id: 1,
+            mode: mode.to_string(),
+            latest_count: None,
+            title_pattern: None,
+            max_age_days: None,
+            max_size_bytes: None,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        parse_published_date("Fri, 10 Mar 2023 00:00:00 +0000").unwrap()
+    }
+
+    fn sample_episodes() -> Vec<Episode> {
+        vec![
+            test_episode(1, "Episode 1", "Wed, 01 Mar 2023 00:00:00 +0000", Some(10)),
+            test_episode(2, "Bonus: Q&A", "Mon, 06 Mar 2023 00:00:00 +0000", Some(20)),
+            test_episode(3, "Episode 2", "Thu, 09 Mar 2023 00:00:00 +0000", Some(30)),
+        ]
+    }
+
+    #[test]
+    fn test_auto_download_off_selects_nothing() {
+        let manager = EpisodeManager::new();
+        let selected = manager.select_auto_downloads(&rule("off"), &sample_episodes(), now());
+        assert!(selected.is_empty());
+    }
+
+    #[test]
+    fn test_auto_download_all_new_selects_newest_first() {
+        let manager = EpisodeManager::new();
+        let selected = manager.select_auto_downloads(&rule("all_new"), &sample_episodes(), now());
+        assert_eq!(selected, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_auto_download_latest_n() {
+        let manager = EpisodeManager::new();
+        let mut latest = rule("latest");
+        latest.latest_count = Some(2);
+
+        let selected = manager.select_auto_downloads(&latest, &sample_episodes(), now());
+        assert_eq!(selected, vec![3, 2]);
+    }
+
+    #[test]
+    fn test_auto_download_title_regex() {
+        let manager = EpisodeManager::new();
+        let mut title_match = rule("title_match");
+        title_match.title_pattern = Some("^Episode \\d+$".to_string());
+
+        let selected = manager.select_auto_downloads(&title_match, &sample_episodes(), now());
+        assert_eq!(selected, vec![3, 1]);
+    }
+
+    #[test]
+    fn test_auto_download_invalid_regex_selects_nothing() {
+        let manager = EpisodeManager::new();
+        let mut title_match = rule("title_match");
+        title_match.title_pattern = Some("(unclosed".to_string());
+
+        let selected = manager.select_auto_downloads(&title_match, &sample_episodes(), now());
+        assert!(selected.is_empty());
+    }
+
+    #[test]
+    fn test_auto_download_max_age_and_size_filters() {
+        let manager = EpisodeManager::new();
+        let mut all_new = rule("all_new");
+        all_new.max_age_days = Some(5);
+        all_new.max_size_bytes = Some(25);
+
+        // Episode 1 is too old, episode 3 is too large
+        let selected = manager.select_auto_downloads(&all_new, &sample_episodes(), now());
+        assert_eq!(selected, vec![2]);
+    }
+
+    #[test]
+    fn test_auto_download_skips_downloaded_episodes() {
+        let manager = EpisodeManager::new();
+        let mut episodes = sample_episodes();
+        episodes[2].downloaded = true;
+
+        let selected = manager.select_auto_downloads(&rule("all_new"), &episodes, now());
+        assert_eq!(selected, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_parse_itunes_duration_formats() {
+        assert_eq!(parse_itunes_duration("45"), Some(45));
+        assert_eq!(parse_itunes_duration("23:45"), Some(1425));
+        assert_eq!(parse_itunes_duration("1:23:45"), Some(5025));
+        assert_eq!(parse_itunes_duration("abc"), None);
+    }
+
+    #[test]
+    fn test_parse_published_date_formats() {
+        assert!(parse_published_date("Sun, 01 Jan 2023 00:00:00 +0000").is_some());
+        // Wrong weekday as found in many real feeds
+        assert!(parse_published_date("Mon, 01 Jan 2023 00:00:00 +0000").is_some());
+        assert!(parse_published_date("2023-01-01T00:00:00Z").is_some());
+        assert!(parse_published_date("yesterday").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_process_new_episodes_only_returns_unseen_items() {
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+        let rss_manager = RssManager::new();
+        let server = MockServer::start();
+
+        let mut feed_mock = server.mock(|when, then| {
+            when.method(GET).path("/feed.xml");
+            then.status(200).body(
+                r#"<?xml version="1.0"?><rss version="2.0"><channel>
+                <title>Refresh Podcast</title><description>Refresh</description>
+                <item><title>First</title>
+                    <enclosure url="https://example.com/first.mp3" type="audio/mpeg" length="1000"/>
+                    <pubDate>Mon, 02 Jan 2023 00:00:00 +0000</pubDate></item>
+                </channel></rss>"#,
+            );
+        });
+
+        let podcast = db
+            .add_podcast(
+                "Refresh Podcast",
+                &server.url("/feed.xml"),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let manager = EpisodeManager::new();
+
+        let first_run = manager
+            .process_new_episodes(&db, &rss_manager, podcast.id)
+            .await
+            .unwrap();
+        assert_eq!(first_run.len(), 1);
+        assert_eq!(first_run[0].file_size, Some(1000));
+
+        feed_mock.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/feed.xml");
+            then.status(200).body(
+                r#"<?xml version="1.0"?><rss version="2.0"><channel>
+                <title>Refresh Podcast</title><description>Refresh</description>
+                <item><title>Second</title>
+                    <enclosure url="https://example.com/second.mp3" type="audio/mpeg" length="2000"/>
+                    <pubDate>Tue, 03 Jan 2023 00:00:00 +0000</pubDate></item>
+                <item><title>First</title>
+                    <enclosure url="https://example.com/first.mp3" type="audio/mpeg" length="1000"/>
+                    <pubDate>Mon, 02 Jan 2023 00:00:00 +0000</pubDate></item>
+                </channel></rss>"#,
+            );
+        });
+
+        let second_run = manager
+            .process_new_episodes(&db, &rss_manager, podcast.id)
+            .await
+            .unwrap();
+        assert_eq!(second_run.len(), 1);
+        assert_eq!(second_run[0].title, "Second");
+
+        let all_episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        assert_eq!(all_episodes.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_caches_podcast_and_episode_artwork() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+        let artwork_cache = ArtworkCache::new(&temp_dir.path().to_string_lossy());
+        artwork_cache.initialize().await.unwrap();
+        let server = MockServer::start();
+
+        let cover = server.mock(|when, then| {
+            when.method(GET).path("/cover.png");
+            then.status(200).body(png_image(300, 300));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/special.png");
+            then.status(200).body(png_image(40, 40));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/feed.xml");
+            then.status(200).body(format!(
+                r#"<?xml version="1.0"?>
+                <rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"><channel>
+                <title>Artwork Podcast</title><description>Covers</description>
+                <itunes:image href="{cover}"/>
+                <item><title>Plain</title>
+                    <enclosure url="https://example.com/plain.mp3" type="audio/mpeg" length="1000"/>
+                    <itunes:image href="{cover}"/></item>
+                <item><title>Special</title>
+                    <enclosure url="https://example.com/special.mp3" type="audio/mpeg" length="1000"/>
+                    <itunes:image href="{special}"/></item>
+                </channel></rss>"#,
+                cover = server.url("/cover.png"),
+                special = server.url("/special.png"),
+            ));
+        });
+
+        // Subscribed before the feed had artwork
+        let podcast = db
+            .add_podcast(
+                "Artwork Podcast",
+                &server.url("/feed.xml"),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let manager = EpisodeManager::new();
+        manager
+            .process_new_episodes(&db, &RssManager::new(), podcast.id)
+            .await
+            .unwrap();
+
+        let cached = manager
+            .cache_artwork(&db, &artwork_cache, podcast.id)
+            .await
+            .unwrap();
+        assert_eq!(cached, 3);
+        // The shared cover is downloaded once
+        cover.assert_hits(1);
+
+        let podcast = db.get_podcast_by_id(podcast.id).await.unwrap();
+        let podcast_thumbnail = podcast.artwork_thumbnail_path.unwrap();
+        assert!(Path::new(&podcast_thumbnail).exists());
+
+        let episodes = db.get_episodes(Some(podcast.id)).await.unwrap();
+        let plain = episodes.iter().find(|e| e.title == "Plain").unwrap();
+        let special = episodes.iter().find(|e| e.title == "Special").unwrap();
+        assert_eq!(plain.artwork_path, podcast.artwork_path);
+        assert_eq!(
+            plain.artwork_thumbnail_path.as_deref(),
+            Some(podcast_thumbnail.as_str())
+        );
+        assert_eq!(special.artwork_url, Some(server.url("/special.png")));
+        assert_ne!(special.artwork_path, podcast.artwork_path);
+
+        // Everything is cached, so nothing is fetched again
+        assert_eq!(
+            manager
+                .cache_artwork(&db, &artwork_cache, podcast.id)
+                .await
+                .unwrap(),
+            0
+        );
+        cover.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_tag_episode_file_from_database() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+        let podcast = db
+            .add_podcast(
+                "Tagged Show",
+                "https://example.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let newer = db
+            .add_episode(
+                podcast.id,
+                "Second Episode",
+                Some("<p>Guests &amp; news</p>"),
+                "https://example.com/2.mp3",
+                Some("Fri, 10 Mar 2023 08:00:00 +0000"),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        db.add_episode(
+            podcast.id,
+            "First Episode",
+            None,
+            "https://example.com/1.mp3",
+            Some("Wed, 01 Mar 2023 08:00:00 +0000"),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let cover = temp_dir.path().join("cover.png");
+        std::fs::write(&cover, png_image(8, 8)).unwrap();
+        db.update_podcast_artwork_cache(
+            podcast.id,
+            &cover.to_string_lossy(),
+            &cover.to_string_lossy(),
+        )
+        .await
+        .unwrap();
+
+        let file_path = temp_dir.path().join("second.mp3");
+        std::fs::write(&file_path, [0xFF, 0xFB, 0x90, 0x64, 0, 0, 0, 0]).unwrap();
+        db.update_episode_downloaded_status(newer, true, Some(&file_path.to_string_lossy()))
+            .await
+            .unwrap();
+
+        EpisodeManager::new()
+            .tag_episode_file(&db, newer, id3::Version::Id3v24)
+            .await
+            .unwrap();
+
+        let tag = id3::Tag::read_from_path(&file_path).unwrap();
+        use id3::TagLike;
+        assert_eq!(tag.title(), Some("Second Episode"));
+        assert_eq!(tag.album(), Some("Tagged Show"));
+        assert_eq!(tag.artist(), Some("Tagged Show"));
+        // Second oldest episode of the podcast
+        assert_eq!(tag.track(), Some(2));
+        assert_eq!(tag.date_recorded().map(|date| date.day), Some(Some(10)));
+        assert_eq!(
+            tag.comments().next().map(|comment| comment.text.as_str()),
+            Some("Guests & news")
+        );
+        assert_eq!(tag.pictures().next().unwrap().data, png_image(8, 8));
+    }
+
+    #[tokio::test]
+    async fn test_tag_episode_file_requires_download() {
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+        let podcast = db
+            .add_podcast(
+                "Tagged Show",
+                "https://example.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let episode = db
+            .add_episode(
+                podcast.id,
+                "Not Downloaded",
+                None,
+                "https://example.com/1.mp3",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let manager = EpisodeManager::new();
+        assert!(manager
+            .tag_episode_file(&db, episode, id3::Version::Id3v23)
+            .await
+            .is_err());
+        assert!(matches!(
+            manager
+                .tag_episode_file(&db, 999, id3::Version::Id3v23)
+                .await,
+            Err(PodPicoError::EpisodeNotFound(999))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_save_feed_episodes_keeps_chapters_url() {
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+        let podcast = db
+            .add_podcast("Chapters", "https://example.com/feed.xml", None, None, None)
+            .await
+            .unwrap();
+        let channel = rss::Channel::read_from(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:podcast="https://podcastindex.org/namespace/1.0"><channel>
+            <title>Chapters</title><link>https://example.com</link><description>Chapters</description>
+            <item><title>With Chapters</title>
+                <enclosure url="https://example.com/1.mp3" type="audio/mpeg" length="1000"/>
+                <podcast:chapters url="https://example.com/1.json" type="application/json+chapters"/></item>
+            <item><title>Without</title>
+                <enclosure url="https://example.com/2.mp3" type="audio/mpeg" length="1000"/></item>
+            </channel></rss>"#
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let ids = EpisodeManager::new()
+            .save_feed_episodes(&db, podcast.id, channel.items())
+            .await
+            .unwrap();
+
+        let with_chapters = db.get_episode_by_id(ids[0]).await.unwrap();
+        assert_eq!(
+            with_chapters.chapters_url.as_deref(),
+            Some("https://example.com/1.json")
+        );
+        assert!(db
+            .get_episode_by_id(ids[1])
+            .await
+            .unwrap()
+            .chapters_url
+            .is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_analyze_episode_loudness_stores_measurement() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let encoder = temp_dir.path().join("fake-ffmpeg");
+        std::fs::write(
+            &encoder,
+            "#!/bin/sh\nprintf 'Summary:\\n  I: -20.0 LUFS\\n  Peak: -3.0 dBFS\\n' >&2\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&encoder, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+        let podcast = db
+            .add_podcast(
+                "Loud Show",
+                "https://example.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let episode = db
+            .add_episode(
+                podcast.id,
+                "Quiet Episode",
+                None,
+                "https://example.com/1.mp3",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let file_path = temp_dir.path().join("episode.mp3");
+        std::fs::write(&file_path, [0xFF, 0xFB, 0x90, 0x64, 0, 0, 0, 0]).unwrap();
+        db.update_episode_downloaded_status(episode, true, Some(&file_path.to_string_lossy()))
+            .await
+            .unwrap();
+
+        let measurement = EpisodeManager::new()
+            .analyze_episode_loudness(&db, episode, &encoder, true)
+            .await
+            .unwrap();
+        assert_eq!(measurement.integrated_lufs, -20.0);
+
+        let stored = db.get_episode_by_id(episode).await.unwrap();
+        assert_eq!(stored.loudness_lufs, Some(-20.0));
+        assert_eq!(stored.true_peak_dbtp, Some(-3.0));
+        let tag = id3::Tag::read_from_path(&file_path).unwrap();
+        assert!(tag
+            .extended_texts()
+            .any(|text| text.description == "REPLAYGAIN_TRACK_GAIN" && text.value == "+2.00 dB"));
+    }
+
+    fn png_image(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbImage::from_pixel(width, height, image::Rgb([10, 120, 200]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_process_new_episodes_unknown_podcast() {
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+
+        let result = EpisodeManager::new()
+            .process_new_episodes(&db, &RssManager::new(), 999)
+            .await;
+        assert!(matches!(result, Err(PodPicoError::PodcastNotFound(999))));
+    }
+
+    fn downloaded_episode(id: i64, podcast_id: i64, published: &str) -> Episode {
+        Episode {
+            podcast_id,
+            downloaded: true,
+            local_file_path: Some(format!("/episodes/{}.mp3", id)),
+            ..test_episode(id, &format!("Episode {}", id), published, Some(100))
+        }
+    }
+
+    fn retention(listened_days: Option<i64>, keep_latest: Option<i64>) -> RetentionPolicy {
+        RetentionPolicy {
+            delete_listened_after_days: listened_days,
+            keep_latest_downloads_per_podcast: keep_latest,
+            ..RetentionPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_cleanup_selects_listened_episodes_after_threshold() {
+        let mut old = downloaded_episode(1, 1, "Wed, 01 Mar 2023 00:00:00 +0000");
+        old.status = "listened".to_string();
+        old.listened_at = Some("2023-03-01 12:00:00".to_string());
+        let mut recent = downloaded_episode(2, 1, "Mon, 06 Mar 2023 00:00:00 +0000");
+        recent.status = "listened".to_string();
+        recent.listened_at = Some("2023-03-08 12:00:00".to_string());
+        let unlistened = downloaded_episode(3, 1, "Wed, 01 Mar 2023 00:00:00 +0000");
+        let episodes = vec![old, recent, unlistened];
+
+        let selected =
+            EpisodeManager::new().select_for_cleanup(&retention(Some(7), None), &episodes, now());
+
+        let ids: Vec<i64> = selected.iter().map(|(e, _)| e.id).collect();
+        assert_eq!(ids, vec![1]);
+        assert!(selected[0].1.contains("7 days"));
+    }
+
+    #[test]
+    fn test_cleanup_keeps_latest_downloads_per_podcast() {
+        let episodes = vec![
+            downloaded_episode(1, 1, "Wed, 01 Mar 2023 00:00:00 +0000"),
+            downloaded_episode(2, 1, "Mon, 06 Mar 2023 00:00:00 +0000"),
+            downloaded_episode(3, 1, "Thu, 09 Mar 2023 00:00:00 +0000"),
+            downloaded_episode(4, 2, "Wed, 01 Mar 2023 00:00:00 +0000"),
+        ];
+
+        let selected =
+            EpisodeManager::new().select_for_cleanup(&retention(None, Some(2)), &episodes, now());
+
+        let ids: Vec<i64> = selected.iter().map(|(e, _)| e.id).collect();
+        assert_eq!(ids, vec![1]);
+    }
+
+    #[test]
+    fn test_cleanup_never_selects_starred_or_on_device_episodes() {
+        let mut starred = downloaded_episode(1, 1, "Wed, 01 Mar 2023 00:00:00 +0000");
+        starred.starred = true;
+        let mut on_device = downloaded_episode(2, 1, "Thu, 02 Mar 2023 00:00:00 +0000");
+        on_device.on_device = true;
+        on_device.status = "listened".to_string();
+        on_device.listened_at = Some("2023-01-01 00:00:00".to_string());
+        let plain = downloaded_episode(3, 1, "Fri, 03 Mar 2023 00:00:00 +0000");
+        let latest = downloaded_episode(4, 1, "Thu, 09 Mar 2023 00:00:00 +0000");
+        let episodes = vec![starred, on_device, plain, latest];
+
+        let selected = EpisodeManager::new().select_for_cleanup(
+            &retention(Some(1), Some(1)),
+            &episodes,
+            now(),
+        );
+
+        let ids: Vec<i64> = selected.iter().map(|(e, _)| e.id).collect();
+        assert_eq!(ids, vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_old_episodes_dry_run_and_delete() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+        let file_manager = FileManager::new(&temp_dir.path().to_string_lossy());
+        let podcast = db
+            .add_podcast(
+                "Retention",
+                "https://example.com/feed.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let mut file_paths = Vec::new();
+        for (i, date) in [
+            "Wed, 01 Mar 2023 00:00:00 +0000",
+            "Thu, 09 Mar 2023 00:00:00 +0000",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let episode_id = db
+                .add_episode(
+                    podcast.id,
+                    &format!("Episode {}", i),
+                    None,
+                    &format!("https://example.com/{}.mp3", i),
+                    Some(date),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            let path = temp_dir.path().join(format!("{}.mp3", i));
+            std::fs::write(&path, b"audio").unwrap();
+            db.update_episode_downloaded_status(episode_id, true, Some(&path.to_string_lossy()))
+                .await
+                .unwrap();
+            file_paths.push(path);
+        }
+
+        let manager = EpisodeManager::new();
+        let policy = retention(None, Some(1));
+
+        let dry_run = manager
+            .cleanup_old_episodes(&db, &file_manager, &policy, true, now())
+            .await
+            .unwrap();
+        assert!(dry_run.dry_run);
+        assert_eq!(dry_run.removed.len(), 1);
+        assert_eq!(dry_run.freed_bytes, 5);
+        assert!(file_paths[0].exists());
+
+        let report = manager
+            .cleanup_old_episodes(&db, &file_manager, &policy, false, now())
+            .await
+            .unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert!(report.errors.is_empty());
+        assert!(!file_paths[0].exists());
+        assert!(file_paths[1].exists());
+        assert_eq!(db.get_downloaded_episodes().await.unwrap().len(), 1);
+    }
+
+    async fn library_with_downloads(
+        library: &Path,
+        podcasts: &[&str],
+    ) -> (DatabaseManager, Vec<(i64, PathBuf)>) {
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+
+        let mut files = Vec::new();
+        for (i, name) in podcasts.iter().enumerate() {
+            let podcast = db
+                .add_podcast(
+                    name,
+                    &format!("https://example.com/{}.xml", i),
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            let episode_id = db
+                .add_episode(
+                    podcast.id,
+                    "Episode",
+                    None,
+                    &format!("https://example.com/{}.mp3", i),
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            let path = library.join(name).join("episode.mp3");
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, name.as_bytes()).unwrap();
+            db.update_episode_downloaded_status(episode_id, true, Some(&path.to_string_lossy()))
+                .await
+                .unwrap();
+            files.push((episode_id, path));
+        }
+        (db, files)
+    }
+
+    #[tokio::test]
+    async fn test_relocate_library_moves_files_and_paths() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_library = temp_dir.path().join("old");
+        let new_library = temp_dir.path().join("new");
+        let (db, files) = library_with_downloads(&old_library, &["Alpha", "Beta"]).await;
+        let file_manager = FileManager::new(&old_library.to_string_lossy());
+
+        // A downloaded episode whose file disappeared is reported, not fatal
+        std::fs::remove_file(&files[1].1).unwrap();
+
+        let report = EpisodeManager::new()
+            .relocate_library(&db, &file_manager, &new_library)
+            .await
+            .unwrap();
+
+        assert_eq!(report.moved_files, 1);
+        assert_eq!(report.moved_bytes, 5);
+        assert_eq!(report.skipped.len(), 1);
+        let moved = new_library.join("Alpha").join("episode.mp3");
+        assert_eq!(std::fs::read(&moved).unwrap(), b"Alpha");
+        assert!(!files[0].1.exists());
+        assert!(!old_library.join("Alpha").exists());
+
+        let episodes = db.get_downloaded_episodes().await.unwrap();
+        let alpha = episodes.iter().find(|e| e.id == files[0].0).unwrap();
+        assert_eq!(
+            alpha.local_file_path.as_deref(),
+            Some(moved.to_string_lossy().as_ref())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_relocate_library_rolls_back_on_failure() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_library = temp_dir.path().join("old");
+        let new_library = temp_dir.path().join("new");
+        let (db, files) = library_with_downloads(&old_library, &["Alpha", "Beta"]).await;
+        let file_manager = FileManager::new(&old_library.to_string_lossy());
+
+        // A plain file where Beta's directory has to go makes the second move fail
+        std::fs::create_dir_all(&new_library).unwrap();
+        std::fs::write(new_library.join("Beta"), b"in the way").unwrap();
+
+        let result = EpisodeManager::new()
+            .relocate_library(&db, &file_manager, &new_library)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(PodPicoError::LibraryRelocationFailed(_))
+        ));
+        for (episode_id, path) in &files {
+            assert!(path.exists(), "{:?} should be restored", path);
+            let episodes = db.get_downloaded_episodes().await.unwrap();
+            let episode = episodes.iter().find(|e| e.id == *episode_id).unwrap();
+            assert_eq!(
+                episode.local_file_path.as_deref(),
+                Some(path.to_string_lossy().as_ref())
+            );
+        }
+        assert!(!new_library.join("Alpha").join("episode.mp3").exists());
+    }
+
+    #[tokio::test]
+    async fn test_relocate_library_rejects_nested_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_library = temp_dir.path().join("old");
+        let (db, _files) = library_with_downloads(&old_library, &["Alpha"]).await;
+        let file_manager = FileManager::new(&old_library.to_string_lossy());
+
+        let result = EpisodeManager::new()
+            .relocate_library(&db, &file_manager, &old_library.join("inner"))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(PodPicoError::LibraryRelocationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scan_library_reconciles_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let library = temp_dir.path().join("library");
+        let (db, files) = library_with_downloads(&library, &["Alpha", "Beta"]).await;
+        let file_manager = FileManager::new(&library.to_string_lossy());
+        let podcast_id = db.get_podcasts().await.unwrap()[0].id;
+
+        // Beta's file is gone; Alpha has been listened to but its file is still tracked
+        std::fs::remove_file(&files[1].1).unwrap();
+        db.update_episode_status(files[0].0, "listened")
+            .await
+            .unwrap();
+
+        // Not yet downloaded episodes: one named like its URL, one with a known size
+        let by_name = db
+            .add_episode(
+                podcast_id,
+                "By name",
+                None,
+                "https://cdn.example.com/show-42.mp3",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let by_size = db
+            .add_episode(
+                podcast_id,
+                "By size",
+                None,
+                "https://example.com/x.mp3",
+                None,
+                None,
+                Some(11),
+            )
+            .await
+            .unwrap();
+
+        let legacy_dir = library.join(podcast_id.to_string());
+        std::fs::create_dir_all(&legacy_dir).unwrap();
+        std::fs::write(legacy_dir.join("show-42.mp3"), b"named").unwrap();
+        std::fs::write(library.join("renamed.mp3"), b"eleven byte").unwrap();
+        std::fs::write(library.join("copy.mp3"), b"Alpha").unwrap();
+        std::fs::write(library.join("unknown.ogg"), b"?").unwrap();
+        std::fs::write(library.join("notes.txt"), b"not audio").unwrap();
+
+        let report = EpisodeManager::new()
+            .scan_library(&db, &file_manager)
+            .await
+            .unwrap();
+
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].episode_id, files[1].0);
+        let beta = db.get_episode_by_id(files[1].0).await.unwrap();
+        assert!(!beta.downloaded);
+
+        let suggestion = |name: &str| {
+            report
+                .untracked
+                .iter()
+                .find(|file| file.file_path.ends_with(name))
+                .unwrap_or_else(|| panic!("{} should be untracked", name))
+                .suggested_match
+                .as_ref()
+                .map(|m| (m.episode_id, m.reason.clone()))
+        };
+        assert_eq!(report.untracked.len(), 4);
+        assert_eq!(
+            suggestion("show-42.mp3"),
+            Some((by_name, "filename".to_string()))
+        );
+        assert_eq!(
+            suggestion("renamed.mp3"),
+            Some((by_size, "size".to_string()))
+        );
+        assert_eq!(
+            suggestion("copy.mp3"),
+            Some((files[0].0, "duplicate".to_string()))
+        );
+        assert_eq!(suggestion("unknown.ogg"), None);
+        assert_eq!(report.reclaimable_bytes, 5 + 11 + 5 + 1);
+    }
+
+    fn write_tagged_file(path: &Path, title: &str, album: Option<&str>, duration_ms: Option<u32>) {
+        use id3::TagLike;
+
+        std::fs::write(path, b"audio frames").unwrap();
+        let mut tag = id3::Tag::new();
+        tag.set_title(title);
+        if let Some(album) = album {
+            tag.set_album(album);
+        }
+        if let Some(duration_ms) = duration_ms {
+            tag.set_duration(duration_ms);
+        }
+        tag.write_to_path(path, id3::Version::Id3v24).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_files_matches_by_filename_title_and_duration() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let library = temp_dir.path().join("library");
+        let source = temp_dir.path().join("other-tool");
+        std::fs::create_dir_all(source.join("nested")).unwrap();
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.initialize().await.unwrap();
+        let file_manager = FileManager::new(&library.to_string_lossy());
+        let podcast = db
+            .add_podcast(
+                "Import Show",
+                "https://example.com/import.xml",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let mut ids = Vec::new();
+        for (title, url, duration) in [
+            ("First", "https://cdn.example.com/ep-001.mp3", None),
+            ("The Second: Episode!", "https://example.com/2.mp3", None),
+            ("Third", "https://example.com/3.mp3", Some(1800)),
+        ] {
+            ids.push(
+                db.add_episode(podcast.id, title, None, url, None, duration, None)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let (by_name, by_title, by_duration) = (ids[0], ids[1], ids[2]);
+        // Already listened to elsewhere, but still worth keeping a copy of
+        db.update_episode_status(by_duration, "listened")
+            .await
+            .unwrap();
+
+        std::fs::write(source.join("EP-001.mp3"), b"first").unwrap();
+        write_tagged_file(
+            &source.join("nested").join("second.mp3"),
+            "the second episode",
+            Some("Import Show"),
+            None,
+        );
+        write_tagged_file(&source.join("third.mp3"), "Untitled", None, Some(1_801_000));
+        std::fs::write(source.join("stranger.mp3"), b"?").unwrap();
+
+        let report = EpisodeManager::new()
+            .import_files(&db, &file_manager, &source, false)
+            .await
+            .unwrap();
+
+        let reasons: HashMap<i64, String> = report
+            .imported
+            .iter()
+            .map(|file| (file.episode_id, file.reason.clone()))
+            .collect();
+        assert_eq!(reasons.get(&by_name).map(String::as_str), Some("filename"));
+        assert_eq!(reasons.get(&by_title).map(String::as_str), Some("title"));
+        assert_eq!(
+            reasons.get(&by_duration).map(String::as_str),
+            Some("duration")
+        );
+        assert_eq!(report.unmatched.len(), 1);
+        assert!(report.errors.is_empty());
+
+        // Copies land in the library layout and the originals stay
+        assert!(source.join("EP-001.mp3").exists());
+        let downloaded = db.get_downloaded_episodes().await.unwrap();
+        assert_eq!(downloaded.len(), 3);
+        for episode in downloaded {
+            let path = PathBuf::from(episode.local_file_path.unwrap());
+            assert!(path.starts_with(&library));
+            assert!(path.exists());
+        }
+    }
+
+    #[test]
+    fn test_normalize_title() {
+        assert_eq!(
+            normalize_title("  The Second: Episode! "),
+            "the second episode"
+        );
+        assert_eq!(normalize_title("Ep. #42 — Q&A"), "ep 42 q a");
     }
 }
@@ -46,6 +46,30 @@ pub enum PodPicoError {
     #[error("Download in progress")]
     DownloadInProgress,
 
+    #[error("Insufficient disk space: need {needed} bytes, {available} bytes available")]
+    InsufficientDiskSpace { needed: u64, available: u64 },
+
+    #[error("Invalid filename template: {0}")]
+    InvalidFilenameTemplate(String),
+
+    #[error("Invalid download window: {0}")]
+    InvalidDownloadWindow(String),
+
+    #[error("Invalid tracking prefix: {0}")]
+    InvalidTrackingPrefix(String),
+
+    #[error("Unsupported format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("Transcoding failed: {0}")]
+    TranscodeFailed(String),
+
+    #[error("Artwork error: {0}")]
+    ArtworkError(String),
+
+    #[error("Library relocation failed: {0}")]
+    LibraryRelocationFailed(String),
+
     #[error("Generic error: {0}")]
     Generic(String),
 }
@@ -0,0 +1,116 @@
+// Event emission module for PodPico
+// Pushes download and transfer progress to the frontend via Tauri events
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+pub const DOWNLOAD_FINISHED_EVENT: &str = "download-finished";
+pub const TRANSFER_PROGRESS_EVENT: &str = "transfer-progress";
+pub const LIBRARY_RELOCATION_PROGRESS_EVENT: &str = "library-relocation-progress";
+
+/// Minimum time between two progress events for the same download or transfer
+pub const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Emits events to the frontend when an `AppHandle` is attached.
+/// Without a handle (e.g. in tests) every emit is a no-op.
+#[derive(Clone, Default)]
+pub struct EventEmitter {
+    app_handle: Option<AppHandle>,
+    last_emitted: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl EventEmitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_app_handle(app_handle: AppHandle) -> Self {
+        Self {
+            app_handle: Some(app_handle),
+            last_emitted: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Emits an event immediately and resets the throttle window for `key`
+    pub fn emit<S: Serialize + Clone>(&self, event: &str, key: &str, payload: S) {
+        if let Ok(mut last_emitted) = self.last_emitted.lock() {
+            last_emitted.remove(key);
+        }
+        self.send(event, payload);
+    }
+
+    /// Emits an event unless one was already emitted for `key` within `PROGRESS_EMIT_INTERVAL`
+    pub fn emit_throttled<S: Serialize + Clone>(&self, event: &str, key: &str, payload: S) {
+        if self.should_emit(key, Instant::now()) {
+            self.send(event, payload);
+        }
+    }
+
+    fn should_emit(&self, key: &str, now: Instant) -> bool {
+        let mut last_emitted = match self.last_emitted.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        match last_emitted.get(key) {
+            Some(last) if now.duration_since(*last) < PROGRESS_EMIT_INTERVAL => false,
+            _ => {
+                last_emitted.insert(key.to_string(), now);
+                true
+            }
+        }
+    }
+
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(event, payload) {
+                log::warn!("Failed to emit {} event: {}", event, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_allows_first_event() {
+        let emitter = EventEmitter::new();
+        assert!(emitter.should_emit("download-1", Instant::now()));
+    }
+
+    #[test]
+    fn test_throttle_suppresses_events_within_interval() {
+        let emitter = EventEmitter::new();
+        let start = Instant::now();
+
+        assert!(emitter.should_emit("download-1", start));
+        assert!(!emitter.should_emit("download-1", start + Duration::from_millis(100)));
+        assert!(emitter.should_emit("download-1", start + PROGRESS_EMIT_INTERVAL));
+    }
+
+    #[test]
+    fn test_throttle_is_tracked_per_key() {
+        let emitter = EventEmitter::new();
+        let now = Instant::now();
+
+        assert!(emitter.should_emit("download-1", now));
+        assert!(emitter.should_emit("download-2", now));
+        assert!(!emitter.should_emit("download-1", now));
+    }
+
+    #[test]
+    fn test_immediate_emit_resets_throttle() {
+        let emitter = EventEmitter::new();
+        let now = Instant::now();
+
+        assert!(emitter.should_emit("transfer-1", now));
+        emitter.emit(TRANSFER_PROGRESS_EVENT, "transfer-1", 100.0);
+        assert!(emitter.should_emit("transfer-1", now));
+    }
+}
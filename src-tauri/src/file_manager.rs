@@ -1,12 +1,30 @@
 // File management module for PodPico
 // Handles episode downloads, local file storage, and file operations
 
+use crate::audio_format::{
+    choose_audio_extension, is_audio_file, FALLBACK_AUDIO_EXTENSION, SNIFF_HEADER_LEN,
+};
+use crate::commands::{
+    DownloadFinishedEvent, DownloadProgressResponse, DownloadWindow, Episode,
+    LibraryRelocationProgress,
+};
+use crate::episode_manager::parse_published_date;
 use crate::error::PodPicoError;
+use crate::events::{
+    EventEmitter, DOWNLOAD_FINISHED_EVENT, DOWNLOAD_PROGRESS_EVENT,
+    LIBRARY_RELOCATION_PROGRESS_EVENT,
+};
+use crate::throttle::{
+    is_within_download_windows, time_until_download_window, RateLimiter, WINDOW_RECHECK_INTERVAL,
+};
+use crate::tracking::strip_tracking_prefixes;
+use chrono::format::{Item, StrftimeItems};
 use reqwest;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tauri::AppHandle;
 use tokio::fs;
 use tokio::sync::Mutex;
 
@@ -23,6 +41,8 @@ pub struct DownloadProgress {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DownloadStatus {
+    /// Waiting for the download window; nothing has been written yet
+    Queued,
     Pending,
     InProgress,
     Completed,
@@ -30,26 +50,156 @@ pub enum DownloadStatus {
     Cancelled,
 }
 
+/// Free space kept available on the download volume unless configured otherwise
+pub const DEFAULT_DISK_SPACE_RESERVE_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Layout of downloaded files below the download directory.
+/// Placeholders: `{podcast}`, `{podcast_id}`, `{title}`, `{episode_id}`, `{date}` or
+/// `{date:<strftime format>}`, `{basename}` (file name from the URL) and `{ext}`.
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{podcast}/{date:%Y-%m-%d} - {title}.{ext}";
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const UNDATED: &str = "undated";
+const MAX_PLACEHOLDER_CHARS: usize = 100;
+const MAX_COMPONENT_BYTES: usize = 200;
+const MAX_COLLISION_SUFFIX: u32 = 1000;
+const PARTIAL_DOWNLOAD_EXTENSION: &str = "part";
+
 pub struct FileManager {
     download_directory: PathBuf,
     client: reqwest::Client,
     downloads: Arc<Mutex<HashMap<i64, DownloadProgress>>>,
+    disk_space_reserve: u64,
+    filename_template: String,
+    /// Shared by all downloads of this manager
+    global_rate_limit: Option<Arc<RateLimiter>>,
+    /// Bytes per second each single download may use
+    per_download_rate_limit: Option<u64>,
+    download_windows: Vec<DownloadWindow>,
+    /// Redirect prefixes stripped from enclosure URLs; `None` when privacy mode is off
+    tracking_prefixes: Option<Vec<String>>,
+    events: EventEmitter,
 }
 
 impl FileManager {
     pub fn new(download_directory: &str) -> Self {
         Self {
             download_directory: PathBuf::from(download_directory),
+            // Time out stalled connections rather than whole downloads, which can
+            // legitimately take long on slow or throttled connections
             client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(300)) // 5 minute timeout for downloads
+                .connect_timeout(std::time::Duration::from_secs(30))
+                .read_timeout(std::time::Duration::from_secs(300))
                 .build()
                 .unwrap_or_else(|_| reqwest::Client::new()),
             downloads: Arc::new(Mutex::new(HashMap::new())),
+            disk_space_reserve: DEFAULT_DISK_SPACE_RESERVE_BYTES,
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            global_rate_limit: None,
+            per_download_rate_limit: None,
+            download_windows: Vec::new(),
+            tracking_prefixes: None,
+            events: EventEmitter::new(),
+        }
+    }
+
+    /// Pushes `download-progress` and `download-finished` events through the given app handle
+    pub fn with_app_handle(mut self, app_handle: AppHandle) -> Self {
+        self.events = EventEmitter::with_app_handle(app_handle);
+        self
+    }
+
+    /// Sets how many bytes must remain free on the download volume after a download
+    pub fn with_disk_space_reserve(mut self, reserve_bytes: u64) -> Self {
+        self.disk_space_reserve = reserve_bytes;
+        self
+    }
+
+    /// Sets the template used to lay out downloaded files, see `DEFAULT_FILENAME_TEMPLATE`.
+    /// Invalid templates are rejected and the current template is kept.
+    pub fn with_filename_template(mut self, template: &str) -> Self {
+        match validate_filename_template(template) {
+            Ok(()) => self.filename_template = template.to_string(),
+            Err(e) => log::warn!("Ignoring filename template '{}': {}", template, e),
         }
+        self
+    }
+
+    /// Caps download bandwidth in KB/s across all downloads and for each single download
+    pub fn with_rate_limits(
+        mut self,
+        global_kbps: Option<u64>,
+        per_download_kbps: Option<u64>,
+    ) -> Self {
+        self.global_rate_limit = global_kbps.map(|kbps| Arc::new(RateLimiter::from_kbps(kbps)));
+        self.per_download_rate_limit = per_download_kbps.map(|kbps| kbps * 1024);
+        self
+    }
+
+    /// Restricts queued downloads to the given daily time windows (local time)
+    pub fn with_download_windows(mut self, windows: Vec<DownloadWindow>) -> Self {
+        self.download_windows = windows;
+        self
+    }
+
+    /// Privacy mode: downloads bypass the given tracking redirect prefixes (see
+    /// `DEFAULT_TRACKING_PREFIXES`), falling back to the original URL on failure
+    pub fn with_tracking_prefixes(mut self, prefixes: Option<Vec<String>>) -> Self {
+        self.tracking_prefixes = prefixes;
+        self
     }
 
     pub fn clone_manager(&self) -> Self {
-        Self::new(&self.download_directory.to_string_lossy())
+        self.with_download_directory(&self.download_directory.to_string_lossy())
+    }
+
+    /// Returns a manager with the same settings that stores downloads in
+    /// `download_directory`. Download progress is shared, so queued and running
+    /// downloads stay visible through the new manager.
+    pub fn with_download_directory(&self, download_directory: &str) -> Self {
+        let mut manager = Self::new(download_directory)
+            .with_disk_space_reserve(self.disk_space_reserve)
+            .with_filename_template(&self.filename_template)
+            .with_download_windows(self.download_windows.clone())
+            .with_tracking_prefixes(self.tracking_prefixes.clone());
+        manager.downloads = self.downloads.clone();
+        manager.global_rate_limit = self.global_rate_limit.clone();
+        manager.per_download_rate_limit = self.per_download_rate_limit;
+        manager.events = self.events.clone();
+        manager
+    }
+
+    pub fn download_directory(&self) -> &Path {
+        &self.download_directory
+    }
+
+    /// How long queued downloads should wait before checking the download window
+    /// again, or `None` when a window is open or no windows are configured
+    pub fn download_window_delay(&self) -> Option<std::time::Duration> {
+        let now = chrono::Local::now().time();
+        if is_within_download_windows(&self.download_windows, now) {
+            return None;
+        }
+        let wait = time_until_download_window(&self.download_windows, now);
+        Some(wait.clamp(std::time::Duration::from_secs(1), WINDOW_RECHECK_INTERVAL))
+    }
+
+    /// Marks an episode as waiting for the download window. Queued downloads don't
+    /// count as active, so they never hold up a library relocation.
+    pub async fn mark_queued(&self, episode_id: i64) {
+        self.update_download_status(episode_id, DownloadStatus::Queued, 0.0, 0, 0)
+            .await;
+    }
+
+    /// True while any download is pending or writing to the download directory
+    pub async fn has_active_downloads(&self) -> bool {
+        let downloads = self.downloads.lock().await;
+        downloads.values().any(|progress| {
+            matches!(
+                progress.status,
+                DownloadStatus::Pending | DownloadStatus::InProgress
+            )
+        })
     }
 
     pub async fn initialize(&self) -> Result<(), PodPicoError> {
@@ -58,53 +208,37 @@ impl FileManager {
         Ok(())
     }
 
-    pub async fn download_episode(
-        &self,
-        episode_url: &str,
-        episode_id: i64,
-        podcast_id: i64,
-    ) -> Result<String, PodPicoError> {
+    pub async fn download_episode(&self, episode: &Episode) -> Result<String, PodPicoError> {
+        let episode_url = episode.episode_url.as_str();
+        let episode_id = episode.id;
         log::info!(
             "Starting download for episode {} from {} (User Story #3)",
             episode_id,
             episode_url
         );
 
-        // Create podcast directory first
-        let podcast_dir = self.download_directory.join(podcast_id.to_string());
-        fs::create_dir_all(&podcast_dir).await?;
-
-        // Extract filename from URL or use episode ID
-        let filename = self.extract_filename_from_url(episode_url, episode_id);
-        let file_path = podcast_dir.join(&filename);
-
         // Check if already downloaded BEFORE any other operations
-        if file_path.exists() {
-            log::info!(
-                "Episode {} already downloaded at {:?}",
-                episode_id,
-                file_path
-            );
+        if let Some(existing) = episode
+            .local_file_path
+            .as_ref()
+            .filter(|path| Path::new(path).is_file())
+        {
+            log::info!("Episode {} already downloaded at {}", episode_id, existing);
 
             // User Story #3 Acceptance Criteria: Progress indicator appears immediately
-            let mut downloads = self.downloads.lock().await;
-            downloads.insert(
-                episode_id,
-                DownloadProgress {
-                    episode_id,
-                    total_bytes: 0,
-                    downloaded_bytes: 0,
-                    percentage: 100.0,
-                    speed_bytes_per_sec: 0.0,
-                    eta_seconds: None,
-                    status: DownloadStatus::Completed,
-                },
-            );
-            drop(downloads);
-
-            return Ok(file_path.to_string_lossy().to_string());
+            self.update_download_status(episode_id, DownloadStatus::Completed, 100.0, 0, 0)
+                .await;
+            let result = Ok(existing.clone());
+            self.emit_download_finished(episode_id, &result);
+            return result;
         }
 
+        // Download into a claimed partial file next to the final location; the real
+        // extension is only known once the response and the first bytes are in
+        let partial_path = self
+            .claim_unique_path(&self.episode_file_path(episode, PARTIAL_DOWNLOAD_EXTENSION))
+            .await?;
+
         // User Story #3 Acceptance Criteria: Progress indicator appears immediately
         let mut downloads = self.downloads.lock().await;
         downloads.insert(
@@ -121,27 +255,48 @@ impl FileManager {
         );
         drop(downloads);
 
-        // User Story #3 Acceptance Criteria: Check disk space before download
-        self.check_disk_space(&self.download_directory).await?;
-
         // User Story #3 Acceptance Criteria: Download with progress tracking
-        let result = self
-            .download_with_progress(episode_url, &file_path, episode_id)
-            .await;
+        let result = match self
+            .download_bypassing_trackers(episode_url, &partial_path, episode_id)
+            .await
+        {
+            Ok(content_type) => {
+                self.finish_download(episode, &partial_path, content_type.as_deref())
+                    .await
+            }
+            Err(e) => Err(e),
+        };
 
-        match result {
+        if result.is_err() {
+            // Release the claimed name, ignoring errors as the file may never have been written
+            let _ = fs::remove_file(&partial_path).await;
+        }
+
+        // Keep the transferred byte counts and average speed for the download history
+        let (downloaded, total, speed) = self
+            .get_download_progress(episode_id)
+            .await
+            .map(|progress| {
+                (
+                    progress.downloaded_bytes,
+                    progress.total_bytes,
+                    progress.speed_bytes_per_sec,
+                )
+            })
+            .unwrap_or_default();
+
+        match &result {
             Ok(path) => {
                 log::info!("Successfully downloaded episode {} to {}", episode_id, path);
                 self.update_download_status_with_speed(
                     episode_id,
                     DownloadStatus::Completed,
                     100.0,
-                    0,
-                    0,
-                    0.0,
+                    downloaded,
+                    total.max(downloaded),
+                    speed,
                 )
                 .await;
-                Ok(path)
             }
             Err(e) => {
                 log::error!("Failed to download episode {}: {}", episode_id, e);
@@ -149,22 +304,93 @@ impl FileManager {
                     episode_id,
                     DownloadStatus::Failed(e.to_string()),
                     0.0,
-                    0,
-                    0,
-                    0.0,
+                    downloaded,
+                    total,
+                    speed,
                 )
                 .await;
-                Err(e)
             }
         }
+
+        self.emit_download_finished(episode_id, &result);
+        result
     }
 
-    async fn download_with_progress(
+    /// Downloads from the URL behind any tracking redirects when privacy mode is on,
+    /// retrying with the original URL if the direct one fails
+    async fn download_bypassing_trackers(
         &self,
         url: &str,
         file_path: &PathBuf,
         episode_id: i64,
+    ) -> Result<Option<String>, PodPicoError> {
+        let direct_url = self
+            .tracking_prefixes
+            .as_ref()
+            .and_then(|prefixes| strip_tracking_prefixes(url, prefixes));
+
+        if let Some(direct_url) = direct_url {
+            log::info!(
+                "Downloading episode {} from {} without tracking redirects",
+                episode_id,
+                direct_url
+            );
+            match self
+                .download_with_progress(&direct_url, file_path, episode_id)
+                .await
+            {
+                Err(PodPicoError::NetworkError(e)) => {
+                    log::warn!(
+                        "Direct download of episode {} failed ({}), using the original URL",
+                        episode_id,
+                        e
+                    );
+                }
+                result => return result,
+            }
+        }
+
+        self.download_with_progress(url, file_path, episode_id)
+            .await
+    }
+
+    /// Names a completed partial download after its detected format and moves it
+    /// into place, returning the final path
+    async fn finish_download(
+        &self,
+        episode: &Episode,
+        partial_path: &Path,
+        content_type: Option<&str>,
     ) -> Result<String, PodPicoError> {
+        let header = read_file_header(partial_path, SNIFF_HEADER_LEN).await?;
+        let extension = choose_audio_extension(
+            &header,
+            content_type,
+            episode.enclosure_type.as_deref(),
+            &episode.episode_url,
+        );
+
+        let file_path = self
+            .claim_unique_path(&self.episode_file_path(episode, &extension))
+            .await?;
+        if let Err(e) = fs::rename(partial_path, &file_path).await {
+            let _ = fs::remove_file(&file_path).await;
+            return Err(PodPicoError::IoError(format!(
+                "Failed to move download into place: {}",
+                e
+            )));
+        }
+
+        Ok(file_path.to_string_lossy().to_string())
+    }
+
+    /// Streams `url` into `file_path` and returns the response's Content-Type
+    async fn download_with_progress(
+        &self,
+        url: &str,
+        file_path: &PathBuf,
+        episode_id: i64,
+    ) -> Result<Option<String>, PodPicoError> {
         use futures_util::StreamExt;
         use tokio::io::AsyncWriteExt;
 
@@ -189,6 +415,16 @@ impl FileManager {
         }
 
         let total_size = response.content_length().unwrap_or(0);
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        // User Story #3 Acceptance Criteria: Check disk space before download
+        let download_dir = file_path.parent().unwrap_or(&self.download_directory);
+        self.check_disk_space(download_dir, total_size).await?;
+
         let mut downloaded = 0u64;
         let mut file = tokio::fs::File::create(file_path)
             .await
@@ -196,6 +432,7 @@ impl FileManager {
 
         let start_time = std::time::Instant::now();
         let mut stream = response.bytes_stream();
+        let per_download_limit = self.per_download_rate_limit.map(RateLimiter::new);
 
         while let Some(chunk) = stream.next().await {
             let chunk = chunk
@@ -207,6 +444,15 @@ impl FileManager {
 
             downloaded += chunk.len() as u64;
 
+            // Throttle by waiting after each chunk; the per-download and global limits
+            // are both honoured
+            if let Some(limiter) = &per_download_limit {
+                limiter.acquire(chunk.len() as u64).await;
+            }
+            if let Some(limiter) = &self.global_rate_limit {
+                limiter.acquire(chunk.len() as u64).await;
+            }
+
             // User Story #3 Acceptance Criteria: Progress percentage tracking
             let percentage = if total_size > 0 {
                 (downloaded as f64 / total_size as f64) * 100.0
@@ -237,7 +483,7 @@ impl FileManager {
             .await
             .map_err(|e| PodPicoError::IoError(format!("Failed to sync file: {}", e)))?;
 
-        Ok(file_path.to_string_lossy().to_string())
+        Ok(content_type)
     }
 
     async fn update_download_status(
@@ -278,6 +524,10 @@ impl FileManager {
                 },
             );
         }
+
+        if let Some(progress) = downloads.get(&episode_id) {
+            self.emit_download_progress(progress);
+        }
     }
 
     pub async fn update_download_status_with_speed(
@@ -324,6 +574,59 @@ impl FileManager {
                 },
             );
         }
+
+        if let Some(progress) = downloads.get(&episode_id) {
+            self.emit_download_progress(progress);
+        }
+    }
+
+    /// Emits throttled `download-progress` events while a download is running
+    fn emit_download_progress(&self, progress: &DownloadProgress) {
+        if matches!(
+            progress.status,
+            DownloadStatus::Pending | DownloadStatus::InProgress
+        ) {
+            self.events.emit_throttled(
+                DOWNLOAD_PROGRESS_EVENT,
+                &format!("download-{}", progress.episode_id),
+                DownloadProgressResponse::from(progress),
+            );
+        }
+    }
+
+    /// Reports library relocation progress; the final update is never throttled
+    pub fn emit_relocation_progress(&self, progress: &LibraryRelocationProgress) {
+        let key = "library-relocation";
+        if progress.moved_files == progress.total_files {
+            self.events
+                .emit(LIBRARY_RELOCATION_PROGRESS_EVENT, key, progress.clone());
+        } else {
+            self.events
+                .emit_throttled(LIBRARY_RELOCATION_PROGRESS_EVENT, key, progress.clone());
+        }
+    }
+
+    /// Emits the `download-finished` event, bypassing the progress throttle
+    fn emit_download_finished(&self, episode_id: i64, result: &Result<String, PodPicoError>) {
+        let payload = match result {
+            Ok(path) => DownloadFinishedEvent {
+                episode_id,
+                success: true,
+                file_path: Some(path.clone()),
+                error: None,
+            },
+            Err(e) => DownloadFinishedEvent {
+                episode_id,
+                success: false,
+                file_path: None,
+                error: Some(e.to_string()),
+            },
+        };
+        self.events.emit(
+            DOWNLOAD_FINISHED_EVENT,
+            &format!("download-{}", episode_id),
+            payload,
+        );
     }
 
     pub async fn get_download_progress(&self, episode_id: i64) -> Option<DownloadProgress> {
@@ -331,28 +634,35 @@ impl FileManager {
         downloads.get(&episode_id).cloned()
     }
 
-    async fn check_disk_space(&self, directory: &Path) -> Result<(), PodPicoError> {
-        // User Story #3 Acceptance Criteria: Check disk space before download
-        // For now, just check if directory is writable
-        // TODO: Implement actual disk space check
-        if !directory.exists() {
-            return Ok(()); // Will be created
-        }
+    /// Fails with `InsufficientDiskSpace` unless the volume holding `directory`
+    /// can take `expected_bytes` and still keep the configured reserve free
+    pub async fn check_disk_space(
+        &self,
+        directory: &Path,
+        expected_bytes: u64,
+    ) -> Result<(), PodPicoError> {
+        let needed = expected_bytes.saturating_add(self.disk_space_reserve);
+        let directory = directory.to_path_buf();
+        let available = tokio::task::spawn_blocking(move || available_disk_space(&directory))
+            .await
+            .map_err(|e| PodPicoError::IoError(format!("Disk space check failed: {}", e)))??;
 
-        let temp_file = directory.join(".podpico_space_check");
-        match tokio::fs::write(&temp_file, b"test").await {
-            Ok(_) => {
-                let _ = tokio::fs::remove_file(&temp_file).await;
-                Ok(())
-            }
-            Err(e) => Err(PodPicoError::IoError(format!(
-                "Insufficient disk space or permissions: {}",
-                e
-            ))),
+        log::info!(
+            "Disk space check: {} bytes needed (including {} byte reserve), {} bytes available",
+            needed,
+            self.disk_space_reserve,
+            available
+        );
+
+        if available < needed {
+            return Err(PodPicoError::InsufficientDiskSpace { needed, available });
         }
+
+        Ok(())
     }
 
-    fn extract_filename_from_url(&self, url: &str, episode_id: i64) -> String {
+    /// File name the legacy layout used for an episode: the URL basename or `<episode_id>.mp3`
+    pub fn extract_filename_from_url(&self, url: &str, episode_id: i64) -> String {
         // Try to extract filename from URL
         if let Some(filename) = url.split('/').next_back() {
             // Remove query parameters (everything after ?)
@@ -380,17 +690,124 @@ impl FileManager {
                     .collect::<String>();
 
                 // Ensure we have a valid extension
-                if sanitized.ends_with(".mp3")
-                    || sanitized.ends_with(".m4a")
-                    || sanitized.ends_with(".wav")
-                {
+                if is_audio_file(Path::new(&sanitized)) {
                     return sanitized;
                 }
             }
         }
 
-        // Fallback to episode ID with .mp3 extension
-        format!("{}.mp3", episode_id)
+        // Fallback to episode ID with the default extension
+        format!("{}.{}", episode_id, FALLBACK_AUDIO_EXTENSION)
+    }
+
+    /// Path below the download directory where the filename template places the episode
+    pub fn episode_file_path(&self, episode: &Episode, extension: &str) -> PathBuf {
+        let basename = self.extract_filename_from_url(&episode.episode_url, episode.id);
+        let basename = Path::new(&basename)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        self.download_directory.join(render_filename_template(
+            &self.filename_template,
+            episode,
+            &basename,
+            extension,
+        ))
+    }
+
+    /// Places an existing audio file into the library at the episode's templated path,
+    /// either as a copy or as a hard link (falling back to a copy across filesystems)
+    pub async fn import_file(
+        &self,
+        episode: &Episode,
+        source: &Path,
+        hard_link: bool,
+    ) -> Result<PathBuf, PodPicoError> {
+        let extension = source
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "mp3".to_string());
+        let target = self
+            .claim_unique_path(&self.episode_file_path(episode, &extension))
+            .await?;
+
+        if hard_link {
+            // hard_link refuses to replace the claimed placeholder
+            fs::remove_file(&target).await?;
+            match fs::hard_link(source, &target).await {
+                Ok(()) => return Ok(target),
+                Err(e) if is_cross_device_error(&e) => {
+                    log::info!("Cannot link across filesystems, copying {:?}", source)
+                }
+                Err(e) => {
+                    return Err(PodPicoError::IoError(format!(
+                        "Failed to link {}: {}",
+                        source.display(),
+                        e
+                    )))
+                }
+            }
+        }
+
+        if let Err(e) = fs::copy(source, &target).await {
+            let _ = fs::remove_file(&target).await;
+            return Err(PodPicoError::IoError(format!(
+                "Failed to copy {}: {}",
+                source.display(),
+                e
+            )));
+        }
+        Ok(target)
+    }
+
+    /// Atomically creates an empty file at `path`, or at `path` with a " (n)" suffix
+    /// when that name is taken, and returns the path that was created
+    async fn claim_unique_path(&self, path: &Path) -> Result<PathBuf, PodPicoError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        for attempt in 1..=MAX_COLLISION_SUFFIX {
+            let candidate = if attempt == 1 {
+                path.to_path_buf()
+            } else {
+                with_collision_suffix(path, attempt)
+            };
+
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&candidate)
+                .await
+            {
+                Ok(_) => return Ok(candidate),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => {
+                    return Err(PodPicoError::IoError(format!(
+                        "Failed to create file {}: {}",
+                        candidate.display(),
+                        e
+                    )))
+                }
+            }
+        }
+
+        Err(PodPicoError::IoError(format!(
+            "No free file name for {}",
+            path.display()
+        )))
+    }
+
+    /// True when `path` is an existing file inside the download directory
+    pub async fn is_in_library(&self, path: &Path) -> bool {
+        match (
+            fs::canonicalize(&self.download_directory).await,
+            fs::canonicalize(path).await,
+        ) {
+            (Ok(library), Ok(path)) => path.starts_with(library) && path.is_file(),
+            _ => false,
+        }
     }
 
     pub fn get_episode_path(&self, podcast_id: i64, episode_id: i64) -> PathBuf {
@@ -445,12 +862,406 @@ impl FileManager {
     }
 }
 
+/// Checks that a filename template only uses known placeholders and valid date formats
+pub fn validate_filename_template(template: &str) -> Result<(), PodPicoError> {
+    if template.trim().is_empty() {
+        return Err(PodPicoError::InvalidFilenameTemplate(
+            "template must not be empty".to_string(),
+        ));
+    }
+    if template.starts_with('/') || template.starts_with('\\') {
+        return Err(PodPicoError::InvalidFilenameTemplate(
+            "template must be relative to the download directory".to_string(),
+        ));
+    }
+
+    for placeholder in template_placeholders(template)? {
+        let (name, format) = split_placeholder(placeholder);
+        match (name, format) {
+            ("podcast" | "podcast_id" | "title" | "episode_id" | "basename" | "ext", None) => {}
+            ("date", None) => {}
+            ("date", Some(format)) if is_valid_date_format(format) => {}
+            ("date", Some(format)) => {
+                return Err(PodPicoError::InvalidFilenameTemplate(format!(
+                    "invalid date format '{}'",
+                    format
+                )))
+            }
+            _ => {
+                return Err(PodPicoError::InvalidFilenameTemplate(format!(
+                    "unknown placeholder '{{{}}}'",
+                    placeholder
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders a filename template into a relative path. Every placeholder value and
+/// every path component is sanitized, so the result is always a safe relative path.
+pub fn render_filename_template(
+    template: &str,
+    episode: &Episode,
+    basename: &str,
+    extension: &str,
+) -> PathBuf {
+    let template = if validate_filename_template(template).is_ok() {
+        template
+    } else {
+        DEFAULT_FILENAME_TEMPLATE
+    };
+    let published = episode
+        .published_date
+        .as_deref()
+        .and_then(parse_published_date);
+
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let end = start + rest[start..].find('}').unwrap_or(rest.len() - start);
+        let (name, format) = split_placeholder(&rest[start + 1..end]);
+
+        let value = match name {
+            "podcast" => episode.podcast_name.clone(),
+            "podcast_id" => episode.podcast_id.to_string(),
+            "title" => episode.title.clone(),
+            "episode_id" => episode.id.to_string(),
+            "basename" => basename.to_string(),
+            "ext" => extension.to_string(),
+            "date" => published
+                .map(|date| {
+                    date.format(format.unwrap_or(DEFAULT_DATE_FORMAT))
+                        .to_string()
+                })
+                .unwrap_or_else(|| UNDATED.to_string()),
+            _ => String::new(),
+        };
+        let value: String = value.chars().take(MAX_PLACEHOLDER_CHARS).collect();
+        // Values must not introduce directories of their own
+        rendered.push_str(&value.replace(['/', '\\'], "_"));
+
+        rest = &rest[(end + 1).min(rest.len())..];
+    }
+    rendered.push_str(rest);
+
+    let components: Vec<&str> = rendered.split(['/', '\\']).collect();
+    let last = components.len() - 1;
+    components
+        .iter()
+        .enumerate()
+        .filter(|(i, component)| *i == last || !component.trim().is_empty())
+        .map(|(i, component)| sanitize_path_component(component, i == last))
+        .collect()
+}
+
+/// Makes a single path component safe on all common filesystems
+pub fn sanitize_path_component(component: &str, is_file_name: bool) -> String {
+    let sanitized: String = component
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let sanitized = sanitized.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    // Leading dots would hide the file or escape the directory, trailing dots and
+    // spaces are stripped by Windows
+    let mut sanitized = sanitized
+        .trim_start_matches('.')
+        .trim_end_matches(['.', ' '])
+        .to_string();
+    if sanitized.is_empty() {
+        sanitized = "untitled".to_string();
+    }
+
+    // Device names Windows reserves regardless of extension
+    let stem = sanitized
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let reserved = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.as_bytes()[3].is_ascii_digit());
+    if reserved {
+        sanitized.insert(0, '_');
+    }
+
+    truncate_component(&sanitized, is_file_name)
+}
+
+fn truncate_component(component: &str, is_file_name: bool) -> String {
+    if component.len() <= MAX_COMPONENT_BYTES {
+        return component.to_string();
+    }
+
+    // Keep the extension of file names intact
+    let (stem, extension) = match component.rsplit_once('.') {
+        Some((stem, extension)) if is_file_name && extension.len() < 16 => {
+            (stem, format!(".{}", extension))
+        }
+        _ => (component, String::new()),
+    };
+    let mut end = MAX_COMPONENT_BYTES - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", stem[..end].trim_end(), extension)
+}
+
+/// Recursively lists audio files below `directory` with their sizes
+pub async fn list_audio_files(directory: &Path) -> Result<Vec<(PathBuf, u64)>, PodPicoError> {
+    let mut files = Vec::new();
+    let mut pending = vec![directory.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let mut entries = fs::read_dir(&current).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() && is_audio_file(&path) {
+                files.push((path, entry.metadata().await?.len()));
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Reads up to `len` bytes from the start of a file
+pub async fn read_file_header(path: &Path, len: usize) -> Result<Vec<u8>, PodPicoError> {
+    use tokio::io::AsyncReadExt;
+
+    let file = fs::File::open(path).await?;
+    let mut header = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut header).await?;
+    Ok(header)
+}
+
+/// SHA-256 of a file's contents as lowercase hex
+pub async fn file_sha256(path: &Path) -> Result<String, PodPicoError> {
+    use sha2::{Digest, Sha256};
+
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await
+    .map_err(|e| PodPicoError::Generic(format!("Hashing task failed: {}", e)))?
+}
+
+/// How `move_file` placed a file at its destination
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileMove {
+    /// Renamed in place; the source no longer exists
+    Renamed,
+    /// Copied across filesystems; the source is left for the caller to remove
+    Copied,
+}
+
+/// Moves a file, falling back to copying when source and destination are on
+/// different filesystems. A failed copy leaves no partial destination behind.
+pub async fn move_file(from: &Path, to: &Path) -> Result<FileMove, PodPicoError> {
+    match fs::rename(from, to).await {
+        Ok(()) => return Ok(FileMove::Renamed),
+        Err(e) if !is_cross_device_error(&e) => {
+            return Err(PodPicoError::IoError(format!(
+                "Failed to move {} to {}: {}",
+                from.display(),
+                to.display(),
+                e
+            )))
+        }
+        Err(_) => {}
+    }
+
+    let copied = async {
+        let expected = fs::metadata(from).await?.len();
+        let copied = fs::copy(from, to).await?;
+        if copied != expected {
+            return Err(std::io::Error::other(format!(
+                "copied {} of {} bytes",
+                copied, expected
+            )));
+        }
+        fs::File::open(to).await?.sync_all().await
+    }
+    .await;
+
+    match copied {
+        Ok(()) => Ok(FileMove::Copied),
+        Err(e) => {
+            let _ = fs::remove_file(to).await;
+            Err(PodPicoError::IoError(format!(
+                "Failed to copy {} to {}: {}",
+                from.display(),
+                to.display(),
+                e
+            )))
+        }
+    }
+}
+
+fn is_cross_device_error(error: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    if error.raw_os_error() == Some(libc::EXDEV) {
+        return true;
+    }
+    error.kind() == std::io::ErrorKind::CrossesDevices
+}
+
+/// First of `path`, `path (2)`, `path (3)`, ... that doesn't exist yet
+pub fn unused_path(path: &Path) -> Result<PathBuf, PodPicoError> {
+    if !path.exists() {
+        return Ok(path.to_path_buf());
+    }
+    (2..=MAX_COLLISION_SUFFIX)
+        .map(|n| with_collision_suffix(path, n))
+        .find(|candidate| !candidate.exists())
+        .ok_or_else(|| PodPicoError::IoError(format!("No free file name for {}", path.display())))
+}
+
+fn with_collision_suffix(path: &Path, n: u32) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{} ({}).{}", stem, n, extension.to_string_lossy()),
+        None => format!("{} ({})", stem, n),
+    };
+    path.with_file_name(file_name)
+}
+
+fn template_placeholders(template: &str) -> Result<Vec<&str>, PodPicoError> {
+    let mut placeholders = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(PodPicoError::InvalidFilenameTemplate(
+                "unmatched '}'".to_string(),
+            ));
+        }
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| PodPicoError::InvalidFilenameTemplate("unclosed '{'".to_string()))?;
+        placeholders.push(&rest[start + 1..end]);
+        rest = &rest[end + 1..];
+    }
+    Ok(placeholders)
+}
+
+fn split_placeholder(placeholder: &str) -> (&str, Option<&str>) {
+    match placeholder.split_once(':') {
+        Some((name, format)) => (name, Some(format)),
+        None => (placeholder, None),
+    }
+}
+
+fn is_valid_date_format(format: &str) -> bool {
+    !format.is_empty() && StrftimeItems::new(format).all(|item| !matches!(item, Item::Error))
+}
+
+/// Returns the free space available to unprivileged users on the volume holding `path`.
+/// Walks up to the nearest existing ancestor so not-yet-created directories can be checked.
+fn available_disk_space(path: &Path) -> std::io::Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| !ancestor.as_os_str().is_empty() && ancestor.exists())
+        .unwrap_or_else(|| Path::new("."));
+    query_available_space(existing)
+}
+
+#[cfg(unix)]
+fn query_available_space(path: &Path) -> std::io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: c_path is a valid NUL-terminated string and stats points to writable memory
+    // large enough for a statvfs struct; it is only read after statvfs reports success.
+    let result = unsafe { libc::statvfs(c_path.as_ptr(), stats.as_mut_ptr()) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let stats = unsafe { stats.assume_init() };
+
+    #[allow(clippy::unnecessary_cast)] // field widths differ between platforms
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn query_available_space(path: &Path) -> std::io::Result<u64> {
+    // Pick the disk with the longest mount point that contains the path
+    let path = std::fs::canonicalize(path)?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No disk found for {}", path.display()),
+            )
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use httpmock::prelude::*;
     use tempfile::tempdir;
 
+    fn test_episode(id: i64, podcast_id: i64, url: &str) -> Episode {
+        Episode {
+            id,
+            podcast_id,
+            podcast_name: "Test Podcast".to_string(),
+            title: format!("Episode {}", id),
+            description: None,
+            episode_url: url.to_string(),
+            published_date: Some("Mon, 02 Jan 2023 10:00:00 +0000".to_string()),
+            duration: None,
+            file_size: None,
+            local_file_path: None,
+            status: "new".to_string(),
+            downloaded: false,
+            on_device: false,
+            starred: false,
+            listened_at: None,
+            enclosure_type: None,
+            artwork_url: None,
+            artwork_path: None,
+            artwork_thumbnail_path: None,
+            audio_duration: None,
+            audio_bitrate: None,
+            audio_sample_rate: None,
+            loudness_lufs: None,
+            true_peak_dbtp: None,
+            device_duration: None,
+            trimmed_silence: None,
+            chapters_url: None,
+            device_ids: Vec::new(),
+        }
+    }
+
     async fn create_test_file_manager() -> FileManager {
         let temp_dir = tempdir().unwrap();
         let manager = FileManager::new(temp_dir.path().to_str().unwrap());
@@ -479,7 +1290,7 @@ mod tests {
 
         // Test acceptance criteria: Progress indicator appears immediately
         let result = file_manager
-            .download_episode(&url, episode_id, podcast_id)
+            .download_episode(&test_episode(episode_id, podcast_id, &url))
             .await;
 
         assert!(result.is_ok(), "Download should succeed");
@@ -549,34 +1360,87 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_download_respects_per_download_rate_limit() {
+        let temp_dir = tempdir().unwrap();
+        let file_manager =
+            FileManager::new(temp_dir.path().to_str().unwrap()).with_rate_limits(None, Some(10));
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/slow.mp3");
+            then.status(200).body(vec![0u8; 20 * 1024]);
+        });
+
+        let start = std::time::Instant::now();
+        let result = file_manager
+            .download_episode(&test_episode(20, 1, &server.url("/slow.mp3")))
+            .await;
+
+        assert!(result.is_ok());
+        // One second of burst, then the remaining 10 KB at 10 KB/s
+        assert!(start.elapsed() >= std::time::Duration::from_millis(900));
+        assert_eq!(std::fs::metadata(result.unwrap()).unwrap().len(), 20 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_download_window_delay_and_queued_downloads() {
+        let file_manager = create_test_file_manager().await;
+        assert!(
+            file_manager.download_window_delay().is_none(),
+            "No windows configured means downloads may start right away"
+        );
+
+        // A one-minute window an hour from now is closed
+        let start = chrono::Local::now().time() + chrono::Duration::hours(1);
+        let end = start + chrono::Duration::minutes(1);
+        let file_manager = file_manager.with_download_windows(vec![DownloadWindow {
+            start: start.format("%H:%M").to_string(),
+            end: end.format("%H:%M").to_string(),
+        }]);
+        let delay = file_manager.download_window_delay().unwrap();
+        assert!(delay <= WINDOW_RECHECK_INTERVAL);
+
+        // Downloads waiting for the window don't block a relocation
+        file_manager.mark_queued(21).await;
+        assert_eq!(
+            file_manager.get_download_progress(21).await.unwrap().status,
+            DownloadStatus::Queued
+        );
+        assert!(!file_manager.has_active_downloads().await);
+    }
+
+    #[tokio::test]
+    async fn test_relocated_manager_keeps_download_progress() {
+        let file_manager = create_test_file_manager().await;
+        file_manager.mark_queued(8).await;
+
+        let relocated = file_manager.with_download_directory("./relocated_episodes");
+        assert_eq!(
+            relocated.download_directory(),
+            Path::new("./relocated_episodes")
+        );
+        assert_eq!(
+            relocated.get_download_progress(8).await.unwrap().status,
+            DownloadStatus::Queued
+        );
+    }
+
     #[tokio::test]
     async fn test_user_story_3_download_already_exists() {
         // Test that downloading an already downloaded episode returns existing file
         let file_manager = create_test_file_manager().await;
         let server = MockServer::start();
-        let episode_id = 3;
-        let podcast_id = 1;
 
-        // Create podcast directory
-        let podcast_dir = file_manager.download_directory.join(podcast_id.to_string());
-        tokio::fs::create_dir_all(&podcast_dir).await.unwrap();
-
-        // Test filename extraction first
-        let test_url = &server.url("/episode.mp3");
-        let expected_filename = file_manager.extract_filename_from_url(test_url, episode_id);
-
-        // Create file with the exact name that would be extracted
-        let existing_file = podcast_dir.join(&expected_filename);
+        let mut episode = test_episode(3, 1, &server.url("/episode.mp3"));
+        let existing_file = file_manager.episode_file_path(&episode, "mp3");
+        tokio::fs::create_dir_all(existing_file.parent().unwrap())
+            .await
+            .unwrap();
         tokio::fs::write(&existing_file, b"existing content")
             .await
             .unwrap();
-
-        // Verify file exists before test
-        assert!(
-            existing_file.exists(),
-            "Test setup: existing file should exist at {:?}",
-            existing_file
-        );
+        episode.downloaded = true;
+        episode.local_file_path = Some(existing_file.to_string_lossy().to_string());
 
         // Create a mock that shouldn't be called since file exists
         let mock = server.mock(|when, then| {
@@ -584,38 +1448,276 @@ mod tests {
             then.status(404); // This should not be called
         });
 
-        // Test with the server URL - since file exists, no network call should be made
-        let result = file_manager
-            .download_episode(test_url, episode_id, podcast_id)
-            .await;
-        assert!(
-            result.is_ok(),
-            "Should return existing file path: {:?}",
-            result
+        let result = file_manager.download_episode(&episode).await;
+        assert_eq!(
+            result.unwrap(),
+            existing_file.to_string_lossy(),
+            "Should return existing file path"
         );
 
-        // Verify the file still exists and has the same content
-        assert!(existing_file.exists(), "Existing file should still exist");
+        // Verify the file still exists and has the same content, with no duplicate next to it
         let content = tokio::fs::read(&existing_file).await.unwrap();
         assert_eq!(
             content, b"existing content",
             "File content should be unchanged"
         );
+        let files = std::fs::read_dir(existing_file.parent().unwrap())
+            .unwrap()
+            .count();
+        assert_eq!(files, 1, "No duplicate file should be written");
 
-        let progress = file_manager.get_download_progress(episode_id).await;
-        if let Some(progress) = progress {
-            assert_eq!(
-                progress.status,
-                DownloadStatus::Completed,
-                "Should be marked as completed"
-            );
-            assert_eq!(progress.percentage, 100.0, "Should be 100% complete");
-        }
-
-        // The mock should NOT have been called since file already exists
+        let progress = file_manager.get_download_progress(3).await.unwrap();
+        assert_eq!(
+            progress.status,
+            DownloadStatus::Completed,
+            "Should be marked as completed"
+        );
         mock.assert_hits(0);
     }
 
+    #[tokio::test]
+    async fn test_download_does_not_overwrite_existing_file() {
+        // A file already at the templated path is kept and the download gets a " (2)" suffix
+        let file_manager = create_test_file_manager().await;
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/audio.mp3");
+            then.status(200).body("new content");
+        });
+
+        let episode = test_episode(3, 1, &server.url("/audio.mp3"));
+        let existing_file = file_manager.episode_file_path(&episode, "mp3");
+        tokio::fs::create_dir_all(existing_file.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(&existing_file, b"existing content")
+            .await
+            .unwrap();
+
+        let result = file_manager.download_episode(&episode).await.unwrap();
+
+        assert!(result.ends_with("2023-01-02 - Episode 3 (2).mp3"));
+        let content = tokio::fs::read(&existing_file).await.unwrap();
+        assert_eq!(
+            content, b"existing content",
+            "Existing file content should be unchanged"
+        );
+        assert_eq!(tokio::fs::read(&result).await.unwrap(), b"new content");
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_download_extension_follows_content_type() {
+        let file_manager = create_test_file_manager().await;
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/media/12345");
+            then.status(200)
+                .header("Content-Type", "audio/ogg")
+                .body("not a recognisable header");
+        });
+
+        let episode = test_episode(30, 1, &server.url("/media/12345"));
+        let result = file_manager.download_episode(&episode).await.unwrap();
+
+        assert!(result.ends_with("2023-01-02 - Episode 30.ogg"));
+        assert!(!file_manager
+            .episode_file_path(&episode, PARTIAL_DOWNLOAD_EXTENSION)
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_extension_follows_file_contents() {
+        // The bytes win over a URL and Content-Type claiming MP3
+        let file_manager = create_test_file_manager().await;
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/episode.mp3");
+            then.status(200)
+                .header("Content-Type", "audio/mpeg")
+                .body(b"fLaC\x00\x00\x00\x22 flac stream".as_slice());
+        });
+
+        let mut episode = test_episode(31, 1, &server.url("/episode.mp3"));
+        episode.enclosure_type = Some("audio/mpeg".to_string());
+        let result = file_manager.download_episode(&episode).await.unwrap();
+
+        assert!(result.ends_with(".flac"));
+    }
+
+    #[tokio::test]
+    async fn test_privacy_mode_downloads_from_underlying_host() {
+        let server = MockServer::start();
+        let direct = server.mock(|when, then| {
+            when.method(GET).path("/audio.mp3");
+            then.status(200).body("direct");
+        });
+        let tracker = server.mock(|when, then| {
+            when.method(GET).path_contains("/redirect/");
+            then.status(200).body("tracked");
+        });
+
+        let host = server.address().to_string();
+        let file_manager = create_test_file_manager()
+            .await
+            .with_tracking_prefixes(Some(vec![format!("{}/redirect/", host)]));
+        let episode = test_episode(40, 1, &server.url(format!("/redirect/{}/audio.mp3", host)));
+
+        let result = file_manager.download_episode(&episode).await.unwrap();
+
+        assert_eq!(tokio::fs::read(&result).await.unwrap(), b"direct");
+        direct.assert();
+        tracker.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn test_privacy_mode_falls_back_to_original_url() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/audio.mp3");
+            then.status(404);
+        });
+        let tracker = server.mock(|when, then| {
+            when.method(GET).path_contains("/redirect/");
+            then.status(200).body("tracked");
+        });
+
+        let host = server.address().to_string();
+        let file_manager = create_test_file_manager()
+            .await
+            .with_tracking_prefixes(Some(vec![format!("{}/redirect/", host)]));
+        let episode = test_episode(41, 1, &server.url(format!("/redirect/{}/audio.mp3", host)));
+
+        let result = file_manager.download_episode(&episode).await.unwrap();
+
+        assert_eq!(tokio::fs::read(&result).await.unwrap(), b"tracked");
+        tracker.assert();
+    }
+
+    #[tokio::test]
+    async fn test_episodes_sharing_url_basename_do_not_collide() {
+        let file_manager = create_test_file_manager().await;
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path_contains("audio.mp3");
+            then.status(200).body("audio");
+        });
+
+        let mut first = test_episode(10, 1, &server.url("/a/audio.mp3"));
+        first.title = "Same Title".to_string();
+        let mut second = test_episode(11, 1, &server.url("/b/audio.mp3"));
+        second.title = "Same Title".to_string();
+
+        let first_path = file_manager.download_episode(&first).await.unwrap();
+        let second_path = file_manager.download_episode(&second).await.unwrap();
+
+        assert_ne!(first_path, second_path);
+        assert!(Path::new(&first_path).exists());
+        assert!(Path::new(&second_path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_releases_claimed_file() {
+        let file_manager = create_test_file_manager().await;
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/missing.mp3");
+            then.status(404);
+        });
+
+        let episode = test_episode(12, 1, &server.url("/missing.mp3"));
+        assert!(file_manager.download_episode(&episode).await.is_err());
+        assert!(!file_manager.episode_file_path(&episode, "mp3").exists());
+    }
+
+    #[test]
+    fn test_render_default_filename_template() {
+        let mut episode = test_episode(7, 3, "https://example.com/audio.m4a");
+        episode.podcast_name = "My: Podcast".to_string();
+        episode.title = "Part 1/2 - \"Intro\"?".to_string();
+
+        let path = render_filename_template(DEFAULT_FILENAME_TEMPLATE, &episode, "audio", "m4a");
+
+        assert_eq!(
+            path,
+            PathBuf::from("My_ Podcast").join("2023-01-02 - Part 1_2 - _Intro__.m4a")
+        );
+    }
+
+    #[test]
+    fn test_render_filename_template_placeholders() {
+        let mut episode = test_episode(7, 3, "https://example.com/audio.mp3");
+        let template = "{podcast_id}/{date:%Y}/{episode_id}-{basename}.{ext}";
+
+        let path = render_filename_template(template, &episode, "audio", "mp3");
+        assert_eq!(path, PathBuf::from("3").join("2023").join("7-audio.mp3"));
+
+        episode.published_date = None;
+        let path = render_filename_template(template, &episode, "audio", "mp3");
+        assert_eq!(path, PathBuf::from("3").join("undated").join("7-audio.mp3"));
+    }
+
+    #[test]
+    fn test_render_filename_template_cannot_escape_download_directory() {
+        let mut episode = test_episode(1, 1, "https://example.com/a.mp3");
+        episode.podcast_name = "..".to_string();
+        episode.title = "../../etc/passwd".to_string();
+
+        let path = render_filename_template("../{podcast}/{title}.{ext}", &episode, "a", "mp3");
+
+        assert!(path.is_relative());
+        assert!(path
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_))));
+    }
+
+    #[test]
+    fn test_validate_filename_template() {
+        assert!(validate_filename_template(DEFAULT_FILENAME_TEMPLATE).is_ok());
+        assert!(validate_filename_template("{title}.{ext}").is_ok());
+        assert!(validate_filename_template("").is_err());
+        assert!(validate_filename_template("/abs/{title}.{ext}").is_err());
+        assert!(validate_filename_template("{unknown}.{ext}").is_err());
+        assert!(validate_filename_template("{title.{ext}").is_err());
+        assert!(validate_filename_template("{title}}.{ext}").is_err());
+        assert!(validate_filename_template("{date:%Q}.{ext}").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_move_file_and_unused_path() {
+        let temp_dir = tempdir().unwrap();
+        let from = temp_dir.path().join("a.mp3");
+        let to = temp_dir.path().join("b.mp3");
+        std::fs::write(&from, b"audio").unwrap();
+
+        assert_eq!(unused_path(&to).unwrap(), to);
+        assert_eq!(move_file(&from, &to).await.unwrap(), FileMove::Renamed);
+        assert!(!from.exists());
+        assert_eq!(std::fs::read(&to).unwrap(), b"audio");
+
+        assert_eq!(unused_path(&to).unwrap(), temp_dir.path().join("b (2).mp3"));
+        assert!(move_file(&from, &to).await.is_err());
+    }
+
+    #[test]
+    fn test_sanitize_path_component() {
+        assert_eq!(sanitize_path_component("a<b>c:d", false), "a_b_c_d");
+        assert_eq!(
+            sanitize_path_component("  spaced   out  ", false),
+            "spaced out"
+        );
+        assert_eq!(sanitize_path_component("...hidden.", true), "hidden");
+        assert_eq!(sanitize_path_component("", true), "untitled");
+        assert_eq!(sanitize_path_component("con.mp3", true), "_con.mp3");
+        assert_eq!(sanitize_path_component("COM1", false), "_COM1");
+        assert_eq!(sanitize_path_component("Comedy", false), "Comedy");
+
+        let long_name = format!("{}.mp3", "é".repeat(300));
+        let truncated = sanitize_path_component(&long_name, true);
+        assert!(truncated.len() <= MAX_COMPONENT_BYTES);
+        assert!(truncated.ends_with(".mp3"));
+    }
+
     #[tokio::test]
     async fn test_user_story_3_download_network_error() {
         // User Story #3 Acceptance Criteria: Clear error message for failed downloads
@@ -632,7 +1734,7 @@ mod tests {
         let url = server.url("/error-episode.mp3");
 
         let result = file_manager
-            .download_episode(&url, episode_id, podcast_id)
+            .download_episode(&test_episode(episode_id, podcast_id, &url))
             .await;
         assert!(result.is_err(), "Download should fail for 404 error");
 
@@ -650,7 +1752,9 @@ mod tests {
         // Test handling of invalid URLs
         let file_manager = create_test_file_manager().await;
 
-        let result = file_manager.download_episode("invalid-url", 5, 1).await;
+        let result = file_manager
+            .download_episode(&test_episode(5, 1, "invalid-url"))
+            .await;
         assert!(result.is_err(), "Should fail for invalid URL");
 
         let progress = file_manager.get_download_progress(5).await;
@@ -761,11 +1865,86 @@ mod tests {
 
         // This should succeed for a valid temp directory
         let result = file_manager
-            .check_disk_space(&file_manager.download_directory)
+            .check_disk_space(&file_manager.download_directory, 0)
             .await;
         assert!(
             result.is_ok(),
             "Disk space check should succeed for valid directory"
         );
     }
+
+    #[tokio::test]
+    async fn test_disk_space_check_insufficient_space() {
+        let temp_dir = tempdir().unwrap();
+        let file_manager = FileManager::new(temp_dir.path().to_str().unwrap())
+            .with_disk_space_reserve(u64::MAX / 2);
+        file_manager.initialize().await.unwrap();
+
+        let result = file_manager
+            .check_disk_space(&file_manager.download_directory, 1024)
+            .await;
+        match result {
+            Err(PodPicoError::InsufficientDiskSpace { needed, available }) => {
+                assert_eq!(needed, u64::MAX / 2 + 1024);
+                assert!(available < needed);
+            }
+            other => panic!("Expected InsufficientDiskSpace, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disk_space_check_missing_directory_uses_parent_volume() {
+        let temp_dir = tempdir().unwrap();
+        let missing = temp_dir.path().join("not").join("created");
+
+        let available = available_disk_space(&missing).unwrap();
+        assert!(available > 0, "Parent volume should report free space");
+    }
+
+    #[tokio::test]
+    async fn test_download_rejected_when_reserve_not_met() {
+        let temp_dir = tempdir().unwrap();
+        let file_manager = FileManager::new(temp_dir.path().to_str().unwrap())
+            .with_disk_space_reserve(u64::MAX / 2);
+        file_manager.initialize().await.unwrap();
+        let server = MockServer::start();
+
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/big-episode.mp3");
+            then.status(200)
+                .header("content-type", "audio/mpeg")
+                .body(b"audio");
+        });
+
+        let result = file_manager
+            .download_episode(&test_episode(8, 1, &server.url("/big-episode.mp3")))
+            .await;
+        assert!(matches!(
+            result,
+            Err(PodPicoError::InsufficientDiskSpace { .. })
+        ));
+        // Nothing is written, wherever the template would put it; not even a .part file
+        let mut written = Vec::new();
+        let mut directories = vec![file_manager.download_directory.clone()];
+        while let Some(directory) = directories.pop() {
+            for entry in std::fs::read_dir(directory).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    directories.push(path);
+                } else {
+                    written.push(path);
+                }
+            }
+        }
+        assert!(
+            written.is_empty(),
+            "No file should be written when space is insufficient: {:?}",
+            written
+        );
+
+        let progress = file_manager.get_download_progress(8).await.unwrap();
+        assert!(matches!(progress.status, DownloadStatus::Failed(_)));
+
+        mock.assert();
+    }
 }
@@ -5,23 +5,40 @@
 // User Story Foundation Setup
 
 // Module declarations
+pub mod artwork;
+pub mod audio_format;
+pub mod audio_probe;
+pub mod chapters;
 pub mod commands;
 pub mod config;
 pub mod database;
+pub mod device_profile;
+pub mod device_sync;
 pub mod episode_manager;
 pub mod error;
+pub mod events;
 pub mod file_manager;
+pub mod loudness;
+pub mod mp4;
 pub mod rss_manager;
+pub mod tagging;
+pub mod throttle;
+pub mod tracking;
+pub mod transcode;
 pub mod usb_manager;
 
 // Re-exports
 pub use commands::*;
 pub use error::PodPicoError;
 
+use artwork::ArtworkCache;
+use config::ConfigManager;
 use database::DatabaseManager;
 use file_manager::FileManager;
 use rss_manager::RssManager;
 use std::fs;
+use tauri::AppHandle;
+use transcode::Transcoder;
 use usb_manager::UsbManager;
 
 // Tauri application entry point
@@ -32,10 +49,11 @@ pub fn run() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .setup(|_app| {
+        .setup(|app| {
             // Initialize database and managers
+            let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = initialize_app().await {
+                if let Err(e) = initialize_app(app_handle).await {
                     log::error!("Failed to initialize application: {}", e);
                     std::process::exit(1);
                 }
@@ -51,15 +69,39 @@ pub fn run() {
             commands::get_episodes,
             commands::search_episodes,
             commands::update_episode_status,
+            commands::set_episode_starred,
+            commands::refresh_podcast,
+            commands::get_auto_download_rule,
+            commands::set_auto_download_rule,
             // Download management commands
             commands::download_episode,
             commands::get_download_progress,
+            commands::get_download_history,
+            commands::write_episode_tags,
+            commands::analyze_episode_loudness,
+            commands::set_podcast_playback_speed,
+            commands::probe_downloaded_episodes,
+            commands::get_listening_stats,
             // Episode file management commands
             commands::delete_downloaded_episode,
+            commands::cleanup_episodes,
+            commands::scan_library,
+            commands::match_untracked_file,
+            commands::delete_untracked_file,
+            commands::import_episodes,
             // USB device management commands
             commands::get_usb_devices,
+            commands::get_device_episodes,
             commands::transfer_episode_to_device,
             commands::remove_episode_from_device,
+            commands::get_device_profile,
+            commands::set_device_profile,
+            commands::get_sync_rules,
+            commands::set_sync_rules,
+            commands::plan_device_sync,
+            commands::sync_device,
+            commands::get_transcode_profile,
+            commands::set_transcode_profile,
             // User Story #11: Episode device status management commands
             commands::sync_episode_device_status,
             commands::get_device_episodes_by_podcast,
@@ -68,6 +110,7 @@ pub fn run() {
             // Configuration commands
             commands::get_app_config,
             commands::update_app_config,
+            commands::relocate_library,
             // Development/demo command
             commands::greet
         ])
@@ -75,7 +118,9 @@ pub fn run() {
         .expect("error while running tauri application");
 }
 
-async fn initialize_app() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+async fn initialize_app(
+    app_handle: AppHandle,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     log::info!("Initializing PodPico application...");
 
     // Create data directory OUTSIDE src-tauri to avoid file watcher conflicts
@@ -83,11 +128,6 @@ async fn initialize_app() -> Result<(), Box<dyn std::error::Error + Send + Sync>
     fs::create_dir_all(&data_dir)?;
     log::info!("Created data directory: {}", data_dir.display());
 
-    // Create downloads directory for episodes OUTSIDE src-tauri to avoid file watcher conflicts
-    let downloads_dir = std::path::PathBuf::from("../episodes");
-    fs::create_dir_all(&downloads_dir)?;
-    log::info!("Created downloads directory: {}", downloads_dir.display());
-
     // Initialize database OUTSIDE src-tauri to avoid file watcher conflicts
     let db_path = data_dir.join("podcasts.db");
 
@@ -106,12 +146,32 @@ async fn initialize_app() -> Result<(), Box<dyn std::error::Error + Send + Sync>
     log::info!("Creating database schema for podcast management...");
     db.initialize().await?;
 
+    // Load application configuration
+    let config_manager = ConfigManager::new(&data_dir.join("config.json").to_string_lossy());
+    config_manager.initialize().await?;
+    let app_config = config_manager.load_config().await?;
+
+    // Create downloads directory for episodes OUTSIDE src-tauri to avoid file watcher conflicts
+    // (the default "../episodes"; it can be moved with relocate_library)
+    let downloads_dir = std::path::PathBuf::from(&app_config.download_directory);
+    fs::create_dir_all(&downloads_dir)?;
+    log::info!("Created downloads directory: {}", downloads_dir.display());
+
     // Initialize RSS manager
     let rss_manager = RssManager::new();
 
     // Initialize file manager (User Story #3, #9)
     let downloads_dir_str = downloads_dir.to_string_lossy().to_string();
-    let file_manager = FileManager::new(&downloads_dir_str);
+    let file_manager = FileManager::new(&downloads_dir_str)
+        .with_disk_space_reserve(app_config.disk_space_reserve_bytes())
+        .with_filename_template(&app_config.filename_template)
+        .with_rate_limits(
+            app_config.max_download_rate_kbps,
+            app_config.max_episode_download_rate_kbps,
+        )
+        .with_download_windows(app_config.download_windows.clone())
+        .with_tracking_prefixes(app_config.tracking_prefixes())
+        .with_app_handle(app_handle.clone());
     file_manager.initialize().await?;
     log::info!(
         "File manager initialized with downloads directory: {}",
@@ -119,11 +179,27 @@ async fn initialize_app() -> Result<(), Box<dyn std::error::Error + Send + Sync>
     );
 
     // Initialize USB manager (User Stories #8, #9, #10, #11)
-    let usb_manager = UsbManager::new();
+    let usb_manager = UsbManager::new().with_app_handle(app_handle);
     log::info!("USB manager initialized for device operations");
 
     // Initialize managers globally
     commands::initialize_managers(db, rss_manager, file_manager, usb_manager).await;
+    commands::initialize_config_manager(config_manager).await;
+
+    // Local artwork cache so covers can be shown without network access
+    let artwork_cache = ArtworkCache::new(&data_dir.join("artwork").to_string_lossy());
+    artwork_cache.initialize().await?;
+    commands::initialize_artwork_cache(artwork_cache).await;
+
+    // Converted copies of episodes for devices with a transcode profile
+    let transcoder = Transcoder::new(&data_dir.join("transcoded").to_string_lossy())
+        .with_encoder_path(app_config.transcoder_path.clone())
+        .with_cache_limit_mb(app_config.transcode_cache_limit_mb);
+    transcoder.initialize().await?;
+    commands::initialize_transcoder(transcoder).await;
+
+    // Apply the retention policy periodically in the background
+    commands::start_retention_schedule();
 
     log::info!("All managers initialized successfully");
     Ok(())
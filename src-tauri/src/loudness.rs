@@ -0,0 +1,260 @@
+// Loudness module for PodPico
+// Measures EBU R128 loudness with the external encoder and writes ReplayGain tags
+
+use crate::error::PodPicoError;
+use crate::mp4::{self, MetadataItem};
+use id3::frame::ExtendedText;
+use id3::{Tag, TagLike, Version};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// What is done with loudness measurements: nothing, ReplayGain tags on the
+/// downloaded file, or a gain-adjusted copy on transfer
+pub const LOUDNESS_MODES: &[&str] = &["off", "replaygain", "adjusted_copy"];
+
+/// Common loudness target for spoken-word podcasts
+pub const DEFAULT_LOUDNESS_TARGET_LUFS: f64 = -16.0;
+
+/// ReplayGain 2.0 reference level
+const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+
+/// Gain is limited so adjusted peaks stay below this level
+const MAX_TRUE_PEAK_DBTP: f64 = -1.0;
+
+/// Level reported for silence, where the true peak is minus infinity
+const SILENCE_FLOOR_DB: f64 = -70.0;
+
+/// EBU R128 measurement of one file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessMeasurement {
+    pub integrated_lufs: f64,
+    pub true_peak_dbtp: f64,
+}
+
+impl LoudnessMeasurement {
+    /// Gain in dB that brings the file to `target_lufs`, reduced where needed so
+    /// that peaks don't exceed -1 dBTP
+    pub fn gain_to_target(&self, target_lufs: f64) -> f64 {
+        let gain = target_lufs - self.integrated_lufs;
+        gain.min(MAX_TRUE_PEAK_DBTP - self.true_peak_dbtp)
+    }
+
+    pub fn replaygain_track_gain(&self) -> f64 {
+        REPLAYGAIN_REFERENCE_LUFS - self.integrated_lufs
+    }
+
+    /// True peak as a linear sample value, 1.0 being full scale
+    pub fn replaygain_track_peak(&self) -> f64 {
+        10f64.powf(self.true_peak_dbtp / 20.0)
+    }
+}
+
+pub fn validate_loudness_settings(mode: &str, target_lufs: f64) -> Result<(), PodPicoError> {
+    if !LOUDNESS_MODES.contains(&mode) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Loudness mode must be one of {}, got '{}'",
+            LOUDNESS_MODES.join(", "),
+            mode
+        )));
+    }
+    if !(-31.0..=-5.0).contains(&target_lufs) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Loudness target must be between -31 and -5 LUFS, got {}",
+            target_lufs
+        )));
+    }
+    Ok(())
+}
+
+/// Measures integrated loudness and true peak of `source` with ffmpeg's
+/// `ebur128` filter
+pub async fn measure_loudness(
+    encoder: &Path,
+    source: &Path,
+) -> Result<LoudnessMeasurement, PodPicoError> {
+    log::info!("Measuring loudness of {}", source.display());
+
+    let output = tokio::process::Command::new(encoder)
+        .args(["-nostdin", "-hide_banner", "-nostats", "-i"])
+        .arg(source)
+        .args(["-map", "0:a:0", "-af", "ebur128=peak=true:framelog=verbose"])
+        .args(["-f", "null", "-"])
+        .output()
+        .await
+        .map_err(|e| PodPicoError::TranscodeFailed(format!("Failed to run encoder: {}", e)))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(PodPicoError::TranscodeFailed(format!(
+            "Loudness analysis exited with {}: {}",
+            output.status,
+            stderr.lines().last().unwrap_or_default()
+        )));
+    }
+    parse_ebur128_summary(&stderr).ok_or_else(|| {
+        PodPicoError::TranscodeFailed("Loudness analysis printed no summary".to_string())
+    })
+}
+
+/// Reads the integrated loudness ("I:") and true peak ("Peak:") from the
+/// summary the `ebur128` filter prints when it finishes
+fn parse_ebur128_summary(stderr: &str) -> Option<LoudnessMeasurement> {
+    let summary = &stderr[stderr.rfind("Summary:")?..];
+    let value_after = |label: &str| -> Option<f64> {
+        let line = summary
+            .lines()
+            .map(str::trim)
+            .find(|line| line.starts_with(label))?;
+        let value: f64 = line[label.len()..]
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()?;
+        Some(value.max(SILENCE_FLOOR_DB))
+    };
+
+    Some(LoudnessMeasurement {
+        integrated_lufs: value_after("I:")?,
+        true_peak_dbtp: value_after("Peak:")?,
+    })
+}
+
+/// Writes ReplayGain track gain and peak into an MP3 (ID3 `TXXX` frames) or
+/// M4A file (freeform iTunes items)
+pub fn write_replaygain_tags(
+    path: &Path,
+    measurement: &LoudnessMeasurement,
+) -> Result<(), PodPicoError> {
+    let gain = format!("{:+.2} dB", measurement.replaygain_track_gain());
+    let peak = format!("{:.6}", measurement.replaygain_track_peak());
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "mp3" => {
+            let mut tag = match Tag::read_from_path(path) {
+                Ok(tag) => tag,
+                Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => Tag::new(),
+                Err(e) => return Err(PodPicoError::UnsupportedFormat(e.to_string())),
+            };
+            let version = match tag.version() {
+                Version::Id3v22 => Version::Id3v23,
+                version => version,
+            };
+            for (description, value) in [
+                ("REPLAYGAIN_TRACK_GAIN", gain),
+                ("REPLAYGAIN_TRACK_PEAK", peak),
+            ] {
+                tag.remove_extended_text(Some(description), None);
+                tag.add_frame(ExtendedText {
+                    description: description.to_string(),
+                    value,
+                });
+            }
+            tag.write_to_path(path, version)
+                .map_err(|e| PodPicoError::IoError(format!("Failed to write ID3 tag: {}", e)))
+        }
+        "m4a" | "m4b" | "mp4" => mp4::write_metadata(
+            path,
+            &[
+                MetadataItem::freeform("replaygain_track_gain", &gain),
+                MetadataItem::freeform("replaygain_track_peak", &peak),
+            ],
+        ),
+        _ => Err(PodPicoError::UnsupportedFormat(format!(
+            "Writing ReplayGain tags to .{} files is not supported",
+            extension
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const EBUR128_OUTPUT: &str = "\
+[Parsed_ebur128_0 @ 0x5581] Summary:
+
+  Integrated loudness:
+    I:         -23.4 LUFS
+    Threshold: -33.9 LUFS
+
+  Loudness range:
+    LRA:         5.2 LU
+    Threshold: -43.8 LUFS
+
+  True peak:
+    Peak:       -4.5 dBFS
+";
+
+    #[test]
+    fn test_parse_ebur128_summary() {
+        let measurement = parse_ebur128_summary(EBUR128_OUTPUT).unwrap();
+        assert_eq!(measurement.integrated_lufs, -23.4);
+        assert_eq!(measurement.true_peak_dbtp, -4.5);
+
+        let silent = EBUR128_OUTPUT
+            .replace("-23.4 LUFS", "-70.0 LUFS")
+            .replace("-4.5 dBFS", "-inf dBFS");
+        assert_eq!(
+            parse_ebur128_summary(&silent).unwrap().true_peak_dbtp,
+            SILENCE_FLOOR_DB
+        );
+        assert!(parse_ebur128_summary("Conversion failed!").is_none());
+    }
+
+    #[test]
+    fn test_gain_is_limited_by_true_peak() {
+        let quiet = LoudnessMeasurement {
+            integrated_lufs: -23.4,
+            true_peak_dbtp: -4.5,
+        };
+        // 7.4 dB would bring it to -16 LUFS, but peaks may only rise by 3.5 dB
+        assert!((quiet.gain_to_target(-16.0) - 3.5).abs() < 1e-9);
+
+        let loud = LoudnessMeasurement {
+            integrated_lufs: -12.0,
+            true_peak_dbtp: -0.2,
+        };
+        assert!((loud.gain_to_target(-16.0) + 4.0).abs() < 1e-9);
+        assert!((loud.replaygain_track_gain() + 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_write_replaygain_tags_to_mp3() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.mp3");
+        std::fs::write(&path, [0xFF, 0xFB, 0x90, 0x64, 0, 0, 0, 0]).unwrap();
+        let measurement = LoudnessMeasurement {
+            integrated_lufs: -23.4,
+            true_peak_dbtp: -6.0206,
+        };
+
+        write_replaygain_tags(&path, &measurement).unwrap();
+        // Rewriting replaces the values instead of adding more frames
+        write_replaygain_tags(&path, &measurement).unwrap();
+
+        let tag = Tag::read_from_path(&path).unwrap();
+        let values: Vec<(&str, &str)> = tag
+            .extended_texts()
+            .map(|text| (text.description.as_str(), text.value.as_str()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("REPLAYGAIN_TRACK_GAIN", "+5.40 dB"),
+                ("REPLAYGAIN_TRACK_PEAK", "0.500000"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_loudness_settings() {
+        assert!(validate_loudness_settings("replaygain", -16.0).is_ok());
+        assert!(validate_loudness_settings("louder", -16.0).is_err());
+        assert!(validate_loudness_settings("adjusted_copy", 0.0).is_err());
+    }
+}
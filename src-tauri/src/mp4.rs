@@ -0,0 +1,735 @@
+// MP4 container module for PodPico
+// Walks ISO base media boxes and rewrites the iTunes metadata list (moov/udta/meta/ilst)
+
+use crate::audio_probe::AudioProperties;
+use crate::error::PodPicoError;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Boxes whose children may contain `stco`/`co64` chunk offset tables
+const CHUNK_OFFSET_CONTAINERS: &[&[u8; 4]] = &[b"trak", b"mdia", b"minf", b"stbl"];
+
+/// iTunes data box type indicators
+const DATA_TYPE_IMPLICIT: u32 = 0;
+const DATA_TYPE_UTF8: u32 = 1;
+const DATA_TYPE_JPEG: u32 = 13;
+const DATA_TYPE_PNG: u32 = 14;
+const DATA_TYPE_INTEGER: u32 = 21;
+
+/// `stik` media kind value marking a podcast episode
+const MEDIA_KIND_PODCAST: u8 = 21;
+
+/// Namespace of freeform (`----`) items written by PodPico
+const FREEFORM_MEAN: &str = "com.apple.iTunes";
+
+/// One iTunes metadata item, e.g. `©nam` with a UTF-8 value
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataItem {
+    pub key: [u8; 4],
+    /// Name of a freeform `----` item, e.g. "replaygain_track_gain"
+    pub name: Option<String>,
+    pub data_type: u32,
+    pub value: Vec<u8>,
+}
+
+impl MetadataItem {
+    pub fn text(key: &[u8; 4], value: &str) -> Self {
+        Self {
+            key: *key,
+            name: None,
+            data_type: DATA_TYPE_UTF8,
+            value: value.as_bytes().to_vec(),
+        }
+    }
+
+    /// Track number as stored in `trkn`; a total of 0 means unknown
+    pub fn track_number(track: u16, total: u16) -> Self {
+        let mut value = vec![0, 0];
+        value.extend_from_slice(&track.to_be_bytes());
+        value.extend_from_slice(&total.to_be_bytes());
+        value.extend_from_slice(&[0, 0]);
+        Self {
+            key: *b"trkn",
+            name: None,
+            data_type: DATA_TYPE_IMPLICIT,
+            value,
+        }
+    }
+
+    /// Cover art; `png` selects the PNG type indicator, JPEG otherwise
+    pub fn cover_art(image: Vec<u8>, png: bool) -> Self {
+        Self {
+            key: *b"covr",
+            name: None,
+            data_type: if png { DATA_TYPE_PNG } else { DATA_TYPE_JPEG },
+            value: image,
+        }
+    }
+
+    /// `pcst` and `stik` items that make players file the track as a podcast episode
+    pub fn podcast_flags() -> Vec<Self> {
+        vec![
+            Self {
+                key: *b"pcst",
+                name: None,
+                data_type: DATA_TYPE_INTEGER,
+                value: vec![1],
+            },
+            Self {
+                key: *b"stik",
+                name: None,
+                data_type: DATA_TYPE_INTEGER,
+                value: vec![MEDIA_KIND_PODCAST],
+            },
+        ]
+    }
+
+    /// Freeform text item in the iTunes namespace, as used for ReplayGain values
+    pub fn freeform(name: &str, value: &str) -> Self {
+        Self {
+            key: *b"----",
+            name: Some(name.to_string()),
+            data_type: DATA_TYPE_UTF8,
+            value: value.as_bytes().to_vec(),
+        }
+    }
+
+    /// Whether both items occupy the same slot in the item list
+    fn same_slot(&self, other: &Self) -> bool {
+        self.key == other.key && self.name == other.name
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(8 + self.value.len());
+        data.extend_from_slice(&self.data_type.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes()); // locale
+        data.extend_from_slice(&self.value);
+
+        let mut content = Vec::new();
+        if let Some(name) = &self.name {
+            content.extend(full_box_with(b"mean", FREEFORM_MEAN.as_bytes()));
+            content.extend(full_box_with(b"name", name.as_bytes()));
+        }
+        content.extend(encode_box(b"data", &data));
+        encode_box(&self.key, &content)
+    }
+}
+
+/// Header of a box found while walking a buffer or file
+#[derive(Debug, Clone, Copy)]
+struct BoxHeader {
+    kind: [u8; 4],
+    /// Offset of the box start from the start of the walked region
+    offset: u64,
+    header_len: u64,
+    size: u64,
+}
+
+impl BoxHeader {
+    fn content_range(&self) -> std::ops::Range<usize> {
+        (self.offset + self.header_len) as usize..(self.offset + self.size) as usize
+    }
+
+    fn range(&self) -> std::ops::Range<usize> {
+        self.offset as usize..(self.offset + self.size) as usize
+    }
+}
+
+/// Replaces the file's iTunes metadata items with `items`, keeping existing items
+/// with other keys. Audio data is left untouched; chunk offsets are adjusted when
+/// the metadata grows or shrinks in front of it.
+pub fn write_metadata(path: &Path, items: &[MetadataItem]) -> Result<(), PodPicoError> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let top_level = read_top_level_boxes(&mut file, file_len)?;
+
+    if top_level.iter().any(|header| &header.kind == b"moof") {
+        return Err(PodPicoError::UnsupportedFormat(
+            "Fragmented MP4 files are not supported".to_string(),
+        ));
+    }
+    let moov = *top_level
+        .iter()
+        .find(|header| &header.kind == b"moov")
+        .ok_or_else(|| PodPicoError::UnsupportedFormat("MP4 file has no moov box".to_string()))?;
+    let media_follows_moov = top_level
+        .iter()
+        .any(|header| &header.kind == b"mdat" && header.offset > moov.offset);
+
+    let mut old_moov = vec![0u8; moov.size as usize];
+    file.seek(SeekFrom::Start(moov.offset))?;
+    file.read_exact(&mut old_moov)?;
+
+    let mut new_moov = rebuild_moov(&old_moov, moov.header_len as usize, items)?;
+    let delta = new_moov.len() as i64 - old_moov.len() as i64;
+    if media_follows_moov && delta != 0 {
+        // Skip the moov header written by rebuild_moov
+        shift_chunk_offsets(&mut new_moov[8..], delta)?;
+    }
+
+    // Write the new file next to the original and swap it in
+    let partial = path.with_extension("tagging");
+    let result = (|| -> Result<(), PodPicoError> {
+        let mut output = File::create(&partial)?;
+        for header in &top_level {
+            if header.offset == moov.offset {
+                output.write_all(&new_moov)?;
+            } else {
+                file.seek(SeekFrom::Start(header.offset))?;
+                std::io::copy(&mut (&mut file).take(header.size), &mut output)?;
+            }
+        }
+        output.sync_all()?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Reads the metadata items currently stored in the file
+pub fn read_metadata(path: &Path) -> Result<Vec<MetadataItem>, PodPicoError> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let top_level = read_top_level_boxes(&mut file, file_len)?;
+    let Some(moov) = top_level.iter().find(|header| &header.kind == b"moov") else {
+        return Ok(Vec::new());
+    };
+
+    let mut buffer = vec![0u8; moov.size as usize];
+    file.seek(SeekFrom::Start(moov.offset))?;
+    file.read_exact(&mut buffer)?;
+
+    let moov_content = &buffer[moov.header_len as usize..];
+    let Some(udta) = find_child(moov_content, b"udta")? else {
+        return Ok(Vec::new());
+    };
+    let udta_content = &moov_content[udta.content_range()];
+    let Some(meta) = find_child(udta_content, b"meta")? else {
+        return Ok(Vec::new());
+    };
+    // meta is a full box: version and flags precede its children
+    let meta_children = udta_content
+        .get(meta.content_range().start + 4..meta.content_range().end)
+        .unwrap_or_default();
+    let Some(ilst) = find_child(meta_children, b"ilst")? else {
+        return Ok(Vec::new());
+    };
+
+    parse_items(&meta_children[ilst.content_range()])
+}
+
+/// Reads duration and sample rate of the first sound track from its media header
+/// and sample description; the bitrate is averaged over the media data
+pub fn probe_audio(path: &Path) -> Result<AudioProperties, PodPicoError> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let top_level = read_top_level_boxes(&mut file, file_len)?;
+    let moov = top_level
+        .iter()
+        .find(|header| &header.kind == b"moov")
+        .ok_or_else(malformed)?;
+    let media_bytes: u64 = top_level
+        .iter()
+        .filter(|header| &header.kind == b"mdat")
+        .map(|header| header.size - header.header_len)
+        .sum();
+
+    let mut buffer = vec![0u8; moov.size as usize];
+    file.seek(SeekFrom::Start(moov.offset))?;
+    file.read_exact(&mut buffer)?;
+    let moov_content = &buffer[moov.header_len as usize..];
+
+    for trak in parse_boxes(moov_content, 0)?
+        .into_iter()
+        .filter(|header| &header.kind == b"trak")
+    {
+        let trak_content = &moov_content[trak.content_range()];
+        let Some(mdia) = find_child(trak_content, b"mdia")? else {
+            continue;
+        };
+        let mdia_content = &trak_content[mdia.content_range()];
+        let is_sound = find_child(mdia_content, b"hdlr")?.is_some_and(|hdlr| {
+            // Version and flags, pre_defined, then the handler type
+            mdia_content.get(hdlr.content_range().start + 8..hdlr.content_range().start + 12)
+                == Some(b"soun".as_slice())
+        });
+        if !is_sound {
+            continue;
+        }
+
+        let mdhd = find_child(mdia_content, b"mdhd")?.ok_or_else(malformed)?;
+        let mdhd_content = &mdia_content[mdhd.content_range()];
+        let (timescale, duration) = match mdhd_content.first() {
+            Some(1) => (
+                read_u32(mdhd_content, 20)?,
+                read_u32(mdhd_content, 24)? as u64 * (1 << 32) + read_u32(mdhd_content, 28)? as u64,
+            ),
+            _ => (
+                read_u32(mdhd_content, 12)?,
+                read_u32(mdhd_content, 16)? as u64,
+            ),
+        };
+        if timescale == 0 {
+            return Err(malformed());
+        }
+
+        let sample_rate = sound_sample_rate(mdia_content)?.unwrap_or(timescale);
+        return Ok(AudioProperties::from_audio_bytes(
+            duration as f64 / timescale as f64,
+            media_bytes,
+            sample_rate,
+        ));
+    }
+
+    Err(PodPicoError::UnsupportedFormat(
+        "MP4 file has no audio track".to_string(),
+    ))
+}
+
+/// Sample rate from the first entry of minf/stbl/stsd
+fn sound_sample_rate(mdia: &[u8]) -> Result<Option<u32>, PodPicoError> {
+    let Some(minf) = find_child(mdia, b"minf")? else {
+        return Ok(None);
+    };
+    let minf_content = &mdia[minf.content_range()];
+    let Some(stbl) = find_child(minf_content, b"stbl")? else {
+        return Ok(None);
+    };
+    let stbl_content = &minf_content[stbl.content_range()];
+    let Some(stsd) = find_child(stbl_content, b"stsd")? else {
+        return Ok(None);
+    };
+
+    // Version, flags and entry count precede the first sample entry, whose
+    // 16.16 fixed-point sample rate follows 24 bytes of reserved and format fields
+    let stsd_content = &stbl_content[stsd.content_range()];
+    let rate = stsd_content
+        .get(8..)
+        .and_then(|entries| read_u32(entries, 8 + 24).ok())
+        .map(|rate| rate >> 16);
+    Ok(rate.filter(|&rate| rate > 0))
+}
+
+fn rebuild_moov(
+    moov: &[u8],
+    header_len: usize,
+    items: &[MetadataItem],
+) -> Result<Vec<u8>, PodPicoError> {
+    let content = &moov[header_len..];
+    let children = parse_boxes(content, 0)?;
+
+    let mut new_content = Vec::with_capacity(content.len() + 1024);
+    let mut udta_written = false;
+    for child in &children {
+        if &child.kind == b"udta" {
+            new_content.extend(encode_box(
+                b"udta",
+                &rebuild_udta(&content[child.content_range()], items)?,
+            ));
+            udta_written = true;
+        } else {
+            new_content.extend_from_slice(&content[child.range()]);
+        }
+    }
+    if !udta_written {
+        new_content.extend(encode_box(b"udta", &rebuild_udta(&[], items)?));
+    }
+
+    Ok(encode_box(b"moov", &new_content))
+}
+
+fn rebuild_udta(udta: &[u8], items: &[MetadataItem]) -> Result<Vec<u8>, PodPicoError> {
+    let mut new_content = Vec::with_capacity(udta.len() + 1024);
+    let mut existing_items = Vec::new();
+
+    for child in parse_boxes(udta, 0)? {
+        if &child.kind != b"meta" {
+            new_content.extend_from_slice(&udta[child.range()]);
+            continue;
+        }
+        let meta_children = udta
+            .get(child.content_range().start + 4..child.content_range().end)
+            .unwrap_or_default();
+        if let Some(ilst) = find_child(meta_children, b"ilst")? {
+            existing_items = parse_items(&meta_children[ilst.content_range()])?;
+        }
+    }
+
+    let mut merged: Vec<MetadataItem> = existing_items
+        .into_iter()
+        .filter(|existing| !items.iter().any(|item| item.same_slot(existing)))
+        .collect();
+    merged.extend_from_slice(items);
+
+    let ilst: Vec<u8> = merged.iter().flat_map(MetadataItem::encode).collect();
+    let mut meta = vec![0, 0, 0, 0]; // version and flags
+    meta.extend(metadata_handler());
+    meta.extend(encode_box(b"ilst", &ilst));
+    new_content.extend(encode_box(b"meta", &meta));
+
+    Ok(new_content)
+}
+
+/// `hdlr` box declaring iTunes-style metadata
+fn metadata_handler() -> Vec<u8> {
+    let mut content = vec![0, 0, 0, 0]; // version and flags
+    content.extend_from_slice(&0u32.to_be_bytes()); // pre-defined
+    content.extend_from_slice(b"mdir");
+    content.extend_from_slice(b"appl");
+    content.extend_from_slice(&[0; 8]);
+    content.push(0); // empty name
+    encode_box(b"hdlr", &content)
+}
+
+fn parse_items(ilst: &[u8]) -> Result<Vec<MetadataItem>, PodPicoError> {
+    let mut items = Vec::new();
+    for item in parse_boxes(ilst, 0)? {
+        let item_content = &ilst[item.content_range()];
+        let Some(data) = find_child(item_content, b"data")? else {
+            continue;
+        };
+        let data = &item_content[data.content_range()];
+        if data.len() < 8 {
+            continue;
+        }
+        let name = match &item.kind {
+            b"----" => find_child(item_content, b"name")?.map(|name| {
+                let name = item_content
+                    .get(name.content_range().start + 4..name.content_range().end)
+                    .unwrap_or_default();
+                String::from_utf8_lossy(name).to_string()
+            }),
+            _ => None,
+        };
+        items.push(MetadataItem {
+            key: item.kind,
+            name,
+            data_type: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            value: data[8..].to_vec(),
+        });
+    }
+    Ok(items)
+}
+
+/// Adds `delta` to every chunk offset in the `stco`/`co64` tables below `content`
+fn shift_chunk_offsets(content: &mut [u8], delta: i64) -> Result<(), PodPicoError> {
+    for child in parse_boxes(content, 0)? {
+        let range = child.content_range();
+        let kind = child.kind;
+        let body = &mut content[range];
+
+        if CHUNK_OFFSET_CONTAINERS.contains(&&kind) {
+            shift_chunk_offsets(body, delta)?;
+        } else if &kind == b"stco" || &kind == b"co64" {
+            let entry_len = if &kind == b"stco" { 4 } else { 8 };
+            let count = read_u32(body, 4)? as usize;
+            for index in 0..count {
+                let at = 8 + index * entry_len;
+                let entry = body.get_mut(at..at + entry_len).ok_or_else(malformed)?;
+                if entry_len == 4 {
+                    let offset = u32::from_be_bytes(entry.try_into().map_err(|_| malformed())?);
+                    let shifted = u32::try_from(offset as i64 + delta).map_err(|_| {
+                        PodPicoError::UnsupportedFormat(
+                            "Chunk offsets no longer fit the stco table".to_string(),
+                        )
+                    })?;
+                    entry.copy_from_slice(&shifted.to_be_bytes());
+                } else {
+                    let offset = u64::from_be_bytes(entry.try_into().map_err(|_| malformed())?);
+                    let shifted = (offset as i64 + delta) as u64;
+                    entry.copy_from_slice(&shifted.to_be_bytes());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_top_level_boxes(file: &mut File, file_len: u64) -> Result<Vec<BoxHeader>, PodPicoError> {
+    let mut headers = Vec::new();
+    let mut offset = 0;
+
+    while offset + 8 <= file_len {
+        let mut header = [0u8; 16];
+        file.seek(SeekFrom::Start(offset))?;
+        let available = (file_len - offset).min(16) as usize;
+        file.read_exact(&mut header[..available])?;
+
+        let parsed = parse_header(&header[..available], offset, file_len - offset)?;
+        headers.push(parsed);
+        offset += parsed.size;
+    }
+
+    if headers.is_empty() {
+        return Err(malformed());
+    }
+    Ok(headers)
+}
+
+/// Parses consecutive boxes filling `buffer`
+fn parse_boxes(buffer: &[u8], start: u64) -> Result<Vec<BoxHeader>, PodPicoError> {
+    let mut headers = Vec::new();
+    let mut offset = start;
+
+    while offset + 8 <= buffer.len() as u64 {
+        let remaining = buffer.len() as u64 - offset;
+        let header = parse_header(&buffer[offset as usize..], offset, remaining)?;
+        headers.push(header);
+        offset += header.size;
+    }
+    Ok(headers)
+}
+
+fn find_child(buffer: &[u8], kind: &[u8; 4]) -> Result<Option<BoxHeader>, PodPicoError> {
+    Ok(parse_boxes(buffer, 0)?
+        .into_iter()
+        .find(|header| &header.kind == kind))
+}
+
+fn parse_header(bytes: &[u8], offset: u64, remaining: u64) -> Result<BoxHeader, PodPicoError> {
+    let size32 = read_u32(bytes, 0)? as u64;
+    let kind: [u8; 4] = bytes
+        .get(4..8)
+        .and_then(|kind| kind.try_into().ok())
+        .ok_or_else(malformed)?;
+
+    let (header_len, size) = match size32 {
+        // Box extends to the end of the enclosing region
+        0 => (8, remaining),
+        1 => {
+            let large = bytes
+                .get(8..16)
+                .and_then(|size| size.try_into().ok())
+                .map(u64::from_be_bytes)
+                .ok_or_else(malformed)?;
+            (16, large)
+        }
+        size => (8, size),
+    };
+
+    if size < header_len || size > remaining {
+        return Err(malformed());
+    }
+    Ok(BoxHeader {
+        kind,
+        offset,
+        header_len,
+        size,
+    })
+}
+
+fn encode_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(8 + content.len());
+    encoded.extend_from_slice(&((8 + content.len()) as u32).to_be_bytes());
+    encoded.extend_from_slice(kind);
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+/// Box whose content starts with zero version and flags
+fn full_box_with(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut with_version = vec![0, 0, 0, 0];
+    with_version.extend_from_slice(content);
+    encode_box(kind, &with_version)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, PodPicoError> {
+    bytes
+        .get(at..at + 4)
+        .and_then(|value| value.try_into().ok())
+        .map(u32::from_be_bytes)
+        .ok_or_else(malformed)
+}
+
+fn malformed() -> PodPicoError {
+    PodPicoError::UnsupportedFormat("Malformed MP4 box structure".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const AUDIO: &[u8] = b"0123456789 audio samples";
+
+    fn stco(offset: u32) -> Vec<u8> {
+        let mut table = 1u32.to_be_bytes().to_vec();
+        table.extend_from_slice(&offset.to_be_bytes());
+        full_box_with(b"stco", &table)
+    }
+
+    fn moov(chunk_offset: u32) -> Vec<u8> {
+        let stbl = encode_box(b"stbl", &stco(chunk_offset));
+        let minf = encode_box(b"minf", &stbl);
+        let mdia = encode_box(b"mdia", &minf);
+        let mut content = full_box_with(b"mvhd", &[0; 96]);
+        content.extend(encode_box(b"trak", &mdia));
+        encode_box(b"moov", &content)
+    }
+
+    /// ftyp, moov, mdat: the layout of "fast start" files where the metadata
+    /// precedes the audio
+    fn fast_start_file() -> Vec<u8> {
+        let ftyp = encode_box(b"ftyp", b"M4A \x00\x00\x00\x00M4A isom");
+        let audio_offset = (ftyp.len() + moov(0).len() + 8) as u32;
+
+        let mut file = ftyp;
+        file.extend(moov(audio_offset));
+        file.extend(encode_box(b"mdat", AUDIO));
+        file
+    }
+
+    /// Follows the first chunk offset and returns the bytes it points at
+    fn first_chunk(path: &Path) -> Vec<u8> {
+        let bytes = std::fs::read(path).unwrap();
+        let stco_at = bytes.windows(4).position(|w| w == b"stco").unwrap();
+        let offset = u32::from_be_bytes(bytes[stco_at + 12..stco_at + 16].try_into().unwrap());
+        bytes[offset as usize..offset as usize + AUDIO.len()].to_vec()
+    }
+
+    #[test]
+    fn test_write_metadata_adds_items_and_keeps_audio_reachable() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.m4a");
+        std::fs::write(&path, fast_start_file()).unwrap();
+        assert_eq!(first_chunk(&path), AUDIO);
+
+        let mut items = vec![
+            MetadataItem::text(b"\xa9nam", "Episode 1"),
+            MetadataItem::text(b"\xa9alb", "My Podcast"),
+            MetadataItem::track_number(7, 0),
+            MetadataItem::cover_art(vec![0xFF, 0xD8, 0xFF], false),
+        ];
+        items.extend(MetadataItem::podcast_flags());
+        write_metadata(&path, &items).unwrap();
+
+        assert_eq!(read_metadata(&path).unwrap(), items);
+        assert_eq!(first_chunk(&path), AUDIO);
+        assert!(!path.with_extension("tagging").exists());
+    }
+
+    #[test]
+    fn test_rewriting_replaces_own_items_and_keeps_others() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.m4a");
+        std::fs::write(&path, fast_start_file()).unwrap();
+
+        write_metadata(
+            &path,
+            &[
+                MetadataItem::text(b"\xa9too", "Some Encoder"),
+                MetadataItem::text(b"\xa9nam", "track01"),
+            ],
+        )
+        .unwrap();
+        write_metadata(&path, &[MetadataItem::text(b"\xa9nam", "Real Title")]).unwrap();
+
+        assert_eq!(
+            read_metadata(&path).unwrap(),
+            vec![
+                MetadataItem::text(b"\xa9too", "Some Encoder"),
+                MetadataItem::text(b"\xa9nam", "Real Title"),
+            ]
+        );
+        assert_eq!(first_chunk(&path), AUDIO);
+    }
+
+    #[test]
+    fn test_freeform_items_are_kept_apart_by_name() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.m4a");
+        std::fs::write(&path, fast_start_file()).unwrap();
+
+        write_metadata(
+            &path,
+            &[
+                MetadataItem::freeform("replaygain_track_gain", "+1.00 dB"),
+                MetadataItem::freeform("replaygain_track_peak", "0.500000"),
+            ],
+        )
+        .unwrap();
+        write_metadata(
+            &path,
+            &[MetadataItem::freeform("replaygain_track_gain", "-2.50 dB")],
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_metadata(&path).unwrap(),
+            vec![
+                MetadataItem::freeform("replaygain_track_peak", "0.500000"),
+                MetadataItem::freeform("replaygain_track_gain", "-2.50 dB"),
+            ]
+        );
+        assert_eq!(first_chunk(&path), AUDIO);
+    }
+
+    #[test]
+    fn test_media_before_moov_needs_no_offset_changes() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.m4a");
+        let ftyp = encode_box(b"ftyp", b"M4A \x00\x00\x00\x00");
+        let audio_offset = (ftyp.len() + 8) as u32;
+        let mut bytes = ftyp;
+        bytes.extend(encode_box(b"mdat", AUDIO));
+        bytes.extend(moov(audio_offset));
+        std::fs::write(&path, bytes).unwrap();
+
+        write_metadata(&path, &[MetadataItem::text(b"\xa9nam", "Title")]).unwrap();
+
+        assert_eq!(first_chunk(&path), AUDIO);
+        assert_eq!(read_metadata(&path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_non_mp4_files_are_rejected() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.m4a");
+        std::fs::write(&path, b"ID3\x04\x00 definitely an mp3").unwrap();
+
+        assert!(matches!(
+            write_metadata(&path, &[MetadataItem::text(b"\xa9nam", "Title")]),
+            Err(PodPicoError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_probe_audio_reads_sound_track() {
+        let mut mdhd = vec![0u8; 20];
+        mdhd[8..12].copy_from_slice(&44_100u32.to_be_bytes()); // timescale
+        mdhd[12..16].copy_from_slice(&(44_100u32 * 60).to_be_bytes()); // duration
+        let mut hdlr = vec![0u8; 20];
+        hdlr[4..8].copy_from_slice(b"soun");
+        let mut entry = vec![0u8; 28];
+        entry[24..28].copy_from_slice(&(22_050u32 << 16).to_be_bytes());
+        let mut stsd = 1u32.to_be_bytes().to_vec();
+        stsd.extend(encode_box(b"mp4a", &entry));
+
+        let stbl = encode_box(b"stbl", &full_box_with(b"stsd", &stsd));
+        let mut mdia = full_box_with(b"mdhd", &mdhd);
+        mdia.extend(full_box_with(b"hdlr", &hdlr));
+        mdia.extend(encode_box(b"minf", &stbl));
+        let trak = encode_box(b"trak", &encode_box(b"mdia", &mdia));
+        let mut moov = full_box_with(b"mvhd", &[0; 96]);
+        moov.extend(trak);
+
+        let mut file = encode_box(b"ftyp", b"M4A \x00\x00\x00\x00M4A isom");
+        file.extend(encode_box(b"moov", &moov));
+        file.extend(encode_box(b"mdat", &vec![0u8; 480_000]));
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.m4a");
+        std::fs::write(&path, file).unwrap();
+
+        let properties = probe_audio(&path).unwrap();
+        assert_eq!(properties.duration_seconds, 60.0);
+        assert_eq!(properties.sample_rate, 22_050);
+        assert_eq!(properties.bitrate_bps, 64_000);
+    }
+}
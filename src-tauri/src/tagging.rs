@@ -0,0 +1,285 @@
+// Metadata tagging module for PodPico
+// Writes episode information from the database into ID3v2 (MP3) and MP4 (M4A) tags
+
+use crate::error::PodPicoError;
+use crate::mp4::{self, MetadataItem};
+use chrono::{Datelike, NaiveDate};
+use id3::frame::{Comment, Picture, PictureType};
+use id3::{Tag, TagLike, Timestamp, Version};
+use std::path::Path;
+
+pub const PODCAST_GENRE: &str = "Podcast";
+
+/// ID3 version written unless configured otherwise; v2.3 is what older players read
+pub const DEFAULT_ID3_VERSION: &str = "2.3";
+
+/// Tag values for one episode file
+#[derive(Debug, Clone, Default)]
+pub struct EpisodeTags {
+    pub title: String,
+    /// Used as album, artist and album artist
+    pub podcast: String,
+    pub published: Option<NaiveDate>,
+    /// Position of the episode within its podcast, oldest first
+    pub track: Option<u32>,
+    /// Episode description as plain text
+    pub comment: Option<String>,
+    /// JPEG or PNG image data
+    pub artwork: Option<Vec<u8>>,
+}
+
+/// Parses the configured ID3 version, "2.3" or "2.4"
+pub fn parse_id3_version(version: &str) -> Result<Version, PodPicoError> {
+    match version.trim() {
+        "2.3" => Ok(Version::Id3v23),
+        "2.4" => Ok(Version::Id3v24),
+        other => Err(PodPicoError::UnsupportedFormat(format!(
+            "ID3 version must be 2.3 or 2.4, got '{}'",
+            other
+        ))),
+    }
+}
+
+/// Writes `tags` into the audio file, choosing the tag format from its extension.
+/// Values not managed by PodPico are left in place.
+pub fn write_episode_tags(
+    path: &Path,
+    tags: &EpisodeTags,
+    id3_version: Version,
+) -> Result<(), PodPicoError> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "mp3" => write_id3_tags(path, tags, id3_version),
+        "m4a" | "m4b" | "mp4" => write_mp4_tags(path, tags),
+        _ => Err(PodPicoError::UnsupportedFormat(format!(
+            "Writing tags to .{} files is not supported",
+            extension
+        ))),
+    }
+}
+
+fn write_id3_tags(path: &Path, tags: &EpisodeTags, version: Version) -> Result<(), PodPicoError> {
+    let mut tag = match Tag::read_from_path(path) {
+        Ok(tag) => tag,
+        Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => Tag::new(),
+        Err(e) => return Err(PodPicoError::UnsupportedFormat(e.to_string())),
+    };
+
+    tag.set_title(&tags.title);
+    tag.set_album(&tags.podcast);
+    tag.set_artist(&tags.podcast);
+    tag.set_album_artist(&tags.podcast);
+    tag.set_genre(PODCAST_GENRE);
+
+    // v2.3 only knows the year (TYER); v2.4 stores the full date (TDRC)
+    tag.remove_year();
+    tag.remove_date_recorded();
+    if let Some(date) = tags.published {
+        match version {
+            Version::Id3v24 => tag.set_date_recorded(Timestamp {
+                year: date.year(),
+                month: Some(date.month() as u8),
+                day: Some(date.day() as u8),
+                hour: None,
+                minute: None,
+                second: None,
+            }),
+            _ => tag.set_year(date.year()),
+        }
+    }
+
+    match tags.track {
+        Some(track) => tag.set_track(track),
+        None => tag.remove_track(),
+    }
+
+    tag.remove_comment(Some(""), None);
+    if let Some(comment) = &tags.comment {
+        tag.add_frame(Comment {
+            lang: "eng".to_string(),
+            description: String::new(),
+            text: comment.clone(),
+        });
+    }
+
+    if let Some(artwork) = &tags.artwork {
+        tag.remove_picture_by_type(PictureType::CoverFront);
+        tag.add_frame(Picture {
+            mime_type: artwork_mime_type(artwork).to_string(),
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+            data: artwork.clone(),
+        });
+    }
+
+    tag.write_to_path(path, version)
+        .map_err(|e| PodPicoError::IoError(format!("Failed to write ID3 tag: {}", e)))
+}
+
+fn write_mp4_tags(path: &Path, tags: &EpisodeTags) -> Result<(), PodPicoError> {
+    let mut items = vec![
+        MetadataItem::text(b"\xa9nam", &tags.title),
+        MetadataItem::text(b"\xa9alb", &tags.podcast),
+        MetadataItem::text(b"\xa9ART", &tags.podcast),
+        MetadataItem::text(b"aART", &tags.podcast),
+        MetadataItem::text(b"\xa9gen", PODCAST_GENRE),
+    ];
+    if let Some(date) = tags.published {
+        items.push(MetadataItem::text(
+            b"\xa9day",
+            &date.format("%Y-%m-%d").to_string(),
+        ));
+    }
+    if let Some(track) = tags.track {
+        items.push(MetadataItem::track_number(
+            u16::try_from(track).unwrap_or(u16::MAX),
+            0,
+        ));
+    }
+    if let Some(comment) = &tags.comment {
+        items.push(MetadataItem::text(b"\xa9cmt", comment));
+        items.push(MetadataItem::text(b"desc", comment));
+    }
+    if let Some(artwork) = &tags.artwork {
+        items.push(MetadataItem::cover_art(
+            artwork.clone(),
+            artwork_mime_type(artwork) == "image/png",
+        ));
+    }
+    items.extend(MetadataItem::podcast_flags());
+
+    mp4::write_metadata(path, &items)
+}
+
+fn artwork_mime_type(image: &[u8]) -> &'static str {
+    if image.starts_with(b"\x89PNG") {
+        "image/png"
+    } else {
+        "image/jpeg"
+    }
+}
+
+/// Turns an HTML show-notes description into plain text for tag comments
+pub fn plain_text_description(description: &str) -> String {
+    let mut text = String::with_capacity(description.len());
+    let mut in_tag = false;
+    for c in description.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_tags() -> EpisodeTags {
+        EpisodeTags {
+            title: "Episode 12: Tags".to_string(),
+            podcast: "Tagged Podcast".to_string(),
+            published: NaiveDate::from_ymd_opt(2023, 4, 5),
+            track: Some(12),
+            comment: Some("All about tags".to_string()),
+            artwork: Some(b"\x89PNG\r\n\x1a\n fake png".to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_write_id3v24_tags_replacing_feed_values() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.mp3");
+        let mut feed_tag = Tag::new();
+        feed_tag.set_title("track01");
+        feed_tag.set_artist("Unknown Artist");
+        feed_tag.set_text("TENC", "Some Encoder");
+        std::fs::write(&path, [0xFF, 0xFB, 0x90, 0x64, 0, 0, 0, 0]).unwrap();
+        feed_tag.write_to_path(&path, Version::Id3v24).unwrap();
+
+        write_episode_tags(&path, &sample_tags(), Version::Id3v24).unwrap();
+
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.title(), Some("Episode 12: Tags"));
+        assert_eq!(tag.artist(), Some("Tagged Podcast"));
+        assert_eq!(tag.album(), Some("Tagged Podcast"));
+        assert_eq!(tag.album_artist(), Some("Tagged Podcast"));
+        assert_eq!(tag.genre(), Some(PODCAST_GENRE));
+        assert_eq!(tag.track(), Some(12));
+        assert_eq!(tag.date_recorded().map(|date| date.month), Some(Some(4)));
+        assert_eq!(
+            tag.comments().next().map(|comment| comment.text.as_str()),
+            Some("All about tags")
+        );
+        let picture = tag.pictures().next().unwrap();
+        assert_eq!(picture.mime_type, "image/png");
+        assert_eq!(picture.picture_type, PictureType::CoverFront);
+        // Frames PodPico doesn't manage are kept
+        assert_eq!(
+            tag.get("TENC").and_then(|frame| frame.content().text()),
+            Some("Some Encoder")
+        );
+    }
+
+    #[test]
+    fn test_write_id3v23_uses_year_only() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.mp3");
+        std::fs::write(&path, [0xFF, 0xFB, 0x90, 0x64, 0, 0, 0, 0]).unwrap();
+
+        write_episode_tags(&path, &sample_tags(), Version::Id3v23).unwrap();
+
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.version(), Version::Id3v23);
+        assert_eq!(tag.year(), Some(2023));
+        assert_eq!(tag.title(), Some("Episode 12: Tags"));
+    }
+
+    #[test]
+    fn test_unsupported_formats_are_rejected() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("episode.ogg");
+        std::fs::write(&path, b"OggS").unwrap();
+
+        assert!(matches!(
+            write_episode_tags(&path, &sample_tags(), Version::Id3v23),
+            Err(PodPicoError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_id3_version() {
+        assert_eq!(parse_id3_version("2.3").unwrap(), Version::Id3v23);
+        assert_eq!(parse_id3_version(" 2.4 ").unwrap(), Version::Id3v24);
+        assert!(parse_id3_version("1.1").is_err());
+    }
+
+    #[test]
+    fn test_plain_text_description() {
+        assert_eq!(
+            plain_text_description("<p>Show notes &amp; links:</p><ul><li>One</li></ul>"),
+            "Show notes & links: One"
+        );
+        assert_eq!(plain_text_description("Already plain"), "Already plain");
+    }
+}
@@ -0,0 +1,240 @@
+// Download throttling module for PodPico
+// Limits download bandwidth and restricts queued downloads to configured time windows
+
+use crate::commands::DownloadWindow;
+use crate::error::PodPicoError;
+use chrono::{NaiveTime, Timelike};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest single wait while holding a queued download, so configuration changes
+/// and clock adjustments are picked up
+pub const WINDOW_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token bucket shared by everything that should stay below `bytes_per_second`.
+/// The bucket holds at most one second worth of bytes, so bursts stay short.
+pub struct RateLimiter {
+    bytes_per_second: u64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    available: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            bucket: Mutex::new(Bucket {
+                available: bytes_per_second as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn from_kbps(kilobytes_per_second: u64) -> Self {
+        Self::new(kilobytes_per_second * 1024)
+    }
+
+    /// Waits until `bytes` may be transferred without exceeding the rate
+    pub async fn acquire(&self, bytes: u64) {
+        let wait = self.reserve(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes `bytes` from the bucket, going into debt if needed, and returns how long
+    /// the caller has to wait for the debt to be paid off
+    fn reserve(&self, bytes: u64, now: Instant) -> Duration {
+        let mut bucket = match self.bucket.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let rate = self.bytes_per_second as f64;
+
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.available = (bucket.available + elapsed.as_secs_f64() * rate).min(rate);
+        bucket.last_refill = now;
+        bucket.available -= bytes as f64;
+
+        if bucket.available >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.available / rate)
+        }
+    }
+}
+
+/// Parses "HH:MM"
+pub fn parse_window_time(time: &str) -> Result<NaiveTime, PodPicoError> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| PodPicoError::InvalidDownloadWindow(format!("'{}' is not a HH:MM time", time)))
+}
+
+pub fn validate_download_windows(windows: &[DownloadWindow]) -> Result<(), PodPicoError> {
+    for window in windows {
+        let start = parse_window_time(&window.start)?;
+        let end = parse_window_time(&window.end)?;
+        if start == end {
+            return Err(PodPicoError::InvalidDownloadWindow(format!(
+                "{}-{} is empty",
+                window.start, window.end
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// True when downloads may run at `time`. No windows means no restriction; a window
+/// whose end is before its start spans midnight (e.g. 23:00-06:00).
+pub fn is_within_download_windows(windows: &[DownloadWindow], time: NaiveTime) -> bool {
+    windows.is_empty()
+        || windows.iter().any(|window| {
+            match (
+                parse_window_time(&window.start),
+                parse_window_time(&window.end),
+            ) {
+                (Ok(start), Ok(end)) if start < end => start <= time && time < end,
+                (Ok(start), Ok(end)) => time >= start || time < end,
+                _ => false,
+            }
+        })
+}
+
+/// Time from `time` until the next download window opens, zero when one is open
+pub fn time_until_download_window(windows: &[DownloadWindow], time: NaiveTime) -> Duration {
+    if is_within_download_windows(windows, time) {
+        return Duration::ZERO;
+    }
+
+    let now = time.num_seconds_from_midnight() as u64;
+    windows
+        .iter()
+        .filter_map(|window| parse_window_time(&window.start).ok())
+        .map(|start| {
+            let start = start.num_seconds_from_midnight() as u64;
+            if start > now {
+                start - now
+            } else {
+                start + 24 * 3600 - now
+            }
+        })
+        .min()
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str) -> DownloadWindow {
+        DownloadWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn at(time: &str) -> NaiveTime {
+        parse_window_time(time).unwrap()
+    }
+
+    #[test]
+    fn test_rate_limiter_allows_initial_burst() {
+        let limiter = RateLimiter::new(1000);
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve(1000, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_rate_limiter_delays_when_bucket_is_empty() {
+        let limiter = RateLimiter::new(1000);
+        let now = Instant::now();
+
+        limiter.reserve(1000, now);
+        assert_eq!(limiter.reserve(500, now).as_millis(), 500);
+
+        // 250 ms later a quarter of the rate has been refilled, so the debt shrank
+        assert_eq!(
+            limiter
+                .reserve(250, now + Duration::from_millis(250))
+                .as_millis(),
+            500
+        );
+        assert_eq!(
+            limiter.reserve(0, now + Duration::from_secs(2)),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_caps_throughput() {
+        let limiter = RateLimiter::new(10_000);
+        let start = Instant::now();
+
+        // 10 KB burst plus 5 KB that must be paced
+        for _ in 0..15 {
+            limiter.acquire(1000).await;
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(450));
+    }
+
+    #[test]
+    fn test_no_windows_means_always_open() {
+        assert!(is_within_download_windows(&[], at("12:00")));
+        assert_eq!(time_until_download_window(&[], at("12:00")), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_daytime_window() {
+        let windows = vec![window("09:00", "17:00")];
+
+        assert!(is_within_download_windows(&windows, at("09:00")));
+        assert!(is_within_download_windows(&windows, at("16:59")));
+        assert!(!is_within_download_windows(&windows, at("17:00")));
+        assert_eq!(
+            time_until_download_window(&windows, at("08:30")),
+            Duration::from_secs(30 * 60)
+        );
+    }
+
+    #[test]
+    fn test_overnight_window() {
+        let windows = vec![window("23:00", "06:00")];
+
+        assert!(is_within_download_windows(&windows, at("23:30")));
+        assert!(is_within_download_windows(&windows, at("02:00")));
+        assert!(!is_within_download_windows(&windows, at("12:00")));
+        assert_eq!(
+            time_until_download_window(&windows, at("22:00")),
+            Duration::from_secs(3600)
+        );
+    }
+
+    #[test]
+    fn test_next_window_is_soonest_start() {
+        let windows = vec![window("01:00", "02:00"), window("20:00", "21:00")];
+
+        assert_eq!(
+            time_until_download_window(&windows, at("19:00")),
+            Duration::from_secs(3600)
+        );
+        assert_eq!(
+            time_until_download_window(&windows, at("22:00")),
+            Duration::from_secs(3 * 3600)
+        );
+    }
+
+    #[test]
+    fn test_validate_download_windows() {
+        assert!(validate_download_windows(&[window("23:00", "06:00")]).is_ok());
+        assert!(validate_download_windows(&[window("25:00", "06:00")]).is_err());
+        assert!(validate_download_windows(&[window("10:00", "10:00")]).is_err());
+        assert!(validate_download_windows(&[window("9am", "5pm")]).is_err());
+    }
+}
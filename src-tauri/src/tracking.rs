@@ -0,0 +1,205 @@
+// Tracking redirect module for PodPico
+// Recognises analytics redirect prefixes in enclosure URLs so downloads can go to the host directly
+
+use crate::error::PodPicoError;
+
+/// Redirect prefixes stripped in privacy mode. A rule is a host followed by path
+/// segments and a trailing slash; `*` stands for exactly one path segment.
+pub const DEFAULT_TRACKING_PREFIXES: &[&str] = &[
+    "dts.podtrac.com/*/",
+    "www.podtrac.com/pts/*/",
+    "chrt.fm/track/*/",
+    "chtbl.com/track/*/",
+    "pdst.fm/e/",
+    "op3.dev/e/",
+    "mgln.ai/e/*/",
+    "pfx.vpixl.com/*/",
+    "arttrk.com/p/*/",
+    "verifi.podscribe.com/rss/p/",
+    "pscrb.fm/rss/p/",
+    "claritaspod.com/measure/",
+    "prfx.byspotify.com/e/",
+    "tracking.swap.fm/track/*/",
+    "pdcn.co/e/",
+];
+
+/// Redirectors are commonly chained; stop after this many so a malformed URL can't loop
+const MAX_STRIPPED_PREFIXES: usize = 10;
+
+pub fn validate_tracking_prefixes(prefixes: &[String]) -> Result<(), PodPicoError> {
+    for prefix in prefixes {
+        let invalid =
+            |reason: &str| PodPicoError::InvalidTrackingPrefix(format!("'{}' {}", prefix, reason));
+
+        if prefix.contains("://") {
+            return Err(invalid("must not include a scheme"));
+        }
+        let Some(path) = prefix.strip_suffix('/') else {
+            return Err(invalid("must end with '/'"));
+        };
+        let mut segments = path.split('/');
+        let host = segments.next().unwrap_or_default();
+        if host.is_empty() || host.contains('*') || host.contains(char::is_whitespace) {
+            return Err(invalid("must start with a host name"));
+        }
+        if segments.any(|segment| segment.is_empty()) {
+            return Err(invalid("must not contain empty path segments"));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the URL behind any chain of known redirect prefixes, or `None` when the
+/// URL doesn't start with one. The original scheme is kept unless the redirector
+/// embeds a complete URL.
+pub fn strip_tracking_prefixes<S: AsRef<str>>(url: &str, prefixes: &[S]) -> Option<String> {
+    let mut current = url.to_string();
+    let mut stripped = false;
+
+    for _ in 0..MAX_STRIPPED_PREFIXES {
+        let Some((scheme, rest)) = current.split_once("://") else {
+            break;
+        };
+        let Some(remainder) = prefixes
+            .iter()
+            .find_map(|prefix| match_prefix(rest, prefix.as_ref()))
+        else {
+            break;
+        };
+
+        let next = if has_http_scheme(remainder) {
+            remainder.to_string()
+        } else if looks_like_host(remainder) {
+            format!("{}://{}", scheme, remainder)
+        } else {
+            break;
+        };
+        current = next;
+        stripped = true;
+    }
+
+    stripped.then_some(current)
+}
+
+/// Matches `rule` against the start of a scheme-less URL and returns what follows it
+fn match_prefix<'a>(url: &'a str, rule: &str) -> Option<&'a str> {
+    let mut remainder = url;
+    for segment in rule.strip_suffix('/')?.split('/') {
+        let (head, tail) = remainder.split_once('/')?;
+        let matches = if segment == "*" {
+            !head.is_empty()
+        } else {
+            head.eq_ignore_ascii_case(segment)
+        };
+        if !matches {
+            return None;
+        }
+        remainder = tail;
+    }
+    Some(remainder)
+}
+
+fn has_http_scheme(url: &str) -> bool {
+    let lower = url.get(..8).unwrap_or(url).to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// True when the first segment of `url` could be a host name, e.g. `cdn.example.com`
+fn looks_like_host(url: &str) -> bool {
+    let host = url.split(['/', '?', '#']).next().unwrap_or_default();
+    host.contains('.')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '_'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(url: &str) -> Option<String> {
+        strip_tracking_prefixes(url, DEFAULT_TRACKING_PREFIXES)
+    }
+
+    #[test]
+    fn test_strip_single_prefix() {
+        assert_eq!(
+            strip("https://dts.podtrac.com/redirect.mp3/cdn.example.com/show/ep1.mp3?x=1"),
+            Some("https://cdn.example.com/show/ep1.mp3?x=1".to_string())
+        );
+        assert_eq!(
+            strip("http://pdst.fm/e/media.example.org/ep.m4a"),
+            Some("http://media.example.org/ep.m4a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_strip_prefix_chain() {
+        assert_eq!(
+            strip(
+                "https://chrt.fm/track/ABC123/pdst.fm/e/dts.podtrac.com/redirect.mp3/cdn.example.com/ep.mp3"
+            ),
+            Some("https://cdn.example.com/ep.mp3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_strip_prefix_with_embedded_url() {
+        assert_eq!(
+            strip("https://op3.dev/e/http://cdn.example.com/ep.mp3"),
+            Some("http://cdn.example.com/ep.mp3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_untracked_urls_are_left_alone() {
+        assert_eq!(strip("https://cdn.example.com/show/ep1.mp3"), None);
+        // Wildcards need a non-empty segment and the remainder must name a host
+        assert_eq!(strip("https://chrt.fm/track//cdn.example.com/ep.mp3"), None);
+        assert_eq!(strip("https://pdst.fm/e/episode-123"), None);
+        assert_eq!(strip("not a url"), None);
+    }
+
+    #[test]
+    fn test_custom_prefixes() {
+        let prefixes = vec!["Stats.Example.NET/hit/*/".to_string()];
+
+        assert_eq!(
+            strip_tracking_prefixes(
+                "https://stats.example.net/hit/42/cdn.example.com/ep.mp3",
+                &prefixes
+            ),
+            Some("https://cdn.example.com/ep.mp3".to_string())
+        );
+        assert_eq!(
+            strip_tracking_prefixes(
+                "https://dts.podtrac.com/redirect.mp3/cdn.example.com/ep.mp3",
+                &prefixes
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_tracking_prefixes() {
+        let defaults: Vec<String> = DEFAULT_TRACKING_PREFIXES
+            .iter()
+            .map(|prefix| prefix.to_string())
+            .collect();
+        assert!(validate_tracking_prefixes(&defaults).is_ok());
+
+        for invalid in [
+            "https://pdst.fm/e/",
+            "pdst.fm/e",
+            "*/e/",
+            "/e/",
+            "pdst.fm//e/",
+        ] {
+            assert!(
+                validate_tracking_prefixes(&[invalid.to_string()]).is_err(),
+                "'{}' should be rejected",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,868 @@
+// Transcoding module for PodPico
+// Converts episodes with an external encoder for devices that can't play the
+// original format or have little storage, caching the converted files
+
+use crate::chapters::Segment;
+use crate::commands::TranscodeProfile;
+use crate::error::PodPicoError;
+use crate::file_manager::file_sha256;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Target codecs of a transcode profile; "off" transfers the original file
+pub const TRANSCODE_CODECS: &[&str] = &["off", "mp3", "aac", "opus"];
+
+/// Bitrate offered for new profiles; plenty for speech in MP3
+pub const DEFAULT_TRANSCODE_BITRATE_KBPS: i64 = 64;
+
+/// Playback speeds a podcast can be rendered at
+pub const MIN_PLAYBACK_SPEED: f64 = 0.5;
+pub const MAX_PLAYBACK_SPEED: f64 = 3.0;
+
+/// Silences quieter than this are trimmed by default
+pub const DEFAULT_SILENCE_THRESHOLD_DB: f64 = -50.0;
+
+/// Silences shorter than this are left alone by default
+pub const DEFAULT_MIN_SILENCE_SECONDS: f64 = 2.0;
+
+/// Trimmed silences are shortened to this length rather than removed, so
+/// sentences don't run into each other
+const KEPT_SILENCE_SECONDS: f64 = 0.5;
+
+/// Encoder programs looked up on PATH, in order of preference
+const ENCODER_NAMES: &[&str] = &["ffmpeg"];
+
+/// Source files up to this much above the target bitrate are not worth re-encoding
+const BITRATE_TOLERANCE: f64 = 1.05;
+
+/// Space the cache of converted files may take before the least recently
+/// used ones are removed
+pub const DEFAULT_TRANSCODE_CACHE_LIMIT_MB: u64 = 2048;
+
+impl TranscodeProfile {
+    /// Whether files are converted at all
+    pub fn is_enabled(&self) -> bool {
+        self.codec != "off"
+    }
+
+    /// Extension of the converted files
+    pub fn extension(&self) -> &'static str {
+        match self.codec.as_str() {
+            "aac" => "m4a",
+            "opus" => "opus",
+            _ => "mp3",
+        }
+    }
+
+    /// Re-encodes in the source file's own format, for when only the volume
+    /// changes. `source_bitrate` is the measured bitrate in bits per second.
+    pub fn matching_source(device_id: &str, source: &Path, source_bitrate: Option<i32>) -> Self {
+        let extension = source
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let codec = match extension.as_str() {
+            "m4a" | "m4b" | "aac" => "aac",
+            "opus" => "opus",
+            _ => "mp3",
+        };
+        let bitrate_kbps = source_bitrate
+            .map(|bitrate| i64::from(bitrate / 1000).clamp(32, 320))
+            .unwrap_or(128);
+        Self {
+            device_id: device_id.to_string(),
+            codec: codec.to_string(),
+            bitrate_kbps,
+            mono: false,
+        }
+    }
+
+    /// Identifies the output settings in cache file names
+    fn cache_suffix(&self) -> String {
+        format!(
+            "{}-{}k-{}",
+            self.codec,
+            self.bitrate_kbps,
+            if self.mono { "mono" } else { "stereo" }
+        )
+    }
+
+    /// Encoder library and output container for ffmpeg
+    fn ffmpeg_codec_and_format(&self) -> (&'static str, &'static str) {
+        match self.codec.as_str() {
+            "aac" => ("aac", "ipod"),
+            "opus" => ("libopus", "ogg"),
+            _ => ("libmp3lame", "mp3"),
+        }
+    }
+}
+
+/// Changes made to the audio while converting it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AudioAdjustments {
+    /// Volume change in dB
+    pub gain_db: Option<f64>,
+    /// Playback speed factor, applied without changing the pitch
+    pub speed: Option<f64>,
+    /// Shortening of long silences
+    pub silence: Option<SilenceTrim>,
+}
+
+/// Silences at or below `threshold_db` lasting longer than
+/// `min_duration_seconds` are shortened
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceTrim {
+    pub threshold_db: f64,
+    pub min_duration_seconds: f64,
+}
+
+impl AudioAdjustments {
+    pub fn is_empty(&self) -> bool {
+        self.gain_db.is_none() && self.speed.is_none() && self.silence.is_none()
+    }
+
+    /// Identifies the adjustments in cache file names
+    fn cache_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if let Some(gain) = self.gain_db {
+            suffix.push_str(&format!("-{:+.1}dB", gain));
+        }
+        if let Some(speed) = self.speed {
+            suffix.push_str(&format!("-x{:.2}", speed));
+        }
+        if let Some(silence) = self.silence {
+            suffix.push_str(&format!(
+                "-trim{:.0}dB{:.1}s",
+                silence.threshold_db, silence.min_duration_seconds
+            ));
+        }
+        suffix
+    }
+
+    /// ffmpeg audio filter chain, if anything is adjusted
+    fn ffmpeg_filters(&self) -> Option<String> {
+        let mut filters = Vec::new();
+        if let Some(gain) = self.gain_db {
+            filters.push(format!("volume={:.1}dB", gain));
+        }
+        if let Some(silence) = self.silence {
+            filters.push(format!(
+                "silenceremove=stop_periods=-1:stop_duration={:.1}:stop_threshold={:.0}dB:stop_silence={:.1}",
+                silence.min_duration_seconds, silence.threshold_db, KEPT_SILENCE_SECONDS
+            ));
+        }
+        if let Some(mut speed) = self.speed {
+            // Older ffmpeg versions limit atempo to 0.5-2.0, so larger factors
+            // are split into several stages
+            while speed > 2.0 {
+                filters.push("atempo=2.0".to_string());
+                speed /= 2.0;
+            }
+            filters.push(format!("atempo={:.4}", speed));
+        }
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+}
+
+pub fn validate_playback_speed(speed: f64) -> Result<(), PodPicoError> {
+    if !(MIN_PLAYBACK_SPEED..=MAX_PLAYBACK_SPEED).contains(&speed) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Playback speed must be between {} and {}, got {}",
+            MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED, speed
+        )));
+    }
+    Ok(())
+}
+
+pub fn validate_silence_trim(silence: &SilenceTrim) -> Result<(), PodPicoError> {
+    if !(-90.0..=-20.0).contains(&silence.threshold_db) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Silence threshold must be between -90 and -20 dB, got {}",
+            silence.threshold_db
+        )));
+    }
+    if !(KEPT_SILENCE_SECONDS..=60.0).contains(&silence.min_duration_seconds) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Minimum silence must be between {} and 60 seconds, got {}",
+            KEPT_SILENCE_SECONDS, silence.min_duration_seconds
+        )));
+    }
+    Ok(())
+}
+
+pub fn validate_transcode_profile(profile: &TranscodeProfile) -> Result<(), PodPicoError> {
+    if !TRANSCODE_CODECS.contains(&profile.codec.as_str()) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Transcode codec must be one of {}, got '{}'",
+            TRANSCODE_CODECS.join(", "),
+            profile.codec
+        )));
+    }
+    if !(8..=320).contains(&profile.bitrate_kbps) {
+        return Err(PodPicoError::UnsupportedFormat(format!(
+            "Transcode bitrate must be between 8 and 320 kbps, got {}",
+            profile.bitrate_kbps
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `source` has to be converted to satisfy `profile`. Files that already
+/// use the target codec at or below the target bitrate are transferred as they are.
+/// `source_bitrate` is the measured bitrate in bits per second, if known.
+pub fn needs_transcoding(
+    source: &Path,
+    source_bitrate: Option<i32>,
+    profile: &TranscodeProfile,
+) -> bool {
+    if !profile.is_enabled() {
+        return false;
+    }
+    if profile.mono {
+        // Channel counts aren't recorded, so a mono copy is always made
+        return true;
+    }
+
+    let extension = source
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let same_codec = match profile.codec.as_str() {
+        "aac" => ["m4a", "m4b", "aac"].contains(&extension.as_str()),
+        codec => extension == codec,
+    };
+    let small_enough = source_bitrate.is_some_and(|bitrate| {
+        f64::from(bitrate) <= profile.bitrate_kbps as f64 * 1000.0 * BITRATE_TOLERANCE
+    });
+
+    !(same_codec && small_enough)
+}
+
+/// Finds the encoder: the configured path if given, otherwise the first known
+/// encoder on PATH
+pub fn find_encoder(configured: Option<&str>) -> Option<PathBuf> {
+    if let Some(configured) = configured.filter(|path| !path.trim().is_empty()) {
+        let path = PathBuf::from(configured);
+        return path.is_file().then_some(path);
+    }
+
+    let search_path = std::env::var_os("PATH")?;
+    ENCODER_NAMES.iter().find_map(|name| {
+        std::env::split_paths(&search_path)
+            .map(|dir| dir.join(executable_name(name)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn executable_name(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[derive(Clone)]
+pub struct Transcoder {
+    cache_directory: PathBuf,
+    /// Encoder path from the configuration; PATH is searched when unset
+    encoder_path: Option<String>,
+    cache_limit_bytes: u64,
+}
+
+impl Transcoder {
+    pub fn new(cache_directory: &str) -> Self {
+        Self {
+            cache_directory: PathBuf::from(cache_directory),
+            encoder_path: None,
+            cache_limit_bytes: DEFAULT_TRANSCODE_CACHE_LIMIT_MB * 1024 * 1024,
+        }
+    }
+
+    /// Uses the encoder at `encoder_path` instead of searching PATH
+    pub fn with_encoder_path(mut self, encoder_path: Option<String>) -> Self {
+        self.encoder_path = encoder_path;
+        self
+    }
+
+    /// Limits the cache of converted files to `limit_mb` megabytes
+    pub fn with_cache_limit_mb(mut self, limit_mb: u64) -> Self {
+        self.cache_limit_bytes = limit_mb * 1024 * 1024;
+        self
+    }
+
+    pub async fn initialize(&self) -> Result<(), PodPicoError> {
+        fs::create_dir_all(&self.cache_directory).await?;
+        Ok(())
+    }
+
+    /// Converts `source` according to `profile` and returns the path of the
+    /// converted file. Outputs are cached by source content and settings, so
+    /// transferring the same episode again reuses the earlier conversion.
+    pub async fn transcode(
+        &self,
+        source: &Path,
+        profile: &TranscodeProfile,
+    ) -> Result<PathBuf, PodPicoError> {
+        self.transcode_adjusted(source, profile, &AudioAdjustments::default())
+            .await
+    }
+
+    /// Like `transcode`, additionally applying `adjustments` to the audio
+    pub async fn transcode_adjusted(
+        &self,
+        source: &Path,
+        profile: &TranscodeProfile,
+        adjustments: &AudioAdjustments,
+    ) -> Result<PathBuf, PodPicoError> {
+        let source_hash = file_sha256(source).await?;
+        let output = self.cache_directory.join(format!(
+            "{}-{}{}.{}",
+            source_hash,
+            profile.cache_suffix(),
+            adjustments.cache_suffix(),
+            profile.extension()
+        ));
+        if output.exists() {
+            log::info!("Using cached transcode {}", output.display());
+            mark_used(&output);
+            return Ok(output);
+        }
+
+        let encoder = find_encoder(self.encoder_path.as_deref()).ok_or_else(|| {
+            PodPicoError::TranscodeFailed(
+                "No encoder found; install ffmpeg or configure the encoder path".to_string(),
+            )
+        })?;
+        log::info!(
+            "Transcoding {} to {} with {}",
+            source.display(),
+            profile.cache_suffix(),
+            encoder.display()
+        );
+
+        let partial = output.with_extension("part");
+        let (codec, format) = profile.ffmpeg_codec_and_format();
+        let mut command = tokio::process::Command::new(&encoder);
+        command
+            .args(["-nostdin", "-hide_banner", "-loglevel", "error", "-y", "-i"])
+            .arg(source)
+            .args(["-map", "0:a:0", "-map_metadata", "0", "-c:a", codec])
+            .args(["-b:a", &format!("{}k", profile.bitrate_kbps)]);
+        if profile.mono {
+            command.args(["-ac", "1"]);
+        }
+        if let Some(filters) = adjustments.ffmpeg_filters() {
+            command.args(["-af", &filters]);
+        }
+        command.args(["-f", format]).arg(&partial);
+
+        let result = command
+            .output()
+            .await
+            .map_err(|e| PodPicoError::TranscodeFailed(format!("Failed to run encoder: {}", e)));
+        match result {
+            Ok(result) if result.status.success() && partial.exists() => {
+                fs::rename(&partial, &output).await?;
+                self.evict_cache(std::slice::from_ref(&output)).await?;
+                Ok(output)
+            }
+            Ok(result) => {
+                let _ = fs::remove_file(&partial).await;
+                Err(PodPicoError::TranscodeFailed(format!(
+                    "Encoder exited with {}: {}",
+                    result.status,
+                    String::from_utf8_lossy(&result.stderr).trim()
+                )))
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&partial).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Cuts `source` into `segments` without re-encoding and returns the
+    /// segment files in order. Each segment is tagged with its own title and
+    /// track number; the other tags are copied from the source.
+    pub async fn split(
+        &self,
+        source: &Path,
+        segments: &[Segment],
+        episode_title: &str,
+    ) -> Result<Vec<PathBuf>, PodPicoError> {
+        let extension = source
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let format = match extension.as_str() {
+            "m4a" | "m4b" | "mp4" | "aac" => "ipod",
+            "opus" | "ogg" | "oga" => "ogg",
+            _ => "mp3",
+        };
+        let source_hash = file_sha256(source).await?;
+        let encoder = find_encoder(self.encoder_path.as_deref());
+
+        let mut outputs = Vec::with_capacity(segments.len());
+        for (index, segment) in segments.iter().enumerate() {
+            let number = index + 1;
+            let title = match &segment.title {
+                Some(chapter) => format!(
+                    "{} ({}/{}): {}",
+                    episode_title,
+                    number,
+                    segments.len(),
+                    chapter
+                ),
+                None => format!("{} ({}/{})", episode_title, number, segments.len()),
+            };
+            // Both bounds and the title are part of the name, so splitting at
+            // other times or with other chapters doesn't reuse stale segments
+            let end = segment
+                .end_seconds
+                .map(|end| ((end * 1000.0).round() as u64).to_string())
+                .unwrap_or_else(|| "end".to_string());
+            let output = self.cache_directory.join(format!(
+                "{}-part{:02}of{:02}-{}-{}-{}.{}",
+                source_hash,
+                number,
+                segments.len(),
+                (segment.start_seconds * 1000.0).round() as u64,
+                end,
+                &text_sha256(&title)[..8],
+                extension
+            ));
+            if output.exists() {
+                mark_used(&output);
+                outputs.push(output);
+                continue;
+            }
+
+            let encoder = encoder.as_ref().ok_or_else(|| {
+                PodPicoError::TranscodeFailed(
+                    "No encoder found; install ffmpeg or configure the encoder path".to_string(),
+                )
+            })?;
+
+            let partial = output.with_extension("part");
+            let mut command = tokio::process::Command::new(encoder);
+            command
+                .args(["-nostdin", "-hide_banner", "-loglevel", "error", "-y"])
+                .args(["-ss", &format!("{:.3}", segment.start_seconds), "-i"])
+                .arg(source);
+            if let Some(end) = segment.end_seconds {
+                command.args(["-t", &format!("{:.3}", end - segment.start_seconds)]);
+            }
+            command
+                .args(["-map", "0:a:0", "-map_metadata", "0", "-map_chapters", "-1"])
+                .args(["-c", "copy", "-metadata"])
+                .arg(format!("title={}", title))
+                .arg("-metadata")
+                .arg(format!("track={}/{}", number, segments.len()))
+                .args(["-f", format])
+                .arg(&partial);
+
+            let result = command.output().await.map_err(|e| {
+                PodPicoError::TranscodeFailed(format!("Failed to run encoder: {}", e))
+            })?;
+            if !result.status.success() || !partial.exists() {
+                let _ = fs::remove_file(&partial).await;
+                return Err(PodPicoError::TranscodeFailed(format!(
+                    "Encoder exited with {} while splitting: {}",
+                    result.status,
+                    String::from_utf8_lossy(&result.stderr).trim()
+                )));
+            }
+            fs::rename(&partial, &output).await?;
+            outputs.push(output);
+        }
+
+        self.evict_cache(&outputs).await?;
+        Ok(outputs)
+    }
+
+    /// Removes the least recently used converted files until the cache fits
+    /// its limit. The files in `keep` are about to be transferred and files
+    /// still being written are left alone.
+    async fn evict_cache(&self, keep: &[PathBuf]) -> Result<(), PodPicoError> {
+        let mut files = Vec::new();
+        let mut total_size = 0;
+        let mut entries = fs::read_dir(&self.cache_directory).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+            total_size += metadata.len();
+            let path = entry.path();
+            if keep.contains(&path) || path.extension().is_some_and(|ext| ext == "part") {
+                continue;
+            }
+            let used = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+            files.push((used, metadata.len(), path));
+        }
+
+        files.sort();
+        for (_, size, path) in files {
+            if total_size <= self.cache_limit_bytes {
+                break;
+            }
+            log::info!("Evicting cached transcode {}", path.display());
+            fs::remove_file(&path).await?;
+            total_size -= size;
+        }
+        Ok(())
+    }
+}
+
+fn text_sha256(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// Records a cache hit in the file's modification time, which orders eviction
+fn mark_used(path: &Path) {
+    let touched = std::fs::File::options()
+        .append(true)
+        .open(path)
+        .and_then(|file| file.set_modified(std::time::SystemTime::now()));
+    if let Err(e) = touched {
+        log::warn!("Failed to mark {} as used: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn profile(codec: &str, bitrate_kbps: i64, mono: bool) -> TranscodeProfile {
+        TranscodeProfile {
+            device_id: "player".to_string(),
+            codec: codec.to_string(),
+            bitrate_kbps,
+            mono,
+        }
+    }
+
+    /// Stands in for ffmpeg: records its arguments and copies the input to the output
+    #[cfg(unix)]
+    fn fake_encoder(dir: &Path) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = dir.join("fake-ffmpeg");
+        std::fs::write(
+            &script,
+            r#"#!/bin/sh
+echo "$@" >> "$(dirname "$0")/calls"
+while [ $# -gt 1 ]; do
+    if [ "$1" = "-i" ]; then input="$2"; fi
+    shift
+done
+cp "$input" "$1"
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_transcode_caches_by_source_and_settings() {
+        let temp_dir = tempdir().unwrap();
+        let encoder = fake_encoder(temp_dir.path());
+        let source = temp_dir.path().join("episode.opus");
+        std::fs::write(&source, b"opus audio").unwrap();
+
+        let transcoder = Transcoder::new(&temp_dir.path().join("cache").to_string_lossy())
+            .with_encoder_path(Some(encoder.to_string_lossy().to_string()));
+        transcoder.initialize().await.unwrap();
+
+        let mono = profile("mp3", 48, true);
+        let first = transcoder.transcode(&source, &mono).await.unwrap();
+        let again = transcoder.transcode(&source, &mono).await.unwrap();
+        let stereo = transcoder
+            .transcode(&source, &profile("mp3", 48, false))
+            .await
+            .unwrap();
+
+        assert_eq!(first, again);
+        assert_ne!(first, stereo);
+        assert!(first.to_string_lossy().ends_with("-mp3-48k-mono.mp3"));
+        assert_eq!(std::fs::read(&first).unwrap(), b"opus audio");
+
+        let calls = std::fs::read_to_string(temp_dir.path().join("calls")).unwrap();
+        let calls: Vec<&str> = calls.lines().collect();
+        // The second request for the mono copy came from the cache
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0].contains("-c:a libmp3lame -b:a 48k -ac 1 -f mp3"));
+        assert!(!calls[1].contains("-ac 1"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_gain_adjusted_copy_in_source_format() {
+        let temp_dir = tempdir().unwrap();
+        let encoder = fake_encoder(temp_dir.path());
+        let source = temp_dir.path().join("episode.m4a");
+        std::fs::write(&source, b"aac audio").unwrap();
+        let transcoder = Transcoder::new(&temp_dir.path().join("cache").to_string_lossy())
+            .with_encoder_path(Some(encoder.to_string_lossy().to_string()));
+        transcoder.initialize().await.unwrap();
+
+        let profile = TranscodeProfile::matching_source("player", &source, Some(95_800));
+        assert_eq!((profile.codec.as_str(), profile.bitrate_kbps), ("aac", 95));
+
+        let output = transcoder
+            .transcode_adjusted(
+                &source,
+                &profile,
+                &AudioAdjustments {
+                    gain_db: Some(3.5),
+                    ..AudioAdjustments::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(output
+            .to_string_lossy()
+            .ends_with("-aac-95k-stereo-+3.5dB.m4a"));
+        let calls = std::fs::read_to_string(temp_dir.path().join("calls")).unwrap();
+        assert!(calls.contains("-af volume=3.5dB -f ipod"));
+    }
+
+    #[test]
+    fn test_speed_filters_stay_within_atempo_range() {
+        let adjustments = AudioAdjustments {
+            gain_db: Some(-2.0),
+            speed: Some(2.5),
+            silence: None,
+        };
+        assert_eq!(
+            adjustments.ffmpeg_filters().as_deref(),
+            Some("volume=-2.0dB,atempo=2.0,atempo=1.2500")
+        );
+        assert_eq!(adjustments.cache_suffix(), "--2.0dB-x2.50");
+        assert!(AudioAdjustments::default().ffmpeg_filters().is_none());
+
+        assert!(validate_playback_speed(1.5).is_ok());
+        assert!(validate_playback_speed(0.25).is_err());
+        assert!(validate_playback_speed(4.0).is_err());
+    }
+
+    #[test]
+    fn test_silence_is_trimmed_before_speed_change() {
+        let silence = SilenceTrim {
+            threshold_db: -50.0,
+            min_duration_seconds: 2.0,
+        };
+        let adjustments = AudioAdjustments {
+            speed: Some(1.5),
+            silence: Some(silence),
+            ..AudioAdjustments::default()
+        };
+        assert_eq!(
+            adjustments.ffmpeg_filters().as_deref(),
+            Some(
+                "silenceremove=stop_periods=-1:stop_duration=2.0:stop_threshold=-50dB:stop_silence=0.5,atempo=1.5000"
+            )
+        );
+        assert_eq!(adjustments.cache_suffix(), "-x1.50-trim-50dB2.0s");
+
+        assert!(validate_silence_trim(&silence).is_ok());
+        assert!(validate_silence_trim(&SilenceTrim {
+            threshold_db: -10.0,
+            ..silence
+        })
+        .is_err());
+        assert!(validate_silence_trim(&SilenceTrim {
+            min_duration_seconds: 0.1,
+            ..silence
+        })
+        .is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_split_into_tagged_segments() {
+        let temp_dir = tempdir().unwrap();
+        let encoder = fake_encoder(temp_dir.path());
+        let source = temp_dir.path().join("episode.mp3");
+        std::fs::write(&source, b"mp3 audio").unwrap();
+        let transcoder = Transcoder::new(&temp_dir.path().join("cache").to_string_lossy())
+            .with_encoder_path(Some(encoder.to_string_lossy().to_string()));
+        transcoder.initialize().await.unwrap();
+        let segments = [
+            Segment {
+                start_seconds: 0.0,
+                end_seconds: Some(600.0),
+                title: Some("Intro".to_string()),
+            },
+            Segment {
+                start_seconds: 600.0,
+                end_seconds: None,
+                title: None,
+            },
+        ];
+
+        let outputs = transcoder
+            .split(&source, &segments, "Long Talk")
+            .await
+            .unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs[1]
+            .to_string_lossy()
+            .contains("-part02of02-600000-end-"));
+        let calls = std::fs::read_to_string(temp_dir.path().join("calls")).unwrap();
+        let calls: Vec<&str> = calls.lines().collect();
+        assert!(calls[0].contains("-ss 0.000 -i"));
+        assert!(calls[0].contains("-t 600.000"));
+        assert!(calls[0].contains("title=Long Talk (1/2): Intro -metadata track=1/2"));
+        assert!(!calls[1].contains("-t "));
+
+        // Segments are cached like transcodes
+        transcoder
+            .split(&source, &segments, "Long Talk")
+            .await
+            .unwrap();
+        let calls = std::fs::read_to_string(temp_dir.path().join("calls")).unwrap();
+        assert_eq!(calls.lines().count(), 2);
+
+        // Splitting at other times makes new segments, though the first
+        // still starts at 0
+        let shorter = [
+            Segment {
+                start_seconds: 0.0,
+                end_seconds: Some(480.0),
+                title: None,
+            },
+            Segment {
+                start_seconds: 480.0,
+                end_seconds: None,
+                title: None,
+            },
+        ];
+        let resplit = transcoder
+            .split(&source, &shorter, "Long Talk")
+            .await
+            .unwrap();
+        assert_ne!(resplit[0], outputs[0]);
+        assert_ne!(resplit[1], outputs[1]);
+        let calls = std::fs::read_to_string(temp_dir.path().join("calls")).unwrap();
+        let calls: Vec<&str> = calls.lines().collect();
+        assert_eq!(calls.len(), 4);
+        assert!(calls[2].contains("-t 480.000"));
+        assert!(calls[2].contains("title=Long Talk (1/2) -metadata"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_least_recently_used_transcodes_are_evicted() {
+        let temp_dir = tempdir().unwrap();
+        let encoder = fake_encoder(temp_dir.path());
+        let cache = temp_dir.path().join("cache");
+        let transcoder = Transcoder::new(&cache.to_string_lossy())
+            .with_encoder_path(Some(encoder.to_string_lossy().to_string()))
+            .with_cache_limit_mb(1);
+        transcoder.initialize().await.unwrap();
+        let audio = vec![0u8; 400 * 1024];
+        let mut sources = Vec::new();
+        for name in ["first", "second", "third"] {
+            let source = temp_dir.path().join(format!("{}.opus", name));
+            std::fs::write(&source, [audio.as_slice(), name.as_bytes()].concat()).unwrap();
+            sources.push(source);
+        }
+        let mono = profile("mp3", 48, true);
+        let first = transcoder.transcode(&sources[0], &mono).await.unwrap();
+        let second = transcoder.transcode(&sources[1], &mono).await.unwrap();
+        // Using the first copy again makes the second the least recently used
+        let past = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        std::fs::File::options()
+            .append(true)
+            .open(&second)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+        std::fs::File::options()
+            .append(true)
+            .open(&first)
+            .unwrap()
+            .set_modified(past - std::time::Duration::from_secs(60))
+            .unwrap();
+        transcoder.transcode(&sources[0], &mono).await.unwrap();
+
+        let third = transcoder.transcode(&sources[2], &mono).await.unwrap();
+        assert!(first.exists());
+        assert!(!second.exists());
+        assert!(third.exists());
+    }
+
+    #[tokio::test]
+    async fn test_missing_encoder_is_reported() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("episode.opus");
+        std::fs::write(&source, b"opus audio").unwrap();
+        let transcoder =
+            Transcoder::new(&temp_dir.path().to_string_lossy()).with_encoder_path(Some(
+                temp_dir
+                    .path()
+                    .join("no-such-encoder")
+                    .to_string_lossy()
+                    .to_string(),
+            ));
+
+        let result = transcoder
+            .transcode(&source, &profile("mp3", 64, false))
+            .await;
+        assert!(matches!(result, Err(PodPicoError::TranscodeFailed(_))));
+    }
+
+    #[test]
+    fn test_needs_transcoding() {
+        let mp3 = Path::new("/episodes/show.mp3");
+        let m4a = Path::new("/episodes/show.m4a");
+
+        assert!(!needs_transcoding(
+            mp3,
+            Some(128_000),
+            &profile("off", 64, true)
+        ));
+        // Already small enough in the right format
+        assert!(!needs_transcoding(
+            mp3,
+            Some(64_000),
+            &profile("mp3", 64, false)
+        ));
+        assert!(!needs_transcoding(
+            m4a,
+            Some(48_000),
+            &profile("aac", 64, false)
+        ));
+        // Too large, wrong format, unknown bitrate or a mono copy requested
+        assert!(needs_transcoding(
+            mp3,
+            Some(128_000),
+            &profile("mp3", 64, false)
+        ));
+        assert!(needs_transcoding(
+            m4a,
+            Some(48_000),
+            &profile("mp3", 64, false)
+        ));
+        assert!(needs_transcoding(mp3, None, &profile("mp3", 64, false)));
+        assert!(needs_transcoding(
+            mp3,
+            Some(64_000),
+            &profile("mp3", 64, true)
+        ));
+    }
+
+    #[test]
+    fn test_validate_transcode_profile() {
+        assert!(validate_transcode_profile(&profile("mp3", 64, false)).is_ok());
+        assert!(validate_transcode_profile(&profile("flac", 64, false)).is_err());
+        assert!(validate_transcode_profile(&profile("mp3", 0, false)).is_err());
+    }
+}
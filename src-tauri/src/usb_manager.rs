@@ -1,12 +1,15 @@
 // USB device management module for PodPico
 // Handles USB device detection, mounting, and file operations
 
-use crate::commands::UsbDevice;
+use crate::audio_format::{is_audio_file, AUDIO_FILE_EXTENSIONS};
+use crate::commands::{TransferProgressEvent, UsbDevice};
 use crate::error::PodPicoError;
+use crate::events::{EventEmitter, TRANSFER_PROGRESS_EVENT};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use sysinfo::{Disks, System};
+use tauri::AppHandle;
 use tokio::fs;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::Mutex;
@@ -50,9 +53,195 @@ pub enum TransferStatus {
     Cancelled,
 }
 
+impl TransferStatus {
+    fn as_event_status(&self) -> (&'static str, Option<String>) {
+        match self {
+            TransferStatus::Pending => ("pending", None),
+            TransferStatus::InProgress => ("in_progress", None),
+            TransferStatus::Completed => ("completed", None),
+            TransferStatus::Failed(error) => ("failed", Some(error.clone())),
+            TransferStatus::Cancelled => ("cancelled", None),
+        }
+    }
+}
+
+impl From<&TransferProgress> for TransferProgressEvent {
+    fn from(progress: &TransferProgress) -> Self {
+        let (status, error) = progress.status.as_event_status();
+        Self {
+            episode_id: progress.episode_id,
+            device_id: progress.device_id.clone(),
+            transferred_bytes: progress.transferred_bytes,
+            total_bytes: progress.total_bytes,
+            percentage: progress.percentage,
+            speed_bps: progress.speed_bytes_per_sec,
+            eta_seconds: progress.eta_seconds.unwrap_or(0),
+            status: status.to_string(),
+            error,
+        }
+    }
+}
+
+/// Marker file holding a generated ID on filesystems without a UUID
+pub const DEVICE_ID_MARKER: &str = ".podpico-device-id";
+
+/// How a device is recognised across mounts
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceIdentity {
+    pub id: String,
+    pub filesystem_uuid: Option<String>,
+    pub label: Option<String>,
+}
+
+/// Describes a mounted disk, writing an ID marker to devices that need one
+/// and don't have it yet
+fn usb_device_from_disk(disk: &sysinfo::Disk) -> UsbDevice {
+    let device_name = disk.name().to_string_lossy().to_string();
+    let mount_point = disk.mount_point().to_string_lossy().to_string();
+    let identity = device_identity(&device_name, disk.mount_point());
+    let legacy_id = name_and_mount_id(&device_name, disk.mount_point());
+
+    UsbDevice {
+        id: identity.id,
+        name: match identity.label.clone() {
+            Some(label) => label,
+            None if device_name.is_empty() => "USB Device".to_string(),
+            None => device_name,
+        },
+        path: mount_point,
+        total_space: disk.total_space(),
+        available_space: disk.available_space(),
+        is_connected: true,
+        filesystem_uuid: identity.filesystem_uuid,
+        label: identity.label,
+        legacy_id,
+    }
+}
+
+/// ID made from the disk name and mount point, as devices were identified
+/// before filesystem UUIDs and marker files
+fn name_and_mount_id(device_name: &str, mount_point: &Path) -> String {
+    format!(
+        "{}_{}",
+        device_name.replace([' ', '/'], "_"),
+        mount_point.to_string_lossy().replace(['/', '\\'], "_")
+    )
+}
+
+/// Identifies the filesystem mounted at `mount_point`, independent of where it
+/// is mounted. The filesystem UUID is used where the system exposes it (Linux);
+/// otherwise an ID is kept in a marker file on the device itself. Read-only
+/// devices without a UUID fall back to the name and mount point.
+pub fn device_identity(device_name: &str, mount_point: &Path) -> DeviceIdentity {
+    let source = std::fs::read_to_string("/proc/mounts")
+        .ok()
+        .and_then(|mounts| mount_source(&mounts, &mount_point.to_string_lossy()))
+        .map(PathBuf::from);
+    let find_link = |dir: &str| {
+        source
+            .as_deref()
+            .and_then(|source| find_disk_link(Path::new(dir), source))
+    };
+    let filesystem_uuid = find_link("/dev/disk/by-uuid");
+    let label = find_link("/dev/disk/by-label");
+
+    let id = filesystem_uuid
+        .clone()
+        .or_else(|| read_or_create_marker(mount_point))
+        .unwrap_or_else(|| name_and_mount_id(device_name, mount_point));
+
+    DeviceIdentity {
+        id,
+        filesystem_uuid,
+        label,
+    }
+}
+
+/// Device mounted at `mount_point` according to a `/proc/mounts` listing
+fn mount_source(proc_mounts: &str, mount_point: &str) -> Option<String> {
+    proc_mounts.lines().rev().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let source = fields.next()?;
+        let target = fields.next()?;
+        (unescape_mount_field(target) == mount_point && source.starts_with("/dev/"))
+            .then(|| unescape_mount_field(source))
+    })
+}
+
+/// Undoes the octal escapes (`\040` for a space) used in `/proc/mounts`
+/// and in `/dev/disk/by-label` names (`\x20`)
+fn unescape_mount_field(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        result.push_str(&rest[..index]);
+        let escape = &rest[index + 1..];
+        let (value, length) = if let Some(hex) = escape.strip_prefix('x') {
+            (
+                hex.get(..2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                3,
+            )
+        } else {
+            (
+                escape
+                    .get(..3)
+                    .and_then(|octal| u8::from_str_radix(octal, 8).ok()),
+                3,
+            )
+        };
+        match value {
+            Some(byte) => {
+                result.push(char::from(byte));
+                rest = &escape[length..];
+            }
+            None => {
+                result.push('\\');
+                rest = escape;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Name of the link in `dir` (e.g. `/dev/disk/by-uuid`) that points at `device`
+fn find_disk_link(dir: &Path, device: &Path) -> Option<String> {
+    let device = std::fs::canonicalize(device).ok()?;
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .find(|entry| std::fs::canonicalize(entry.path()).is_ok_and(|target| target == device))
+        .map(|entry| unescape_mount_field(&entry.file_name().to_string_lossy()))
+}
+
+/// ID stored in the device's marker file, if it has one
+fn read_marker(mount_point: &Path) -> Option<String> {
+    let id = std::fs::read_to_string(mount_point.join(DEVICE_ID_MARKER)).ok()?;
+    Some(id.trim().to_string()).filter(|id| !id.is_empty())
+}
+
+/// ID stored in the device's marker file, written on first use
+fn read_or_create_marker(mount_point: &Path) -> Option<String> {
+    if let Some(id) = read_marker(mount_point) {
+        return Some(id);
+    }
+
+    let marker = mount_point.join(DEVICE_ID_MARKER);
+    let id = uuid::Uuid::new_v4().to_string();
+    match std::fs::write(&marker, &id) {
+        Ok(()) => Some(id),
+        Err(e) => {
+            log::warn!("Cannot write device ID marker {}: {}", marker.display(), e);
+            None
+        }
+    }
+}
+
 pub struct UsbManager {
     _system: System,
     transfers: Arc<Mutex<HashMap<String, TransferProgress>>>, // Key: episode_id_device_id
+    events: EventEmitter,
 }
 
 impl Default for UsbManager {
@@ -66,11 +255,20 @@ impl UsbManager {
         Self {
             _system: System::new_all(),
             transfers: Arc::new(Mutex::new(HashMap::new())),
+            events: EventEmitter::new(),
         }
     }
 
+    /// Pushes `transfer-progress` events through the given app handle
+    pub fn with_app_handle(mut self, app_handle: AppHandle) -> Self {
+        self.events = EventEmitter::with_app_handle(app_handle);
+        self
+    }
+
     pub fn clone_manager(&self) -> Self {
-        Self::new()
+        let mut manager = Self::new();
+        manager.events = self.events.clone();
+        manager
     }
 
     /// User Story #8: See USB device storage capacity
@@ -85,30 +283,7 @@ impl UsbManager {
             // Filter for removable devices (USB drives)
             // We identify USB devices by checking if they're removable and not the main system disk
             if self.is_usb_device(disk) {
-                let device_name = disk.name().to_string_lossy().to_string();
-                let mount_point = disk.mount_point().to_string_lossy().to_string();
-
-                // Generate a unique ID based on name and mount point
-                let device_id = format!(
-                    "{}_{}",
-                    device_name.replace([' ', '/'], "_"),
-                    mount_point.replace(['/', '\\'], "_")
-                );
-
-                let usb_device = UsbDevice {
-                    id: device_id,
-                    name: if device_name.is_empty() {
-                        "USB Device".to_string()
-                    } else {
-                        device_name
-                    },
-                    path: mount_point,
-                    total_space: disk.total_space(),
-                    available_space: disk.available_space(),
-                    is_connected: true,
-                };
-
-                usb_devices.push(usb_device);
+                usb_devices.push(usb_device_from_disk(disk));
             }
         }
 
@@ -116,6 +291,18 @@ impl UsbManager {
         Ok(usb_devices)
     }
 
+    /// Total and available space of the USB device mounted at `mount_point`,
+    /// from the system's disk list; the device itself isn't accessed
+    pub fn mounted_device_space(&self, mount_point: &str) -> Option<(u64, u64)> {
+        let disks = Disks::new_with_refreshed_list();
+        disks
+            .iter()
+            .find(|disk| {
+                disk.mount_point().to_string_lossy() == mount_point && self.is_usb_device(disk)
+            })
+            .map(|disk| (disk.total_space(), disk.available_space()))
+    }
+
     /// Helper function to determine if a disk is likely a USB device
     fn is_usb_device(&self, disk: &sysinfo::Disk) -> bool {
         let mount_point = disk.mount_point().to_string_lossy().to_lowercase();
@@ -160,25 +347,7 @@ impl UsbManager {
 
         for disk in &disks {
             if disk.mount_point().to_string_lossy() == device_path {
-                let device_name = disk.name().to_string_lossy().to_string();
-                let device_id = format!(
-                    "{}_{}",
-                    device_name.replace([' ', '/'], "_"),
-                    device_path.replace(['/', '\\'], "_")
-                );
-
-                return Ok(UsbDevice {
-                    id: device_id,
-                    name: if device_name.is_empty() {
-                        "USB Device".to_string()
-                    } else {
-                        device_name
-                    },
-                    path: device_path.to_string(),
-                    total_space: disk.total_space(),
-                    available_space: disk.available_space(),
-                    is_connected: true,
-                });
+                return Ok(usb_device_from_disk(disk));
             }
         }
 
@@ -192,10 +361,31 @@ impl UsbManager {
         source_path: &str,
         device_path: &str,
         filename: &str,
+    ) -> Result<(), PodPicoError> {
+        self.transfer_episode_file(
+            0,
+            device_path,
+            source_path,
+            device_path,
+            &format!("PodPico/{}", filename),
+        )
+        .await
+    }
+
+    /// Transfers an episode file to `relative_path` below the device root,
+    /// creating its folders and tagging progress events with the episode and
+    /// device they belong to
+    pub async fn transfer_episode_file(
+        &self,
+        episode_id: i64,
+        device_id: &str,
+        source_path: &str,
+        device_path: &str,
+        relative_path: &str,
     ) -> Result<(), PodPicoError> {
         log::info!(
             "Transferring file {} to device {} (User Story #9)",
-            filename,
+            relative_path,
             device_path
         );
 
@@ -205,8 +395,8 @@ impl UsbManager {
         transfers.insert(
             transfer_key.clone(),
             TransferProgress {
-                episode_id: 0, // Will be set by caller
-                device_id: device_path.to_string(),
+                episode_id,
+                device_id: device_id.to_string(),
                 total_bytes: 0,
                 transferred_bytes: 0,
                 percentage: 0.0,
@@ -264,13 +454,13 @@ impl UsbManager {
             }
         }
 
-        // Create podcast directory on USB device for organization
-        let usb_podcast_dir = device_path_buf.join("PodPico");
-        fs::create_dir_all(&usb_podcast_dir).await.map_err(|e| {
-            PodPicoError::FileTransferFailed(format!("Cannot create directory on USB: {}", e))
-        })?;
-
-        let destination_path = usb_podcast_dir.join(filename);
+        // Create the episode's directories on the USB device
+        let destination_path = device_path_buf.join(relative_path);
+        if let Some(parent) = destination_path.parent() {
+            fs::create_dir_all(parent).await.map_err(|e| {
+                PodPicoError::FileTransferFailed(format!("Cannot create directory on USB: {}", e))
+            })?;
+        }
 
         // Update progress to InProgress
         self.update_transfer_progress(&transfer_key, file_size, 0, TransferStatus::InProgress)
@@ -290,13 +480,13 @@ impl UsbManager {
             Ok(_) => {
                 self.update_transfer_status(&transfer_key, TransferStatus::Completed)
                     .await;
-                log::info!("Successfully transferred {} to USB device", filename);
+                log::info!("Successfully transferred {} to USB device", relative_path);
                 Ok(())
             }
             Err(e) => {
                 self.update_transfer_status(&transfer_key, TransferStatus::Failed(e.to_string()))
                     .await;
-                log::error!("Failed to transfer {}: {}", filename, e);
+                log::error!("Failed to transfer {}: {}", relative_path, e);
                 Err(e)
             }
         }
@@ -379,6 +569,12 @@ impl UsbManager {
         let mut transfers = self.transfers.lock().await;
         if let Some(progress) = transfers.get_mut(transfer_key) {
             progress.status = status;
+            // Status changes are always pushed, only in-flight progress is throttled
+            self.events.emit(
+                TRANSFER_PROGRESS_EVENT,
+                transfer_key,
+                TransferProgressEvent::from(&*progress),
+            );
         }
     }
 
@@ -429,6 +625,11 @@ impl UsbManager {
             progress.speed_bytes_per_sec = speed_bytes_per_sec;
             progress.eta_seconds = eta_seconds;
             progress.status = status;
+            self.events.emit_throttled(
+                TRANSFER_PROGRESS_EVENT,
+                transfer_key,
+                TransferProgressEvent::from(&*progress),
+            );
         }
     }
 
@@ -438,12 +639,32 @@ impl UsbManager {
         transfers.get(transfer_key).cloned()
     }
 
+    /// Path, relative to the device root, of the episode file stored under
+    /// `relative_stem`, whatever its extension
+    pub fn find_episode_file(&self, device_path: &str, relative_stem: &str) -> Option<String> {
+        let device_dir = PathBuf::from(device_path);
+        AUDIO_FILE_EXTENSIONS
+            .iter()
+            .map(|extension| format!("{}.{}", relative_stem, extension))
+            .find(|path| device_dir.join(path).is_file())
+    }
+
     /// User Story #10: Remove episodes from USB device
-    /// Removes a file from the USB device with proper error handling
+    /// Removes a file from the PodPico folder of the USB device with proper error handling
     pub async fn remove_file(&self, device_path: &str, filename: &str) -> Result<(), PodPicoError> {
+        self.remove_device_file(device_path, &format!("PodPico/{}", filename))
+            .await
+    }
+
+    /// Removes the file at `relative_path` below the device root
+    pub async fn remove_device_file(
+        &self,
+        device_path: &str,
+        relative_path: &str,
+    ) -> Result<(), PodPicoError> {
         log::info!(
             "Removing file {} from device {} (User Story #10)",
-            filename,
+            relative_path,
             device_path
         );
 
@@ -459,9 +680,10 @@ impl UsbManager {
             return Err(PodPicoError::UsbDeviceNotFound(device_path.to_string()));
         }
 
-        // Step 2: Construct full file path in PodPico directory structure
-        let file_path = format!("{}/PodPico/{}", device_path, filename);
+        // Step 2: Construct full file path below the device root
+        let file_path = format!("{}/{}", device_path, relative_path);
         let file_path_buf = PathBuf::from(&file_path);
+        let filename = relative_path.rsplit('/').next().unwrap_or(relative_path);
 
         // Step 3: Check if file exists before attempting removal
         if !file_path_buf.exists() {
@@ -525,10 +747,8 @@ impl UsbManager {
         let mut files_found = 0;
         if let Ok(entries) = std::fs::read_dir(&podpico_dir) {
             for entry in entries.flatten() {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == "mp3" || extension == "m4a" || extension == "wav" {
-                        files_found += 1;
-                    }
+                if is_audio_file(&entry.path()) {
+                    files_found += 1;
                 }
             }
         }
@@ -563,38 +783,34 @@ impl UsbManager {
 
         if let Ok(entries) = std::fs::read_dir(&podpico_dir) {
             for entry in entries.flatten() {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == "mp3" || extension == "m4a" || extension == "wav" {
-                        let filename = entry.file_name().to_string_lossy().to_string();
-
-                        // Extract podcast name from filename (basic implementation)
-                        let podcast_name = if let Some(first_underscore) = filename.find('_') {
-                            filename[..first_underscore].to_string()
-                        } else {
-                            "Unknown Podcast".to_string()
-                        };
-
-                        let metadata = entry.metadata().unwrap_or_else(|_| {
-                            // Create a default metadata-like structure
-                            std::fs::metadata(entry.path()).unwrap_or_else(|_| {
-                                std::fs::metadata(".").unwrap() // Fallback to current directory metadata
-                            })
-                        });
-
-                        let episode_info = DeviceEpisodeInfo {
-                            filename: filename.clone(),
-                            podcast_name: podcast_name.clone(),
-                            file_size: metadata.len(),
-                            last_modified: metadata
-                                .modified()
-                                .unwrap_or(std::time::SystemTime::now()),
-                        };
-
-                        episodes_by_podcast
-                            .entry(podcast_name)
-                            .or_default()
-                            .push(episode_info);
-                    }
+                if is_audio_file(&entry.path()) {
+                    let filename = entry.file_name().to_string_lossy().to_string();
+
+                    // Extract podcast name from filename (basic implementation)
+                    let podcast_name = if let Some(first_underscore) = filename.find('_') {
+                        filename[..first_underscore].to_string()
+                    } else {
+                        "Unknown Podcast".to_string()
+                    };
+
+                    let metadata = entry.metadata().unwrap_or_else(|_| {
+                        // Create a default metadata-like structure
+                        std::fs::metadata(entry.path()).unwrap_or_else(|_| {
+                            std::fs::metadata(".").unwrap() // Fallback to current directory metadata
+                        })
+                    });
+
+                    let episode_info = DeviceEpisodeInfo {
+                        filename: filename.clone(),
+                        podcast_name: podcast_name.clone(),
+                        file_size: metadata.len(),
+                        last_modified: metadata.modified().unwrap_or(std::time::SystemTime::now()),
+                    };
+
+                    episodes_by_podcast
+                        .entry(podcast_name)
+                        .or_default()
+                        .push(episode_info);
                 }
             }
         }
@@ -660,11 +876,9 @@ impl UsbManager {
 
         if let Ok(entries) = std::fs::read_dir(&podpico_dir) {
             for entry in entries.flatten() {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == "mp3" || extension == "m4a" || extension == "wav" {
-                        let filename = entry.file_name().to_string_lossy().to_string();
-                        status_indicators.insert(filename, true); // File exists on device
-                    }
+                if is_audio_file(&entry.path()) {
+                    let filename = entry.file_name().to_string_lossy().to_string();
+                    status_indicators.insert(filename, true); // File exists on device
                 }
             }
         }
@@ -792,6 +1006,53 @@ mod tests {
         // Test passes if detect_devices() succeeds regardless of device count
     }
 
+    #[test]
+    fn test_mount_source_unescapes_paths() {
+        let mounts = "\
+sysfs /sys sysfs rw,nosuid 0 0
+/dev/sda2 / ext4 rw,relatime 0 0
+/dev/sdb1 /media/user/MY\\040STICK vfat rw,nosuid,nodev 0 0
+";
+        assert_eq!(
+            mount_source(mounts, "/media/user/MY STICK").as_deref(),
+            Some("/dev/sdb1")
+        );
+        assert_eq!(mount_source(mounts, "/sys"), None);
+        assert_eq!(unescape_mount_field("PODCASTS\\x20USB"), "PODCASTS USB");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_disk_link_resolves_symlinks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let device = temp_dir.path().join("sdb1");
+        std::fs::write(&device, b"").unwrap();
+        let by_uuid = temp_dir.path().join("by-uuid");
+        std::fs::create_dir(&by_uuid).unwrap();
+        std::os::unix::fs::symlink("../sdb1", by_uuid.join("1A2B-3C4D")).unwrap();
+        std::os::unix::fs::symlink("../sdc1", by_uuid.join("5E6F-7A8B")).unwrap();
+
+        assert_eq!(
+            find_disk_link(&by_uuid, &device).as_deref(),
+            Some("1A2B-3C4D")
+        );
+        assert_eq!(
+            find_disk_link(&by_uuid, &temp_dir.path().join("sdc1")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_marker_id_survives_remounting() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let id = read_or_create_marker(temp_dir.path()).unwrap();
+        assert_eq!(read_or_create_marker(temp_dir.path()), Some(id.clone()));
+
+        let identity = device_identity("sdb1", temp_dir.path());
+        assert_eq!(identity.id, id);
+        assert_eq!(identity.filesystem_uuid, None);
+    }
+
     #[tokio::test]
     async fn test_get_device_info_nonexistent() {
         let usb_manager = UsbManager::new();
@@ -924,6 +1185,64 @@ mod tests {
         std::fs::remove_dir_all(test_device).unwrap_or(());
     }
 
+    #[tokio::test]
+    async fn test_transfer_episode_file_tags_progress_with_episode_and_device() {
+        let usb_manager = UsbManager::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("episode.mp3");
+        let device = temp_dir.path().join("device");
+        std::fs::write(&source, b"tagged transfer content").unwrap();
+        std::fs::create_dir_all(&device).unwrap();
+
+        let source = source.to_string_lossy().to_string();
+        let device = device.to_string_lossy().to_string();
+        usb_manager
+            .transfer_episode_file(
+                42,
+                "usb_stick",
+                &source,
+                &device,
+                "Podcasts/Show/episode.mp3",
+            )
+            .await
+            .unwrap();
+        assert!(std::path::Path::new(&device)
+            .join("Podcasts/Show/episode.mp3")
+            .is_file());
+
+        let progress = usb_manager
+            .get_transfer_progress(&format!("{}_{}", source, device))
+            .await
+            .expect("Transfer progress should be tracked");
+        assert_eq!(progress.episode_id, 42);
+        assert_eq!(progress.device_id, "usb_stick");
+        assert_eq!(progress.status, TransferStatus::Completed);
+
+        let event = TransferProgressEvent::from(&progress);
+        assert_eq!(event.status, "completed");
+        assert_eq!(event.percentage, 100.0);
+        assert!(event.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_progress_event_reports_failure_reason() {
+        let progress = TransferProgress {
+            episode_id: 7,
+            device_id: "usb_stick".to_string(),
+            total_bytes: 100,
+            transferred_bytes: 50,
+            percentage: 50.0,
+            speed_bytes_per_sec: 10.0,
+            eta_seconds: None,
+            status: TransferStatus::Failed("Write error".to_string()),
+        };
+
+        let event = TransferProgressEvent::from(&progress);
+        assert_eq!(event.status, "failed");
+        assert_eq!(event.error, Some("Write error".to_string()));
+        assert_eq!(event.eta_seconds, 0);
+    }
+
     #[tokio::test]
     async fn test_user_story_9_transfer_speed_and_time_remaining() {
         // User Story #9 Acceptance Criteria: Transfer speed and time remaining visible
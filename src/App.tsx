@@ -1,5 +1,6 @@
-import React, { useState, useEffect, useCallback } from 'react'
+import React, { useState, useEffect, useCallback, useRef } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import './App.css'
 import SafeHtmlRenderer from './SafeHtmlRenderer'
 
@@ -41,6 +42,14 @@ interface DownloadProgress {
   eta_seconds: number
 }
 
+// User Story #3: Payload of the `download-finished` event
+interface DownloadFinished {
+  episode_id: number
+  success: boolean
+  file_path: string | null
+  error: string | null
+}
+
 // User Story #8: USB Device Management - Interface for USB devices
 interface UsbDevice {
   id: string
@@ -112,8 +121,56 @@ function App() {
     loadUsbDevices()
   }, [])
 
+  // User Story #3: Download progress and completion are pushed as events
+  const selectedPodcastRef = useRef<Podcast | null>(null)
+  useEffect(() => {
+    const unlistenProgress = listen<DownloadProgress>(
+      'download-progress',
+      event => {
+        const progress = event.payload
+        setDownloadProgress(prev =>
+          new Map(prev).set(progress.episode_id, progress)
+        )
+      }
+    )
+
+    const unlistenFinished = listen<DownloadFinished>(
+      'download-finished',
+      event => {
+        const { episode_id: episodeId, success, error } = event.payload
+
+        setDownloadingEpisodes(prev => {
+          const newSet = new Set(prev)
+          newSet.delete(episodeId)
+          return newSet
+        })
+        setDownloadProgress(prev => {
+          const newMap = new Map(prev)
+          newMap.delete(episodeId)
+          return newMap
+        })
+        if (!success) {
+          setDownloadErrors(prev =>
+            new Map(prev).set(episodeId, `Download failed: ${error}`)
+          )
+        }
+
+        // Only refresh episodes data, don't change the selected episode/podcast
+        loadEpisodes(selectedPodcastRef.current?.id ?? null)
+        // Refresh podcasts for episode count updates
+        loadPodcasts()
+      }
+    )
+
+    return () => {
+      unlistenProgress.then(unlisten => unlisten())
+      unlistenFinished.then(unlisten => unlisten())
+    }
+  }, [])
+
   // Load episodes when podcast selection changes
   useEffect(() => {
+    selectedPodcastRef.current = selectedPodcast
     if (selectedPodcast) {
       loadEpisodes(selectedPodcast.id)
     } else {
@@ -437,10 +494,7 @@ function App() {
         episodeId: episode.id,
       })
       console.log('DEBUG: invoke download_episode completed successfully')
-
-      console.log('DEBUG: Starting progress tracking')
-      // Start progress tracking
-      startProgressTracking(episode.id)
+      // Progress and completion arrive as download events
     } catch (err) {
       // eslint-disable-next-line no-console
       console.error('DEBUG: Failed to start download:', err)
@@ -455,145 +509,6 @@ function App() {
     }
   }
 
-  // User Story #3: Progress tracking for download episodes
-  // Acceptance Criteria: Progress indicators with percentage display
-  function startProgressTracking(episodeId: number) {
-    console.log('DEBUG: Starting progress tracking for episode:', episodeId)
-
-    // Start checking progress immediately (not after 1 second delay)
-    let progressInterval: number
-
-    const checkProgress = async () => {
-      try {
-        console.log('DEBUG: Requesting progress for episode:', episodeId)
-        const progress: DownloadProgress = await invoke(
-          'get_download_progress',
-          {
-            episodeId: episodeId,
-          }
-        )
-
-        console.log('DEBUG: Received progress data:', progress)
-
-        // Validate progress data before setting state
-        if (progress && typeof progress.percentage === 'number') {
-          console.log(
-            'DEBUG: Setting progress state for episode:',
-            episodeId,
-            'progress:',
-            progress.percentage
-          )
-          setDownloadProgress(prev => new Map(prev).set(episodeId, progress))
-
-          // Check if download is complete (100% or episode is now downloaded)
-          if (progress.percentage >= 100) {
-            console.log('DEBUG: Download completed for episode:', episodeId)
-            clearInterval(progressInterval)
-
-            // Remove from downloading set
-            setDownloadingEpisodes(prev => {
-              const newSet = new Set(prev)
-              newSet.delete(episodeId)
-              return newSet
-            })
-
-            // Clear progress tracking
-            setDownloadProgress(prev => {
-              const newMap = new Map(prev)
-              newMap.delete(episodeId)
-              return newMap
-            })
-
-            // Only refresh episodes data, don't change the selected episode/podcast
-            // This prevents the view from changing focus
-            console.log(
-              'DEBUG: Refreshing episodes data without changing selection'
-            )
-            if (selectedPodcast) {
-              await loadEpisodes(selectedPodcast.id)
-            } else {
-              await loadEpisodes(null)
-            }
-
-            // Only refresh podcasts for episode count updates
-            await loadPodcasts()
-          }
-        } else {
-          // Invalid or no progress data - this is normal for very fast downloads
-          console.log(
-            'DEBUG: No progress data available yet for episode:',
-            episodeId
-          )
-
-          // For fast downloads, check if the episode is already downloaded
-          // This can happen when the download completes before progress tracking starts
-          if (selectedPodcast) {
-            await loadEpisodes(selectedPodcast.id)
-          } else {
-            await loadEpisodes(null)
-          }
-
-          // Check if this episode is now marked as downloaded
-          const episodes = (await invoke('get_episodes', {
-            podcastId: selectedPodcast?.id || null,
-          })) as Episode[]
-
-          const updatedEpisode = episodes.find(e => e.id === episodeId)
-          if (updatedEpisode?.downloaded) {
-            console.log(
-              'DEBUG: Episode completed during fast download, stopping progress tracking'
-            )
-            clearInterval(progressInterval)
-
-            // Remove from downloading set
-            setDownloadingEpisodes(prev => {
-              const newSet = new Set(prev)
-              newSet.delete(episodeId)
-              return newSet
-            })
-
-            // Clear progress tracking
-            setDownloadProgress(prev => {
-              const newMap = new Map(prev)
-              newMap.delete(episodeId)
-              return newMap
-            })
-
-            // Refresh podcasts for episode count updates
-            await loadPodcasts()
-            return
-          }
-        }
-      } catch (err) {
-        // eslint-disable-next-line no-console
-        console.error('DEBUG: Progress tracking error:', err)
-        clearInterval(progressInterval)
-
-        setDownloadErrors(prev =>
-          new Map(prev).set(episodeId, `Progress tracking failed: ${err}`)
-        )
-
-        setDownloadingEpisodes(prev => {
-          const newSet = new Set(prev)
-          newSet.delete(episodeId)
-          return newSet
-        })
-
-        setDownloadProgress(prev => {
-          const newMap = new Map(prev)
-          newMap.delete(episodeId)
-          return newMap
-        })
-      }
-    }
-
-    // Check progress immediately
-    checkProgress()
-
-    // Then set up interval to check every 500ms (faster than before for better responsiveness)
-    progressInterval = setInterval(checkProgress, 500)
-  }
-
   // User Story #3: Format file size for display
   function formatFileSize(bytes: number): string {
     if (bytes === 0) return '0 Bytes'
@@ -1,7 +1,12 @@
 import { render, screen, fireEvent, waitFor } from '@testing-library/react'
 import { describe, it, expect, beforeEach } from 'vitest'
 import App from '../App'
-import { mockInvoke, MOCK_PODCAST, MOCK_EPISODE } from '../setupTests'
+import {
+  mockInvoke,
+  emitTauriEvent,
+  MOCK_PODCAST,
+  MOCK_EPISODE,
+} from '../setupTests'
 
 describe('App Component', () => {
   beforeEach(() => {
@@ -605,7 +610,6 @@ describe('App Component', () => {
         .mockResolvedValueOnce([]) // get_episodes (Combined Inbox)
         .mockResolvedValueOnce(mockEpisodes) // get_episodes (for selected podcast)
         .mockResolvedValueOnce(undefined) // download_episode
-        .mockResolvedValue(mockProgress) // get_download_progress (repeated calls)
 
       render(<App />)
 
@@ -627,6 +631,9 @@ describe('App Component', () => {
         expect(screen.getByText('📥 Downloading...')).toBeInTheDocument()
       })
 
+      // The backend pushes progress as a download-progress event
+      emitTauriEvent('download-progress', mockProgress)
+
       // Then wait for progress display with formatted values (longer timeout for async operations)
       await waitFor(
         () => {
@@ -749,7 +756,6 @@ describe('App Component', () => {
         .mockResolvedValueOnce([]) // get_usb_devices
         .mockResolvedValueOnce(mockEpisodes) // get_episodes
         .mockResolvedValueOnce(undefined) // download_episode
-        .mockResolvedValue(mockProgress) // get_download_progress (repeated calls)
 
       render(<App />)
 
@@ -774,7 +780,10 @@ describe('App Component', () => {
         expect(screen.getByText('📥 Downloading...')).toBeInTheDocument()
       })
 
-      // Wait for progress percentage to appear (after progress interval runs)
+      // The backend pushes progress as a download-progress event
+      emitTauriEvent('download-progress', mockProgress)
+
+      // Wait for progress percentage to appear
       await waitFor(
         () => {
           expect(screen.getByText('50.0%')).toBeInTheDocument()
@@ -783,6 +792,59 @@ describe('App Component', () => {
       )
     })
 
+    it('clears the downloading state when the download finishes', async () => {
+      const mockEpisodes = [{ ...MOCK_EPISODE, downloaded: false }]
+      const downloadedEpisodes = [
+        {
+          ...MOCK_EPISODE,
+          downloaded: true,
+          local_file_path: '/path/to/episode.mp3',
+        },
+      ]
+
+      mockInvoke
+        .mockResolvedValueOnce([MOCK_PODCAST]) // get_podcasts
+        .mockResolvedValueOnce([]) // get_usb_devices
+        .mockResolvedValueOnce(mockEpisodes) // get_episodes
+        .mockResolvedValueOnce(undefined) // download_episode
+        .mockResolvedValueOnce(downloadedEpisodes) // get_episodes (refresh)
+        .mockResolvedValueOnce([MOCK_PODCAST]) // get_podcasts (refresh)
+
+      render(<App />)
+
+      await waitFor(() => {
+        expect(screen.getByText('Test Episode')).toBeInTheDocument()
+      })
+
+      fireEvent.click(screen.getByText('Test Episode'))
+
+      await waitFor(() => {
+        const downloadButton = screen.getByText('📥 Download Episode')
+        fireEvent.click(downloadButton)
+      })
+
+      await waitFor(() => {
+        expect(screen.getByText('📥 Downloading...')).toBeInTheDocument()
+      })
+
+      emitTauriEvent('download-finished', {
+        episode_id: 1,
+        success: true,
+        file_path: '/path/to/episode.mp3',
+        error: null,
+      })
+
+      await waitFor(() => {
+        expect(
+          screen.queryByText('📥 Downloading...')
+        ).not.toBeInTheDocument()
+      })
+      expect(mockInvoke).not.toHaveBeenCalledWith(
+        'get_download_progress',
+        expect.anything()
+      )
+    })
+
     it('shows download indicators in episode list', async () => {
       const mockEpisodes = [
         {
@@ -861,14 +923,6 @@ describe('App Component', () => {
         .mockResolvedValueOnce([]) // get_usb_devices
         .mockResolvedValueOnce(mockEpisodes) // get_episodes
         .mockResolvedValueOnce(undefined) // first download_episode
-        .mockResolvedValueOnce({
-          episode_id: 1,
-          downloaded_bytes: 0,
-          total_bytes: 1000000,
-          percentage: 0,
-          speed_bps: 0,
-          eta_seconds: 100,
-        }) // get_download_progress
 
       render(<App />)
 
@@ -894,7 +948,7 @@ describe('App Component', () => {
       expect(mockInvoke).toHaveBeenCalledWith('download_episode', {
         episodeId: 1,
       })
-      // Note: Progress tracking happens asynchronously, so we can't guarantee exact call count
+      // Progress arrives as events, so no further commands are invoked
       expect(mockInvoke).toHaveBeenCalledTimes(4) // get_podcasts, get_usb_devices, get_episodes, download_episode
     })
 
@@ -914,7 +968,6 @@ describe('App Component', () => {
         .mockResolvedValueOnce([]) // get_usb_devices
         .mockResolvedValueOnce(mockEpisodes) // get_episodes
         .mockResolvedValueOnce(undefined) // download_episode
-        .mockResolvedValue(mockProgress) // get_download_progress (repeated calls)
 
       render(<App />)
 
@@ -936,6 +989,9 @@ describe('App Component', () => {
         expect(screen.getByText('📥 Downloading...')).toBeInTheDocument()
       })
 
+      // The backend pushes progress as a download-progress event
+      emitTauriEvent('download-progress', mockProgress)
+
       // Then wait for progress display with formatted values (longer timeout for async operations)
       await waitFor(
         () => {
@@ -8,6 +8,7 @@ vi.mock('@tauri-apps/api/core', () => ({
 }))
 
 import { invoke } from '@tauri-apps/api/core'
+import { emitTauriEvent, mockListen } from '../setupTests'
 const mockInvoke = vi.mocked(invoke)
 
 // Test data that simulates real backend responses
@@ -102,17 +103,6 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
           return Promise.reject(new Error('Episode not found'))
         }
 
-        case 'get_download_progress':
-          // Simulate realistic download progress
-          return Promise.resolve({
-            episode_id: args?.episodeId,
-            bytes_downloaded: 12500000,
-            total_bytes: 25000000,
-            percentage: 50.0,
-            download_speed: 1024000,
-            eta_seconds: 12,
-          })
-
         default:
           return Promise.reject(new Error(`Unhandled command: ${command}`))
       }
@@ -187,16 +177,35 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
       })
       console.log('✅ Downloading state displayed')
 
-      // Step 7: Wait for progress tracking to begin
-      await waitFor(
-        () => {
-          expect(mockInvoke).toHaveBeenCalledWith('get_download_progress', {
-            episodeId: 1,
-          })
-        },
-        { timeout: 3000 }
-      )
-      console.log('✅ Progress tracking initiated')
+      // Step 7: Backend pushes progress events while downloading
+      emitTauriEvent('download-progress', {
+        episode_id: 1,
+        downloaded_bytes: 12500000,
+        total_bytes: 25000000,
+        percentage: 50.0,
+        speed_bps: 1024000,
+        eta_seconds: 12,
+      })
+
+      await waitFor(() => {
+        expect(screen.getByText('50.0%')).toBeInTheDocument()
+      })
+      console.log('✅ Progress event displayed')
+
+      // Step 8: Backend reports the finished download
+      emitTauriEvent('download-finished', {
+        episode_id: 1,
+        success: true,
+        file_path: '/tmp/podpico/episode1.mp3',
+        error: null,
+      })
+
+      await waitFor(() => {
+        expect(
+          screen.queryByText('📥 Downloading...')
+        ).not.toBeInTheDocument()
+      })
+      console.log('✅ Finished event cleared downloading state')
 
       console.log('🎉 Complete download workflow E2E integration test PASSED!')
     })
@@ -252,8 +261,6 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
     it('should track download progress with real-time updates', async () => {
       console.log('🚀 Testing download progress tracking integration...')
 
-      // Mock progressive download updates
-      let progressCallCount = 0
       mockInvoke.mockImplementation((command: string, args?: any) => {
         switch (command) {
           case 'get_podcasts':
@@ -267,17 +274,6 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
             )
           case 'download_episode':
             return Promise.resolve()
-          case 'get_download_progress':
-            progressCallCount++
-            const progress = Math.min(progressCallCount * 25, 100)
-            return Promise.resolve({
-              episode_id: args?.episodeId,
-              bytes_downloaded: progress * 250000,
-              total_bytes: 25000000,
-              percentage: progress,
-              download_speed: 1000000 + progressCallCount * 50000,
-              eta_seconds: Math.max(20 - progressCallCount * 4, 0),
-            })
           default:
             return Promise.reject(new Error(`Unhandled command: ${command}`))
         }
@@ -307,18 +303,34 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
         expect(screen.getByText('📥 Downloading...')).toBeInTheDocument()
       })
 
-      // Wait for progress updates
-      await new Promise(resolve => setTimeout(resolve, 2500))
+      expect(mockListen).toHaveBeenCalledWith(
+        'download-progress',
+        expect.any(Function)
+      )
 
-      // Verify progress tracking was called multiple times
-      expect(mockInvoke).toHaveBeenCalledWith('get_download_progress', {
-        episodeId: 1,
-      })
-      expect(progressCallCount).toBeGreaterThan(1)
+      // Each progress event updates the display
+      for (const percentage of [25, 50, 75]) {
+        emitTauriEvent('download-progress', {
+          episode_id: 1,
+          downloaded_bytes: percentage * 250000,
+          total_bytes: 25000000,
+          percentage,
+          speed_bps: 1000000,
+          eta_seconds: (100 - percentage) / 4,
+        })
+
+        await waitFor(() => {
+          expect(screen.getByText(`${percentage}.0%`)).toBeInTheDocument()
+        })
+      }
 
-      console.log(
-        `✅ Progress tracking integration verified (${progressCallCount} progress updates)`
+      // Progress is pushed, never polled
+      expect(mockInvoke).not.toHaveBeenCalledWith(
+        'get_download_progress',
+        expect.anything()
       )
+
+      console.log('✅ Progress tracking integration verified')
     })
 
     it('should display formatted progress information', async () => {
@@ -338,15 +350,6 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
             )
           case 'download_episode':
             return Promise.resolve()
-          case 'get_download_progress':
-            return Promise.resolve({
-              episode_id: args?.episodeId,
-              bytes_downloaded: 15728640, // 15 MB
-              total_bytes: 26214400, // 25 MB
-              percentage: 60.0,
-              download_speed: 1048576, // 1 MB/s
-              eta_seconds: 10,
-            })
           default:
             return Promise.reject(new Error(`Unhandled command: ${command}`))
         }
@@ -376,8 +379,15 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
         expect(screen.getByText('📥 Downloading...')).toBeInTheDocument()
       })
 
-      // Wait for progress tracking interval to fire and display progress information
-      await new Promise(resolve => setTimeout(resolve, 1500))
+      // Backend pushes a progress event
+      emitTauriEvent('download-progress', {
+        episode_id: 1,
+        downloaded_bytes: 15728640, // 15 MB
+        total_bytes: 26214400, // 25 MB
+        percentage: 60.0,
+        speed_bps: 1048576, // 1 MB/s
+        eta_seconds: 10,
+      })
 
       // Verify inline progress appears in episode list (rounded percentage)
       await waitFor(() => {
@@ -543,8 +553,9 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
       const downloadButton = screen.getByText('📥 Download Episode')
       fireEvent.click(downloadButton)
 
-      // Wait for progress tracking to begin
-      await new Promise(resolve => setTimeout(resolve, 1500))
+      await waitFor(() => {
+        expect(screen.getByText('📥 Downloading...')).toBeInTheDocument()
+      })
 
       // Analyze all backend calls made
       const allCalls = mockInvoke.mock.calls
@@ -555,17 +566,22 @@ describe('User Story #3: Download Episodes - End-to-End Integration Tests', () =
       expect(calledCommands).toContain('get_podcasts')
       expect(calledCommands).toContain('get_episodes')
       expect(calledCommands).toContain('download_episode')
-      expect(calledCommands).toContain('get_download_progress')
+      expect(calledCommands).not.toContain('get_download_progress')
+
+      // Progress and completion are subscribed to as events
+      expect(mockListen).toHaveBeenCalledWith(
+        'download-progress',
+        expect.any(Function)
+      )
+      expect(mockListen).toHaveBeenCalledWith(
+        'download-finished',
+        expect.any(Function)
+      )
 
       // Verify command arguments are correct
       const downloadCall = allCalls.find(call => call[0] === 'download_episode')
       expect(downloadCall?.[1]).toEqual({ episodeId: 1 })
 
-      const progressCall = allCalls.find(
-        call => call[0] === 'get_download_progress'
-      )
-      expect(progressCall?.[1]).toEqual({ episodeId: 1 })
-
       console.log(
         `✅ Backend integration coverage verified: ${uniqueCommands.join(', ')}`
       )
@@ -1,4 +1,5 @@
 import '@testing-library/jest-dom'
+import { act } from '@testing-library/react'
 
 // Mock Tauri API for testing
 const mockInvoke = vi.fn()
@@ -8,8 +9,39 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: mockInvoke,
 }))
 
-// Export mock for tests to use
-export { mockInvoke }
+// Mock the Tauri event API; handlers registered through listen() are kept so
+// tests can push backend events with emitTauriEvent()
+type EventHandler = (event: {
+  event: string
+  id: number
+  payload: unknown
+}) => void
+const eventHandlers = new Map<string, Set<EventHandler>>()
+const mockListen = vi.fn((event: string, handler: EventHandler) => {
+  if (!eventHandlers.has(event)) {
+    eventHandlers.set(event, new Set())
+  }
+  eventHandlers.get(event)!.add(handler)
+  return Promise.resolve(() => {
+    eventHandlers.get(event)?.delete(handler)
+  })
+})
+
+vi.mock('@tauri-apps/api/event', () => ({
+  listen: mockListen,
+}))
+
+// Delivers a backend event to every listener registered for it
+export function emitTauriEvent(event: string, payload: unknown) {
+  act(() => {
+    eventHandlers.get(event)?.forEach(handler =>
+      handler({ event, id: 0, payload })
+    )
+  })
+}
+
+// Export mocks for tests to use
+export { mockInvoke, mockListen }
 
 // Mock podcast data for testing
 export const MOCK_PODCAST = {
@@ -53,4 +85,6 @@ export const MOCK_USB_DEVICE = {
 // Reset mocks before each test
 beforeEach(() => {
   mockInvoke.mockReset()
+  mockListen.mockClear()
+  eventHandlers.clear()
 })